@@ -0,0 +1,148 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventKind, PublicKeyHex, Unixtime};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use tokio::task::spawn_blocking;
+
+/// Which events to export. Unset fields match everything.
+#[derive(Clone, Debug, Default)]
+pub struct EventExport {
+    pub author: Option<PublicKeyHex>,
+    pub kind: Option<EventKind>,
+    pub since: Option<Unixtime>,
+    pub until: Option<Unixtime>,
+}
+
+impl EventExport {
+    fn condition(&self) -> String {
+        let mut parts: Vec<String> = vec!["1".to_owned()];
+        if let Some(ref author) = self.author {
+            parts.push(format!("pubkey='{}'", author.as_str()));
+        }
+        if let Some(kind) = self.kind {
+            parts.push(format!("kind={}", <EventKind as Into<u64>>::into(kind)));
+        }
+        if let Some(since) = self.since {
+            parts.push(format!("created_at >= {}", since.0));
+        }
+        if let Some(until) = self.until {
+            parts.push(format!("created_at <= {}", until.0));
+        }
+        parts.join(" AND ")
+    }
+}
+
+/// How many events we read from the database while holding its lock
+const EXPORT_PAGE_SIZE: usize = 1000;
+
+/// How many lines of an import we read ahead of processing them
+const IMPORT_READ_AHEAD: usize = 1000;
+
+/// Write the matching events to a file, one JSON event per line, oldest first.
+/// Returns how many were written.
+pub async fn export(export: EventExport, path: PathBuf) -> Result<usize, Error> {
+    // Paged by (created_at, id), so other users of the database get their turn
+    // between pages
+    let sql = format!(
+        "SELECT created_at, id, raw FROM event WHERE {} \
+         AND (created_at > ?1 OR (created_at = ?1 AND id > ?2)) \
+         ORDER BY created_at ASC, id ASC LIMIT {}",
+        export.condition(),
+        EXPORT_PAGE_SIZE
+    );
+
+    let output: Result<usize, Error> = spawn_blocking(move || {
+        let mut writer = BufWriter::new(File::create(&path)?);
+
+        let mut count: usize = 0;
+        let mut after: (i64, String) = (i64::MIN, "".to_owned());
+        loop {
+            let page: Vec<(i64, String, String)> = {
+                let db = GLOBALS.db.blocking_lock();
+                let mut stmt = db.prepare(&sql)?;
+                let mut rows = stmt.query((after.0, &after.1))?;
+                let mut page: Vec<(i64, String, String)> = Vec::new();
+                while let Some(row) = rows.next()? {
+                    page.push((row.get(0)?, row.get(1)?, row.get(2)?));
+                }
+                page
+            };
+
+            for (_, _, raw) in page.iter() {
+                writer.write_all(raw.as_bytes())?;
+                writer.write_all(b"\n")?;
+                count += 1;
+            }
+
+            if page.len() < EXPORT_PAGE_SIZE {
+                break;
+            }
+            if let Some((created_at, id, _)) = page.pop() {
+                after = (created_at, id);
+            }
+        }
+        writer.flush()?;
+
+        Ok(count)
+    })
+    .await?;
+
+    output
+}
+
+/// Read events from a file of one JSON event per line, verify them, and process
+/// them as if they came from a relay. Returns how many were imported and how many
+/// were rejected for being malformed or not properly signed.
+pub async fn import(path: PathBuf) -> Result<(usize, usize), Error> {
+    // The file may be large, so it is read a line at a time as we go
+    let (sender, mut receiver) = tokio::sync::mpsc::channel::<String>(IMPORT_READ_AHEAD);
+    let reading = spawn_blocking(move || {
+        let reader = BufReader::new(File::open(&path)?);
+        for line in reader.lines() {
+            if sender.blocking_send(line?).is_err() {
+                break;
+            }
+        }
+        Ok::<(), Error>(())
+    });
+
+    let mut imported: usize = 0;
+    let mut rejected: usize = 0;
+    while let Some(line) = receiver.recv().await {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let event: Event = match serde_json::from_str(line) {
+            Ok(event) => event,
+            Err(e) => {
+                tracing::warn!("Skipping malformed event in import: {}", e);
+                rejected += 1;
+                continue;
+            }
+        };
+
+        if let Err(e) = event.verify(None) {
+            tracing::warn!(
+                "Skipping event {} in import: {}",
+                event.id.as_hex_string(),
+                e
+            );
+            rejected += 1;
+            continue;
+        }
+
+        if let Err(e) = crate::process::process_new_event(&event, true, None, None).await {
+            tracing::warn!("Failed to import event {}: {}", event.id.as_hex_string(), e);
+            rejected += 1;
+            continue;
+        }
+        imported += 1;
+    }
+    reading.await??;
+
+    Ok((imported, rejected))
+}
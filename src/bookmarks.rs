@@ -0,0 +1,228 @@
+use crate::db::{DbBookmark, DbEvent};
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventKind, Id, PreEvent, Tag, Unixtime};
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+use tokio::task;
+
+/// NIP-51 bookmark list, which is what we publish
+pub const BOOKMARK_LIST_KIND: u64 = 10003;
+
+/// The older NIP-51 form, a categorized list with a "bookmark" d tag. We read it
+/// from clients that still publish it.
+pub const CATEGORIZED_BOOKMARK_LIST_KIND: u64 = 30001;
+
+pub fn bookmark_list_kind() -> EventKind {
+    EventKind::from(BOOKMARK_LIST_KIND)
+}
+
+pub fn categorized_bookmark_list_kind() -> EventKind {
+    EventKind::from(CATEGORIZED_BOOKMARK_LIST_KIND)
+}
+
+/// Whether this event is somebody's bookmark list, in either form
+pub fn is_bookmark_list(event: &Event) -> bool {
+    event.kind == bookmark_list_kind()
+        || (event.kind == categorized_bookmark_list_kind()
+            && event.parameter().as_deref() == Some("bookmark"))
+}
+
+/// Our bookmarks, most recently bookmarked first
+pub struct Bookmarks {
+    ids: RwLock<Vec<Id>>,
+
+    /// When we last changed our bookmarks here
+    pub last_bookmark_list_edit: AtomicI64,
+}
+
+impl Default for Bookmarks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bookmarks {
+    pub fn new() -> Bookmarks {
+        Bookmarks {
+            ids: RwLock::new(Vec::new()),
+            last_bookmark_list_edit: AtomicI64::new(0),
+        }
+    }
+
+    /// Load bookmarks from the database (again, after switching identities)
+    pub async fn load(&self) -> Result<(), Error> {
+        self.load_ids().await?;
+
+        let last_edit = task::spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            db.query_row(
+                "SELECT last_bookmark_list_edit FROM local_settings LIMIT 1",
+                [],
+                |row| row.get::<usize, i64>(0),
+            )
+        })
+        .await??;
+        self.last_bookmark_list_edit
+            .store(last_edit, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    async fn load_ids(&self) -> Result<(), Error> {
+        let ids: Vec<Id> = DbBookmark::load_all()
+            .await?
+            .drain(..)
+            .map(|b| b.event)
+            .collect();
+        *self.ids.write() = ids;
+        Ok(())
+    }
+
+    /// Record when our bookmarks here last changed. Publishing our list counts,
+    /// as relays then hold exactly what we have.
+    pub async fn set_last_edit(&self, when: Unixtime) -> Result<(), Error> {
+        self.last_bookmark_list_edit
+            .store(when.0, Ordering::Relaxed);
+        task::spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            db.execute(
+                "UPDATE local_settings SET last_bookmark_list_edit=?",
+                (when.0,),
+            )?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+        Ok(())
+    }
+
+    pub fn get(&self) -> Vec<Id> {
+        self.ids.read().clone()
+    }
+
+    pub fn is_bookmarked(&self, id: &Id) -> bool {
+        self.ids.read().contains(id)
+    }
+
+    pub async fn add(&self, id: Id) -> Result<(), Error> {
+        if self.is_bookmarked(&id) {
+            return Ok(());
+        }
+        DbBookmark::insert(DbBookmark {
+            event: id,
+            bookmarked_at: Unixtime::now().unwrap().0,
+        })
+        .await?;
+        self.ids.write().insert(0, id);
+        self.set_last_edit(Unixtime::now().unwrap()).await
+    }
+
+    pub async fn remove(&self, id: Id) -> Result<(), Error> {
+        DbBookmark::delete(id).await?;
+        self.ids.write().retain(|i| *i != id);
+        self.set_last_edit(Unixtime::now().unwrap()).await
+    }
+
+    /// Take in a bookmark list we published from some client. If it is newer
+    /// than our last change here, our bookmarks become exactly that list, so
+    /// that removals made elsewhere reach us too. An older list is ignored, as
+    /// the list we publish next replaces it.
+    pub async fn take_list(&self, event: &Event) -> Result<(), Error> {
+        if event.created_at.0 <= self.last_bookmark_list_edit.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        // Bookmarks we already had keep their place
+        let bookmarked_at: HashMap<Id, i64> = DbBookmark::load_all()
+            .await?
+            .drain(..)
+            .map(|b| (b.event, b.bookmarked_at))
+            .collect();
+        let mut bookmarks: Vec<DbBookmark> = Vec::new();
+        for tag in event.tags.iter() {
+            if let Tag::Event { id, .. } = tag {
+                bookmarks.push(DbBookmark {
+                    event: *id,
+                    bookmarked_at: bookmarked_at.get(id).copied().unwrap_or(event.created_at.0),
+                });
+            }
+        }
+        DbBookmark::replace_all(bookmarks).await?;
+        self.load_ids().await?;
+        self.set_last_edit(event.created_at).await
+    }
+
+    /// Whether we changed our bookmarks here since we last published them
+    pub async fn is_unpublished(&self) -> Result<bool, Error> {
+        let last_edit = self.last_bookmark_list_edit.load(Ordering::Relaxed);
+        if last_edit == 0 {
+            return Ok(false);
+        }
+        let published = match last_list().await? {
+            Some(event) => event.created_at.0,
+            None => 0,
+        };
+        Ok(last_edit > published)
+    }
+
+    /// Sign a bookmark list of our current bookmarks. Whatever else our last
+    /// published list held (non-event tags, private encrypted content) is kept.
+    pub async fn generate_list_event(&self) -> Result<Event, Error> {
+        let public_key = match GLOBALS.signer.public_key() {
+            Some(pk) => pk,
+            None => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()), // not even a public key
+        };
+
+        let (mut tags, content) = match last_list().await? {
+            Some(last) => {
+                let tags: Vec<Tag> = last
+                    .tags
+                    .into_iter()
+                    .filter(|t| !matches!(t, Tag::Event { .. }))
+                    .collect();
+                (tags, last.content)
+            }
+            None => (Vec::new(), "".to_owned()),
+        };
+
+        // Oldest first, so clients that append show them in order
+        for id in self.get().iter().rev() {
+            tags.push(Tag::Event {
+                id: *id,
+                recommended_relay_url: None,
+                marker: None,
+            });
+        }
+
+        let pre_event = PreEvent {
+            pubkey: public_key,
+            created_at: Unixtime::now().unwrap(),
+            kind: bookmark_list_kind(),
+            tags,
+            content,
+            ots: None,
+        };
+
+        GLOBALS.signer.sign_preevent(pre_event, None, None).await
+    }
+}
+
+// The last bookmark list we published, from any client
+async fn last_list() -> Result<Option<Event>, Error> {
+    let public_key = match GLOBALS.signer.public_key() {
+        Some(pk) => pk,
+        None => return Ok(None),
+    };
+    let last = DbEvent::fetch(Some(&format!(
+        "pubkey='{}' AND kind={} ORDER BY created_at DESC LIMIT 1",
+        public_key.as_hex_string(),
+        BOOKMARK_LIST_KIND
+    )))
+    .await?
+    .pop();
+    match last {
+        Some(dbevent) => Ok(Some(serde_json::from_str(&dbevent.raw)?)),
+        None => Ok(None),
+    }
+}
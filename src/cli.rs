@@ -0,0 +1,340 @@
+use crate::archive::EventExport;
+use crate::comms::{PostingResponse, ToOverlordMessage};
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Id, RelayUrl};
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Instant;
+use zeroize::Zeroize;
+
+pub const USAGE: &str = "Usage: gossip [--headless] [COMMAND]
+
+With no command, gossip runs its user interface (or runs without one if
+--headless is given).
+
+Commands:
+  post <content>                   Post a text note
+  reply <id> <content>             Reply to an event (note1... or hex)
+  repost <id>                      Repost an event
+  delete <id>                      Delete one of your events
+  follow nip05 <user@domain>       Follow a person by their NIP-05 address
+  follow nprofile <nprofile1...>   Follow a person by their nprofile
+  follow pubkey <pubkey> <relay>   Follow a person at a relay
+  follow push                      Publish your contact list
+  relay add <url>                  Add a relay
+  relay rank <url> <0-9>           Set the rank of a relay
+  relay advertise                  Publish your relay list
+  prune                            Prune the database
+  export <file>                    Export all events as JSON lines
+  export mine <file>               Export the events you authored
+  import <file>                    Import events from JSON lines
+  help                             Show this message
+
+Commands that sign events prompt for your passphrase on stdin.";
+
+/// How long we wait for the first relay to answer a posting
+const FIRST_RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Once relays have started answering, how long we wait for more answers
+const FURTHER_RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A command given on the command line
+#[derive(Debug, Clone)]
+pub enum Command {
+    Help,
+    Post(String, Option<Id>),
+    Repost(Id),
+    Delete(Id),
+    FollowNip05(String),
+    FollowNprofile(String),
+    FollowPubkey(String, RelayUrl),
+    FollowPush,
+    RelayAdd(RelayUrl),
+    RelayRank(RelayUrl, u8),
+    RelayAdvertise,
+    Prune,
+    Export(PathBuf),
+    ExportMine(PathBuf),
+    Import(PathBuf),
+}
+
+impl Command {
+    /// Parse the command line arguments (without the program name).
+    /// Returns None if no command was given.
+    pub fn parse(args: &[String]) -> Result<Option<Command>, Error> {
+        let args: Vec<&str> = args
+            .iter()
+            .map(|s| s.as_str())
+            .filter(|s| *s != "--headless")
+            .collect();
+
+        let command = match args.as_slice() {
+            [] => return Ok(None),
+            ["help"] | ["--help"] | ["-h"] => Command::Help,
+            ["post", content] => Command::Post(content.to_string(), None),
+            ["reply", id, content] => Command::Post(content.to_string(), Some(parse_id(id)?)),
+            ["repost", id] => Command::Repost(parse_id(id)?),
+            ["delete", id] => Command::Delete(parse_id(id)?),
+            ["follow", "nip05", nip05] => Command::FollowNip05(nip05.to_string()),
+            ["follow", "nprofile", nprofile] => Command::FollowNprofile(nprofile.to_string()),
+            ["follow", "pubkey", pubkey, relay] => {
+                Command::FollowPubkey(pubkey.to_string(), parse_relay_url(relay)?)
+            }
+            ["follow", "push"] => Command::FollowPush,
+            ["relay", "add", relay] => Command::RelayAdd(parse_relay_url(relay)?),
+            ["relay", "rank", relay, rank] => {
+                let rank: u8 = rank.parse()?;
+                if rank > 9 {
+                    return Err("Relay rank must be between 0 and 9")?;
+                }
+                Command::RelayRank(parse_relay_url(relay)?, rank)
+            }
+            ["relay", "advertise"] => Command::RelayAdvertise,
+            ["prune"] => Command::Prune,
+            ["export", "mine", file] => Command::ExportMine(PathBuf::from(file)),
+            ["export", file] => Command::Export(PathBuf::from(file)),
+            ["import", file] => Command::Import(PathBuf::from(file)),
+            _ => return Err(format!("Unrecognized command: {}", args.join(" ")))?,
+        };
+
+        Ok(Some(command))
+    }
+
+    /// Whether this command signs events, and so needs the private key unlocked
+    pub fn needs_private_key(&self) -> bool {
+        matches!(
+            self,
+            Command::Post(..)
+                | Command::Repost(_)
+                | Command::Delete(_)
+                | Command::FollowPush
+                | Command::RelayAdvertise
+        )
+    }
+}
+
+fn parse_id(s: &str) -> Result<Id, Error> {
+    match Id::try_from_bech32_string(s) {
+        Ok(id) => Ok(id),
+        Err(_) => Ok(Id::try_from_hex_string(s)?),
+    }
+}
+
+fn parse_relay_url(s: &str) -> Result<RelayUrl, Error> {
+    Ok(RelayUrl::try_from_str(s)?)
+}
+
+/// Run a command. This is called on the main thread in place of the UI, while
+/// the overlord runs on the async thread.
+pub fn run(command: Command) -> Result<(), Error> {
+    let mut passphrase = if command.needs_private_key() {
+        Some(prompt_passphrase()?)
+    } else {
+        None
+    };
+
+    let result = tokio::runtime::Handle::current().block_on(run_async(command, &passphrase));
+
+    if let Some(p) = passphrase.as_mut() {
+        p.zeroize();
+    }
+
+    result
+}
+
+async fn run_async(command: Command, passphrase: &Option<String>) -> Result<(), Error> {
+    // Wait for the overlord to finish loading the signer, people and events
+    // from the database, so we don't race it.
+    while !GLOBALS.feed.ready.load(Ordering::Relaxed) {
+        if GLOBALS.shutting_down.load(Ordering::Relaxed) {
+            return Err("Gossip shut down before it finished starting up")?;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    if let Some(passphrase) = passphrase {
+        unlock(passphrase)?;
+    }
+
+    // Listen for what we post, and for relay answers about it, before we post
+    // anything
+    let mut posted_events = GLOBALS.posted_events.subscribe();
+    let mut responses = GLOBALS.posting_responses.subscribe();
+    let posts = command.needs_private_key();
+
+    let message = match command {
+        Command::Help => return Ok(()),
+        Command::Post(content, reply_to) => {
+            ToOverlordMessage::Post(content, vec![], reply_to, None)
+        }
+        Command::Repost(id) => ToOverlordMessage::Repost(id),
+        Command::Delete(id) => ToOverlordMessage::DeletePost(id),
+        Command::FollowNip05(nip05) => {
+            // The overlord runs this in the background where we cannot wait
+            // for it, so we run it here instead.
+            crate::nip05::get_and_follow_nip05(nip05.clone()).await?;
+            println!("Followed {}", nip05);
+            return Ok(());
+        }
+        Command::FollowNprofile(nprofile) => ToOverlordMessage::FollowNprofile(nprofile),
+        Command::FollowPubkey(pubkey, relay) => {
+            ToOverlordMessage::FollowPubkeyAndRelay(pubkey, relay)
+        }
+        Command::FollowPush => ToOverlordMessage::PushFollow,
+        Command::RelayAdd(relay) => ToOverlordMessage::AddRelay(relay),
+        Command::RelayRank(relay, rank) => ToOverlordMessage::RankRelay(relay, rank),
+        Command::RelayAdvertise => ToOverlordMessage::AdvertiseRelayList,
+        Command::Prune => {
+            // Same as above, the overlord would spawn this.
+            crate::db::prune().await?;
+            println!("Database prune has completed.");
+            return Ok(());
+        }
+        Command::Export(path) => {
+            let count = crate::archive::export(EventExport::default(), path.clone()).await?;
+            println!("Exported {} events to {}", count, path.display());
+            return Ok(());
+        }
+        Command::ExportMine(path) => {
+            let export = EventExport {
+                author: match GLOBALS.signer.public_key() {
+                    Some(pk) => Some(pk.into()),
+                    None => return Err("No identity is set up, so no events are yours.")?,
+                },
+                ..Default::default()
+            };
+            let count = crate::archive::export(export, path.clone()).await?;
+            println!("Exported {} events to {}", count, path.display());
+            return Ok(());
+        }
+        Command::Import(path) => {
+            let (imported, rejected) = crate::archive::import(path.clone()).await?;
+            println!(
+                "Imported {} events from {}, rejected {}",
+                imported,
+                path.display(),
+                rejected
+            );
+            return Ok(());
+        }
+    };
+
+    // The overlord handles messages in order, so anything we send after this
+    // (including the shutdown) happens after it is handled. Events are signed
+    // in the background though, so when posting we wait to hear what was posted.
+    let _ = GLOBALS.to_overlord.send(message);
+
+    if posts {
+        let deadline = Instant::now() + FIRST_RESPONSE_TIMEOUT;
+        let posted = loop {
+            match tokio::time::timeout_at(deadline, posted_events.recv()).await {
+                Ok(Ok(id)) => break Some(id),
+                Ok(Err(RecvError::Lagged(_))) => continue,
+                Ok(Err(RecvError::Closed)) | Err(_) => break None,
+            }
+        };
+        match posted {
+            Some(id) => print_posting_responses(&mut responses, id).await,
+            None => println!("Nothing was posted."),
+        }
+    }
+
+    Ok(())
+}
+
+fn prompt_passphrase() -> Result<String, Error> {
+    // Do not echo it at a terminal, but still take it piped in from a script
+    if std::io::stdin().is_terminal() {
+        return Ok(rpassword::prompt_password("Passphrase: ")?);
+    }
+
+    eprint!("Passphrase: ");
+    std::io::stderr().flush()?;
+    let mut passphrase = String::new();
+    std::io::stdin().read_line(&mut passphrase)?;
+    let trimmed = passphrase.trim_end_matches(&['\r', '\n'][..]).to_owned();
+    passphrase.zeroize();
+    Ok(trimmed)
+}
+
+fn unlock(passphrase: &str) -> Result<(), Error> {
+    // A remote signer needs no passphrase
+    if GLOBALS.signer.is_ready() {
+        return Ok(());
+    }
+
+    if !GLOBALS.signer.is_loaded() {
+        return Err("No identity is set up. Set one up in the gossip UI first.")?;
+    }
+
+    // Make sure we have a private key, not just a public one
+    if GLOBALS.signer.encrypted_private_key().is_none() {
+        return Err("No private key is set up, so we cannot sign events.")?;
+    }
+
+    if GLOBALS
+        .signer
+        .unlock_encrypted_private_key(passphrase)
+        .is_err()
+    {
+        return Err("Could not decrypt key with that passphrase.")?;
+    }
+
+    Ok(())
+}
+
+async fn print_posting_responses(
+    responses: &mut tokio::sync::broadcast::Receiver<PostingResponse>,
+    posted: Id,
+) {
+    let mut accepted: usize = 0;
+    let mut rejected: usize = 0;
+    // Answers about other events do not buy us more time
+    let mut deadline = Instant::now() + FIRST_RESPONSE_TIMEOUT;
+
+    loop {
+        match tokio::time::timeout_at(deadline, responses.recv()).await {
+            Ok(Ok(response)) => {
+                if response.id != posted {
+                    continue;
+                }
+                if response.ok {
+                    accepted += 1;
+                } else {
+                    rejected += 1;
+                }
+                if response.message.is_empty() {
+                    println!(
+                        "{}: OK={} id={}",
+                        response.relay,
+                        response.ok,
+                        response.id.as_hex_string()
+                    );
+                } else {
+                    println!(
+                        "{}: OK={} id={} message=\"{}\"",
+                        response.relay,
+                        response.ok,
+                        response.id.as_hex_string(),
+                        response.message
+                    );
+                }
+                deadline = Instant::now() + FURTHER_RESPONSE_TIMEOUT;
+            }
+            Ok(Err(RecvError::Lagged(n))) => {
+                tracing::warn!("Missed {} relay responses", n);
+            }
+            Ok(Err(RecvError::Closed)) | Err(_) => break,
+        }
+    }
+
+    if accepted + rejected == 0 {
+        println!("No relay answered.");
+    } else {
+        println!("{} relay(s) accepted, {} rejected.", accepted, rejected);
+    }
+}
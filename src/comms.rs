@@ -1,48 +1,89 @@
+use crate::archive::EventExport;
+use crate::db::{DbLongFormDraft, EventSearch, MuteKind, RetentionPolicy};
+use crate::nip44::ContentEncryption;
 use nostr_types::{Event, Id, IdHex, Metadata, PublicKey, PublicKeyHex, RelayUrl, Tag};
+use std::path::PathBuf;
 
 /// This is a message sent to the Overlord
 #[derive(Debug, Clone)]
 pub enum ToOverlordMessage {
+    AddBookmark(Id),
+    AddMute(MuteKind, String),
     AddRelay(RelayUrl),
+    AddToPersonList(String, PublicKeyHex),
     AdvertiseRelayList,
     ChangePassphrase(String, String),
     ClearFollowing,
+    ConnectRemoteSigner(String),
+    CreateIdentity(String),
+    ConnectWallet(String, String),
+    CreatePersonList(String),
     DelegationReset,
+    DeleteBookmark(Id),
+    DeleteIdentity(String),
+    DeleteMute(MuteKind, String),
+    DeleteLongFormDraft(String),
+    DeletePersonList(String),
     DeletePost(Id),
     DeletePriv,
     DeletePub,
+    DisconnectRemoteSigner,
+    DisconnectWallet,
+    DropFromOutbox(Id, RelayUrl),
     DropRelay(RelayUrl),
+    ExportEvents(EventExport, PathBuf),
     FetchEvent(Id, Vec<RelayUrl>),
+    FindZapper(PublicKeyHex),
+    FlushOutbox,
     FollowPubkeyAndRelay(String, RelayUrl),
     FollowNip05(String),
     FollowNprofile(String),
     GeneratePrivateKey(String),
     HideOrShowRelay(RelayUrl, bool),
+    ImportEvents(PathBuf),
     ImportPriv(String, String),
     ImportPub(String),
     Like(Id, PublicKey),
+    LoadRelayMetrics(RelayUrl, i64),
     MinionIsReady,
     MinionJobComplete(RelayUrl, u64),
+    NostrConnect(RelayUrl),
     PickRelays,
     ProcessIncomingEvents,
-    Post(String, Vec<Tag>, Option<Id>),
+    Post(String, Vec<Tag>, Option<Id>, Option<Vec<RelayUrl>>),
+    PreviewRetention(RetentionPolicy),
     PruneDatabase,
+    PublishLongForm(DbLongFormDraft),
     PullFollow,
     PushFollow,
+    PushMuteList,
     PushMetadata(Metadata),
     ReengageMinion(RelayUrl, Vec<RelayJob>),
     RefreshFollowedMetadata,
+    RemoveFromPersonList(String, PublicKeyHex),
     Repost(Id),
+    RetryDelivery(Id),
+    RepublishHistory(RelayUrl),
     RankRelay(RelayUrl, u8),
+    SaveLongFormDraft(DbLongFormDraft),
     SaveSettings,
+    SearchLocal(EventSearch),
+    SearchRelays(String),
+    SelectPostRelays(Vec<PublicKeyHex>),
+    CloseRelaySearch,
+    SendDirectMessage(PublicKeyHex, String),
+    SetDmEncryption(PublicKeyHex, ContentEncryption),
     SetActivePerson(PublicKeyHex),
     AdjustRelayUsageBit(RelayUrl, u64, bool),
     SetThreadFeed(Id, Id, Vec<RelayUrl>),
     Shutdown,
+    SwitchIdentity(String),
     UnlockKey(String),
     UpdateFollowing(bool),
     UpdateMetadata(PublicKeyHex),
     UpdateMetadataInBulk(Vec<PublicKeyHex>),
+    UpdateMuteList(bool),
+    Zap(Id, PublicKeyHex, u64, String),
 }
 
 /// This is a message sent to the minions
@@ -65,6 +106,7 @@ pub struct ToMinionPayload {
 
 #[derive(Debug, Clone)]
 pub enum ToMinionPayloadDetail {
+    Authenticate(Box<Event>),
     FetchEvent(IdHex),
     PostEvent(Box<Event>),
     PullFollowing,
@@ -72,12 +114,29 @@ pub enum ToMinionPayloadDetail {
     SubscribeConfig,
     SubscribeDiscover(Vec<PublicKeyHex>),
     SubscribeGeneralFeed(Vec<PublicKeyHex>),
+    SubscribeHashtagFeed(String),
     SubscribeMentions,
     SubscribePersonFeed(PublicKeyHex),
+    SubscribeRemoteSigner(PublicKeyHex),
+    SubscribeSearch(String),
     SubscribeThreadFeed(IdHex, Vec<IdHex>),
+    SubscribeWallet(PublicKeyHex),
     TempSubscribeMetadata(Vec<PublicKeyHex>),
+    UnsubscribeHashtagFeed,
     UnsubscribePersonFeed,
+    UnsubscribeRemoteSigner,
+    UnsubscribeSearch,
     UnsubscribeThreadFeed,
+    UnsubscribeWallet,
+}
+
+/// A relay's answer (an OK message) to an event we posted to it
+#[derive(Debug, Clone)]
+pub struct PostingResponse {
+    pub relay: RelayUrl,
+    pub id: Id,
+    pub ok: bool,
+    pub message: String,
 }
 
 #[derive(Debug, Clone)]
@@ -90,6 +149,9 @@ pub struct RelayJob {
 
     // Persistent? (restart if we get disconnected)
     pub persistent: bool,
+
+    // Keep the posted event in the outbox until the relay answers?
+    pub outbox: bool,
     // NOTE, there is other per-relay data stored elsewhere in
     //   overlord.minions_task_url
     //   GLOBALS.relay_picker
@@ -0,0 +1,366 @@
+use crate::comms::{PostingResponse, ToOverlordMessage};
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use futures_util::sink::SinkExt;
+use futures_util::stream::StreamExt;
+use nostr_types::{Event, Id, PublicKey, RelayUrl};
+use serde_json::{json, Value};
+use std::fs;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::select;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+use tungstenite::protocol::Message as WsMessage;
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const UNAUTHORIZED: i64 = -32001;
+
+/// Start the control API if it is enabled in settings. See CONTROL_API.md
+/// for the protocol.
+pub fn start() {
+    if !GLOBALS.settings.read().enable_control_api {
+        return;
+    }
+
+    std::mem::drop(tokio::spawn(async move {
+        if let Err(e) = run().await {
+            tracing::error!("Control API: {}", e);
+        }
+    }));
+}
+
+async fn run() -> Result<(), Error> {
+    let token = load_or_create_token()?;
+    let port = GLOBALS.settings.read().control_api_port;
+
+    // Localhost only. This is not meant to be reachable from other machines.
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    tracing::info!("Control API listening on ws://127.0.0.1:{}", port);
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let token = token.clone();
+        std::mem::drop(tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, peer, token).await {
+                tracing::warn!("Control API: {}: {}", peer, e);
+            }
+        }));
+    }
+}
+
+/// The path of the file holding the auth token
+pub fn token_path() -> Result<PathBuf, Error> {
+    let mut path = dirs::data_dir()
+        .ok_or::<Error>("Cannot find a directory to store application data.".into())?;
+    path.push("gossip");
+    path.push("control_token");
+    Ok(path)
+}
+
+fn load_or_create_token() -> Result<String, Error> {
+    let path = token_path()?;
+
+    if let Ok(token) = fs::read_to_string(&path) {
+        let token = token.trim().to_owned();
+        if !token.is_empty() {
+            return Ok(token);
+        }
+    }
+
+    let token = hex::encode(rand::random::<[u8; 32]>());
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&path)?;
+    file.write_all(token.as_bytes())?;
+
+    tracing::info!("Created control API token file at {}", path.display());
+
+    Ok(token)
+}
+
+/// State of one client connection
+struct Session {
+    token: String,
+    authenticated: bool,
+    events: Option<Receiver<Event>>,
+    postings: Option<Receiver<PostingResponse>>,
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    peer: SocketAddr,
+    token: String,
+) -> Result<(), Error> {
+    let mut ws_stream = tokio_tungstenite::accept_async(stream).await?;
+    tracing::info!("Control API: {}: connected", peer);
+
+    let mut session = Session {
+        token,
+        authenticated: false,
+        events: None,
+        postings: None,
+    };
+
+    loop {
+        let outgoing: Option<Value> = select! {
+            ws_message = ws_stream.next() => {
+                let ws_message = match ws_message {
+                    Some(m) => m?,
+                    None => break,
+                };
+                match ws_message {
+                    WsMessage::Text(t) => handle_request(&t, &mut session).await,
+                    WsMessage::Close(_) => break,
+                    _ => None,
+                }
+            },
+            event = recv(&mut session.events) => match event {
+                Ok(event) => Some(notification("event", serde_json::to_value(&event)?)),
+                Err(RecvError::Lagged(n)) => Some(notification("lagged", json!({ "missed": n }))),
+                Err(RecvError::Closed) => break,
+            },
+            response = recv(&mut session.postings) => match response {
+                Ok(response) => Some(notification("posting_response", json!({
+                    "relay": response.relay.as_str(),
+                    "id": response.id.as_hex_string(),
+                    "ok": response.ok,
+                    "message": response.message,
+                }))),
+                Err(RecvError::Lagged(n)) => Some(notification("lagged", json!({ "missed": n }))),
+                Err(RecvError::Closed) => break,
+            },
+        };
+
+        if let Some(value) = outgoing {
+            ws_stream.send(WsMessage::Text(value.to_string())).await?;
+        }
+    }
+
+    tracing::info!("Control API: {}: disconnected", peer);
+
+    Ok(())
+}
+
+/// Receive from an optional subscription, waiting forever if there is none
+async fn recv<T: Clone>(receiver: &mut Option<Receiver<T>>) -> Result<T, RecvError> {
+    match receiver {
+        Some(r) => r.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": method,
+        "params": params,
+    })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {
+            "code": code,
+            "message": message,
+        },
+    })
+}
+
+/// Handle one request, returning the response (if it was not a notification)
+async fn handle_request(text: &str, session: &mut Session) -> Option<Value> {
+    let request: Value = match serde_json::from_str(text) {
+        Ok(v) => v,
+        Err(_) => return Some(error_response(Value::Null, PARSE_ERROR, "Parse error")),
+    };
+
+    let id = request.get("id").cloned();
+    let method = match request.get("method").and_then(|m| m.as_str()) {
+        Some(m) => m.to_owned(),
+        None => {
+            return Some(error_response(
+                id.unwrap_or(Value::Null),
+                INVALID_REQUEST,
+                "Invalid request",
+            ))
+        }
+    };
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = handle_method(&method, &params, session).await;
+
+    // Requests without an id are notifications, and get no response
+    let id = id?;
+
+    Some(match result {
+        Ok(value) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "result": value,
+        }),
+        Err((code, message)) => error_response(id, code, &message),
+    })
+}
+
+async fn handle_method(
+    method: &str,
+    params: &Value,
+    session: &mut Session,
+) -> Result<Value, (i64, String)> {
+    if method == "auth" {
+        let token = param_str(params, "token")?;
+        if tokens_match(token.as_bytes(), session.token.as_bytes()) {
+            session.authenticated = true;
+            return Ok(Value::Bool(true));
+        } else {
+            return Err((UNAUTHORIZED, "Invalid token".to_owned()));
+        }
+    }
+
+    if !session.authenticated {
+        return Err((UNAUTHORIZED, "Not authenticated".to_owned()));
+    }
+
+    let message = match method {
+        "status" => {
+            let connected_relays: Vec<String> = GLOBALS
+                .connected_relays
+                .iter()
+                .map(|r| r.key().as_str().to_owned())
+                .collect();
+            return Ok(json!({
+                "public_key": GLOBALS.signer.public_key().map(|pk| pk.as_hex_string()),
+                "signer_ready": GLOBALS.signer.is_ready(),
+                "connected_relays": connected_relays,
+            }));
+        }
+        "subscribe_events" => {
+            session.events = Some(GLOBALS.new_events.subscribe());
+            return Ok(Value::Bool(true));
+        }
+        "unsubscribe_events" => {
+            session.events = None;
+            return Ok(Value::Bool(true));
+        }
+        "subscribe_postings" => {
+            session.postings = Some(GLOBALS.posting_responses.subscribe());
+            return Ok(Value::Bool(true));
+        }
+        "unsubscribe_postings" => {
+            session.postings = None;
+            return Ok(Value::Bool(true));
+        }
+        "unlock" => ToOverlordMessage::UnlockKey(param_str(params, "passphrase")?),
+        "post" => {
+            let reply_to = match params.get("reply_to").and_then(|v| v.as_str()) {
+                Some(s) => Some(parse_id(s)?),
+                None => None,
+            };
+            ToOverlordMessage::Post(param_str(params, "content")?, vec![], reply_to, None)
+        }
+        "like" => {
+            let id = parse_id(&param_str(params, "id")?)?;
+            let pubkey = parse_pubkey(&param_str(params, "pubkey")?)?;
+            ToOverlordMessage::Like(id, pubkey)
+        }
+        "repost" => ToOverlordMessage::Repost(parse_id(&param_str(params, "id")?)?),
+        "delete" => ToOverlordMessage::DeletePost(parse_id(&param_str(params, "id")?)?),
+        "fetch_event" => {
+            let id = parse_id(&param_str(params, "id")?)?;
+            let mut relays: Vec<RelayUrl> = Vec::new();
+            if let Some(array) = params.get("relays").and_then(|v| v.as_array()) {
+                for relay in array.iter() {
+                    match relay.as_str() {
+                        Some(s) => relays.push(parse_relay_url(s)?),
+                        None => return Err(invalid_params("relays must be strings")),
+                    }
+                }
+            }
+            ToOverlordMessage::FetchEvent(id, relays)
+        }
+        "follow_nip05" => ToOverlordMessage::FollowNip05(param_str(params, "nip05")?),
+        "follow_nprofile" => ToOverlordMessage::FollowNprofile(param_str(params, "nprofile")?),
+        "follow_pubkey" => ToOverlordMessage::FollowPubkeyAndRelay(
+            param_str(params, "pubkey")?,
+            parse_relay_url(&param_str(params, "relay")?)?,
+        ),
+        "pull_follow" => ToOverlordMessage::PullFollow,
+        "push_follow" => ToOverlordMessage::PushFollow,
+        "add_relay" => ToOverlordMessage::AddRelay(parse_relay_url(&param_str(params, "url")?)?),
+        "drop_relay" => ToOverlordMessage::DropRelay(parse_relay_url(&param_str(params, "url")?)?),
+        "rank_relay" => {
+            let url = parse_relay_url(&param_str(params, "url")?)?;
+            let rank = match params.get("rank").and_then(|v| v.as_u64()) {
+                Some(r) if r <= 9 => r as u8,
+                _ => return Err(invalid_params("rank must be a number from 0 to 9")),
+            };
+            ToOverlordMessage::RankRelay(url, rank)
+        }
+        "advertise_relay_list" => ToOverlordMessage::AdvertiseRelayList,
+        "pick_relays" => ToOverlordMessage::PickRelays,
+        "prune" => ToOverlordMessage::PruneDatabase,
+        _ => return Err((METHOD_NOT_FOUND, "Method not found".to_owned())),
+    };
+
+    if GLOBALS.to_overlord.send(message).is_err() {
+        return Err((INVALID_REQUEST, "Gossip is shutting down".to_owned()));
+    }
+
+    Ok(Value::Bool(true))
+}
+
+/// Compare tokens in time that does not depend on where they differ, so the
+/// token cannot be guessed a byte at a time
+fn tokens_match(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter()
+        .zip(b.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
+
+fn invalid_params(message: &str) -> (i64, String) {
+    (INVALID_PARAMS, message.to_owned())
+}
+
+fn param_str(params: &Value, name: &str) -> Result<String, (i64, String)> {
+    match params.get(name).and_then(|v| v.as_str()) {
+        Some(s) => Ok(s.to_owned()),
+        None => Err(invalid_params(&format!(
+            "Missing string parameter: {}",
+            name
+        ))),
+    }
+}
+
+fn parse_id(s: &str) -> Result<Id, (i64, String)> {
+    Id::try_from_bech32_string(s)
+        .or_else(|_| Id::try_from_hex_string(s))
+        .map_err(|_| invalid_params("Invalid event id"))
+}
+
+fn parse_pubkey(s: &str) -> Result<PublicKey, (i64, String)> {
+    PublicKey::try_from_bech32_string(s)
+        .or_else(|_| PublicKey::try_from_hex_string(s))
+        .map_err(|_| invalid_params("Invalid public key"))
+}
+
+fn parse_relay_url(s: &str) -> Result<RelayUrl, (i64, String)> {
+    RelayUrl::try_from_str(s).map_err(|_| invalid_params("Invalid relay url"))
+}
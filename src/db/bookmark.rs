@@ -0,0 +1,86 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::Id;
+use tokio::task::spawn_blocking;
+
+/// A bookmarked event
+#[derive(Debug, Clone)]
+pub struct DbBookmark {
+    pub event: Id,
+    pub bookmarked_at: i64,
+}
+
+impl DbBookmark {
+    /// All bookmarks, most recently bookmarked first
+    pub async fn load_all() -> Result<Vec<DbBookmark>, Error> {
+        let sql =
+            "SELECT event, bookmarked_at FROM bookmark ORDER BY bookmarked_at DESC".to_owned();
+        let output: Result<Vec<DbBookmark>, Error> = spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(&sql)?;
+            let mut output: Vec<DbBookmark> = Vec::new();
+            let mut rows = stmt.raw_query();
+            while let Some(row) = rows.next()? {
+                let event: String = row.get(0)?;
+                if let Ok(event) = Id::try_from_hex_string(&event) {
+                    output.push(DbBookmark {
+                        event,
+                        bookmarked_at: row.get(1)?,
+                    });
+                }
+            }
+            Ok(output)
+        })
+        .await?;
+
+        output
+    }
+
+    pub async fn insert(bookmark: DbBookmark) -> Result<(), Error> {
+        let sql = "INSERT OR IGNORE INTO bookmark (event, bookmarked_at) VALUES (?, ?)";
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(sql)?;
+            stmt.execute((bookmark.event.as_hex_string(), bookmark.bookmarked_at))?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// Make these exactly our bookmarks
+    pub async fn replace_all(bookmarks: Vec<DbBookmark>) -> Result<(), Error> {
+        spawn_blocking(move || {
+            let mut db = GLOBALS.db.blocking_lock();
+            let tx = db.transaction()?;
+            tx.execute("DELETE FROM bookmark", [])?;
+            {
+                let mut stmt = tx.prepare(
+                    "INSERT OR IGNORE INTO bookmark (event, bookmarked_at) VALUES (?, ?)",
+                )?;
+                for bookmark in bookmarks.iter() {
+                    stmt.execute((bookmark.event.as_hex_string(), bookmark.bookmarked_at))?;
+                }
+            }
+            tx.commit()?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    pub async fn delete(event: Id) -> Result<(), Error> {
+        let sql = "DELETE FROM bookmark WHERE event=?";
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(sql)?;
+            stmt.execute((event.as_hex_string(),))?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::nip44::ContentEncryption;
+use nostr_types::PublicKeyHex;
+use tokio::task::spawn_blocking;
+
+/// Per-conversation direct message settings
+#[derive(Debug, Clone)]
+pub struct DbDmChannel {
+    pub counterparty: PublicKeyHex,
+    pub encryption: ContentEncryption,
+}
+
+impl DbDmChannel {
+    pub async fn load_all() -> Result<Vec<DbDmChannel>, Error> {
+        let sql = "SELECT counterparty, encryption FROM dm_channel".to_owned();
+        let output: Result<Vec<DbDmChannel>, Error> = spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(&sql)?;
+            let mut output: Vec<DbDmChannel> = Vec::new();
+            let mut rows = stmt.raw_query();
+            while let Some(row) = rows.next()? {
+                let counterparty: String = row.get(0)?;
+                let encryption: u8 = row.get(1)?;
+                if let (Ok(counterparty), Some(encryption)) = (
+                    PublicKeyHex::try_from_string(counterparty),
+                    ContentEncryption::from_u8(encryption),
+                ) {
+                    output.push(DbDmChannel {
+                        counterparty,
+                        encryption,
+                    });
+                }
+            }
+            Ok(output)
+        })
+        .await?;
+
+        output
+    }
+
+    pub async fn set_encryption(
+        counterparty: PublicKeyHex,
+        encryption: ContentEncryption,
+    ) -> Result<(), Error> {
+        let sql = "INSERT INTO dm_channel (counterparty, encryption) VALUES (?, ?) \
+                   ON CONFLICT(counterparty) DO UPDATE SET encryption=excluded.encryption";
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(sql)?;
+            stmt.execute((counterparty.as_str(), encryption.as_u8()))?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+}
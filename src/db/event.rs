@@ -1,6 +1,7 @@
 use crate::error::Error;
 use crate::globals::GLOBALS;
-use nostr_types::{Event, EventKind, IdHex, PublicKeyHex};
+use nostr_types::{Event, EventKind, IdHex, PublicKeyHex, Unixtime};
+use rusqlite::types::Value;
 use serde::{Deserialize, Serialize};
 use tokio::task::spawn_blocking;
 
@@ -15,6 +16,45 @@ pub struct DbEvent {
     pub ots: Option<String>,
 }
 
+/// A search over locally stored events. Every part is optional, but at least
+/// one should be given.
+#[derive(Clone, Debug, Default)]
+pub struct EventSearch {
+    /// Words to look for in the content. A trailing `*` matches by prefix.
+    pub text: String,
+    pub author: Option<PublicKeyHex>,
+    pub kind: Option<EventKind>,
+    pub hashtag: Option<String>,
+    pub since: Option<Unixtime>,
+    pub until: Option<Unixtime>,
+    pub limit: usize,
+}
+
+impl EventSearch {
+    pub fn is_empty(&self) -> bool {
+        self.text.trim().is_empty()
+            && self.author.is_none()
+            && self.kind.is_none()
+            && self.hashtag.is_none()
+            && self.since.is_none()
+            && self.until.is_none()
+    }
+
+    // Quote every word so that user input is never taken as FTS5 query syntax
+    fn fts_query(&self) -> String {
+        self.text
+            .split_whitespace()
+            .map(|word| match word.strip_suffix('*') {
+                Some(prefix) if !prefix.is_empty() => {
+                    format!("\"{}\"*", prefix.replace('"', "\"\""))
+                }
+                _ => format!("\"{}\"", word.replace('"', "\"\"")),
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
 impl DbEvent {
     pub async fn fetch(criteria: Option<&str>) -> Result<Vec<DbEvent>, Error> {
         let sql = "SELECT id, raw, pubkey, created_at, kind, content, ots FROM event".to_owned();
@@ -207,6 +247,70 @@ impl DbEvent {
         Ok(())
     }
 
+    /// Search locally stored events, newest first
+    pub async fn search(search: EventSearch) -> Result<Vec<Event>, Error> {
+        let mut conditions: Vec<&str> = vec!["event.kind != 4"];
+        let mut params: Vec<Value> = Vec::new();
+
+        let fts_query = search.fts_query();
+        let mut sql = if fts_query.is_empty() {
+            "SELECT event.raw FROM event".to_owned()
+        } else {
+            conditions.push("event_fts MATCH ?");
+            params.push(Value::Text(fts_query));
+            "SELECT event.raw FROM event_fts \
+             INNER JOIN event_fts_docid ON event_fts_docid.docid=event_fts.rowid \
+             INNER JOIN event ON event.id=event_fts_docid.event"
+                .to_owned()
+        };
+        if let Some(author) = search.author {
+            conditions.push("event.pubkey=?");
+            params.push(Value::Text(author.as_str().to_owned()));
+        }
+        if let Some(kind) = search.kind {
+            conditions.push("event.kind=?");
+            params.push(Value::Integer(<EventKind as Into<u64>>::into(kind) as i64));
+        }
+        if let Some(hashtag) = search.hashtag {
+            conditions.push(
+                "event.id IN (SELECT event FROM event_hashtag WHERE hashtag=? COLLATE NOCASE)",
+            );
+            params.push(Value::Text(hashtag.trim_start_matches('#').to_owned()));
+        }
+        if let Some(since) = search.since {
+            conditions.push("event.created_at>=?");
+            params.push(Value::Integer(since.0));
+        }
+        if let Some(until) = search.until {
+            conditions.push("event.created_at<=?");
+            params.push(Value::Integer(until.0));
+        }
+
+        sql.push_str(&format!(
+            " WHERE {} ORDER BY event.created_at DESC LIMIT {}",
+            conditions.join(" AND "),
+            search.limit
+        ));
+
+        let output: Result<Vec<Event>, Error> = spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(&sql)?;
+            for (i, param) in params.iter().enumerate() {
+                stmt.raw_bind_parameter(i + 1, param)?;
+            }
+            let mut rows = stmt.raw_query();
+            let mut events: Vec<Event> = Vec::new();
+            while let Some(row) = rows.next()? {
+                let raw: String = row.get(0)?;
+                events.push(serde_json::from_str(&raw)?);
+            }
+            Ok(events)
+        })
+        .await?;
+
+        output
+    }
+
     // This is for replaceable (not parameterized!) events only.
     // Returns true if it inserted something, false if it didn't have to.
     pub async fn replace(event: DbEvent) -> Result<bool, Error> {
@@ -0,0 +1,279 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::PublicKey;
+use tokio::task::spawn_blocking;
+
+/// A named identity. Only one is active at a time; the data of the active
+/// identity lives in the usual tables, while the data of the others is set
+/// aside in the identity_* tables.
+#[derive(Debug, Clone)]
+pub struct DbIdentity {
+    pub name: String,
+    pub active: bool,
+    pub public_key: Option<PublicKey>,
+}
+
+impl DbIdentity {
+    pub async fn fetch_all() -> Result<Vec<DbIdentity>, Error> {
+        let sql = "SELECT name, active, \
+                   CASE WHEN active=1 THEN (SELECT value FROM settings WHERE key='public_key') \
+                   ELSE public_key END \
+                   FROM identity ORDER BY name"
+            .to_owned();
+        let output: Result<Vec<DbIdentity>, Error> = spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(&sql)?;
+            let mut output: Vec<DbIdentity> = Vec::new();
+            let mut rows = stmt.raw_query();
+            while let Some(row) = rows.next()? {
+                let public_key: Option<String> = row.get(2)?;
+                output.push(DbIdentity {
+                    name: row.get(0)?,
+                    active: row.get(1)?,
+                    public_key: public_key.and_then(|pk| PublicKey::try_from_hex_string(&pk).ok()),
+                });
+            }
+            Ok(output)
+        })
+        .await?;
+
+        output
+    }
+
+    /// Create a new (inactive) identity. It starts without keys and without
+    /// follows, but with the same relay configuration as the active identity.
+    pub async fn create(name: String) -> Result<(), Error> {
+        spawn_blocking(move || {
+            let mut db = GLOBALS.db.blocking_lock();
+            let tx = db.transaction()?;
+            tx.execute(
+                "INSERT INTO identity (name, active) VALUES (?, 0)",
+                (&name,),
+            )?;
+            tx.execute(
+                "INSERT INTO identity_relay (identity, relay, usage_bits) \
+                 SELECT ?, url, usage_bits FROM relay WHERE usage_bits != 0",
+                (&name,),
+            )?;
+            tx.commit()?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// Delete an inactive identity and everything set aside for it
+    pub async fn delete(name: String) -> Result<(), Error> {
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let changed = db.execute("DELETE FROM identity WHERE name=? AND active=0", (&name,))?;
+            if changed == 0 {
+                return Err(Error::from("Cannot delete the active identity"));
+            }
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// Set aside the data of the active identity and bring in the data of
+    /// the identity named `to`, all in one transaction. Returns the public key
+    /// and delegatee tag of the new identity. The caller is responsible for
+    /// reloading everything else that was loaded from these tables.
+    pub async fn switch(to: String) -> Result<(Option<PublicKey>, String), Error> {
+        let output: Result<(Option<String>, String), Error> = spawn_blocking(move || {
+            let mut db = GLOBALS.db.blocking_lock();
+            let tx = db.transaction()?;
+
+            let from: String =
+                tx.query_row("SELECT name FROM identity WHERE active=1", [], |row| {
+                    row.get(0)
+                })?;
+            if from == to {
+                return Err(Error::from(format!("{} is already the active identity", to)));
+            }
+            let exists: bool = tx.query_row(
+                "SELECT count(*) FROM identity WHERE name=?",
+                (&to,),
+                |row| row.get::<usize, u64>(0).map(|c| c > 0),
+            )?;
+            if !exists {
+                return Err(Error::from(format!("No identity named {}", to)));
+            }
+
+            // Set aside the active identity
+            tx.execute(
+                "UPDATE identity SET active=0, \
+                 public_key=(SELECT value FROM settings WHERE key='public_key'), \
+                 encrypted_private_key=(SELECT encrypted_private_key FROM local_settings), \
+                 remote_signer=(SELECT remote_signer FROM local_settings), \
+                 wallet_connection=(SELECT wallet_connection FROM local_settings), \
+                 last_contact_list_edit=(SELECT last_contact_list_edit FROM local_settings), \
+                 last_mute_list_edit=(SELECT last_mute_list_edit FROM local_settings), \
+                 last_bookmark_list_edit=(SELECT last_bookmark_list_edit FROM local_settings), \
+                 delegatee_tag=coalesce((SELECT value FROM settings WHERE key='delegatee_tag'), '') \
+                 WHERE name=?",
+                (&from,),
+            )?;
+            tx.execute("DELETE FROM identity_person WHERE identity=?", (&from,))?;
+            tx.execute(
+                "INSERT INTO identity_person (identity, person, followed, muted) \
+                 SELECT ?, pubkey, followed, muted FROM person WHERE followed=1 OR muted=1",
+                (&from,),
+            )?;
+            tx.execute("DELETE FROM identity_relay WHERE identity=?", (&from,))?;
+            tx.execute(
+                "INSERT INTO identity_relay (identity, relay, usage_bits) \
+                 SELECT ?, url, usage_bits FROM relay WHERE usage_bits != 0",
+                (&from,),
+            )?;
+            tx.execute("DELETE FROM identity_viewed WHERE identity=?", (&from,))?;
+            tx.execute(
+                "INSERT INTO identity_viewed (identity, event) \
+                 SELECT ?, event FROM event_flags WHERE viewed=1",
+                (&from,),
+            )?;
+            tx.execute("DELETE FROM identity_bookmark WHERE identity=?", (&from,))?;
+            tx.execute(
+                "INSERT INTO identity_bookmark (identity, event, bookmarked_at) \
+                 SELECT ?, event, bookmarked_at FROM bookmark",
+                (&from,),
+            )?;
+            tx.execute("DELETE FROM identity_mute WHERE identity=?", (&from,))?;
+            tx.execute(
+                "INSERT INTO identity_mute (identity, kind, value) \
+                 SELECT ?, kind, value FROM mute",
+                (&from,),
+            )?;
+            tx.execute("DELETE FROM identity_person_list WHERE identity=?", (&from,))?;
+            tx.execute(
+                "INSERT INTO identity_person_list (identity, name, last_edit) \
+                 SELECT ?, name, last_edit FROM person_list",
+                (&from,),
+            )?;
+            tx.execute(
+                "DELETE FROM identity_person_list_member WHERE identity=?",
+                (&from,),
+            )?;
+            tx.execute(
+                "INSERT INTO identity_person_list_member (identity, list, pubkey) \
+                 SELECT ?, list, pubkey FROM person_list_member",
+                (&from,),
+            )?;
+            tx.execute("DELETE FROM identity_dm_channel WHERE identity=?", (&from,))?;
+            tx.execute(
+                "INSERT INTO identity_dm_channel (identity, counterparty, encryption) \
+                 SELECT ?, counterparty, encryption FROM dm_channel",
+                (&from,),
+            )?;
+
+            // Bring in the new identity
+            tx.execute("UPDATE person SET followed=0, muted=0", [])?;
+            tx.execute(
+                "UPDATE person SET \
+                 followed=(SELECT followed FROM identity_person WHERE identity=?1 AND person=pubkey), \
+                 muted=(SELECT muted FROM identity_person WHERE identity=?1 AND person=pubkey) \
+                 WHERE pubkey IN (SELECT person FROM identity_person WHERE identity=?1)",
+                (&to,),
+            )?;
+            tx.execute("UPDATE relay SET usage_bits=0", [])?;
+            tx.execute(
+                "UPDATE relay SET \
+                 usage_bits=(SELECT usage_bits FROM identity_relay WHERE identity=?1 AND relay=url) \
+                 WHERE url IN (SELECT relay FROM identity_relay WHERE identity=?1)",
+                (&to,),
+            )?;
+            tx.execute("DELETE FROM event_flags", [])?;
+            tx.execute(
+                "INSERT INTO event_flags (event, viewed) \
+                 SELECT event, 1 FROM identity_viewed WHERE identity=?",
+                (&to,),
+            )?;
+            tx.execute("DELETE FROM bookmark", [])?;
+            tx.execute(
+                "INSERT INTO bookmark (event, bookmarked_at) \
+                 SELECT event, bookmarked_at FROM identity_bookmark WHERE identity=?",
+                (&to,),
+            )?;
+            tx.execute("DELETE FROM mute", [])?;
+            tx.execute(
+                "INSERT INTO mute (kind, value) \
+                 SELECT kind, value FROM identity_mute WHERE identity=?",
+                (&to,),
+            )?;
+            tx.execute("DELETE FROM person_list", [])?; // members go by cascade
+            tx.execute(
+                "INSERT INTO person_list (name, last_edit) \
+                 SELECT name, last_edit FROM identity_person_list WHERE identity=?",
+                (&to,),
+            )?;
+            tx.execute(
+                "INSERT INTO person_list_member (list, pubkey) \
+                 SELECT list, pubkey FROM identity_person_list_member WHERE identity=?",
+                (&to,),
+            )?;
+            tx.execute("DELETE FROM dm_channel", [])?;
+            tx.execute(
+                "INSERT INTO dm_channel (counterparty, encryption) \
+                 SELECT counterparty, encryption FROM identity_dm_channel WHERE identity=?",
+                (&to,),
+            )?;
+            tx.execute(
+                "UPDATE local_settings SET \
+                 encrypted_private_key=(SELECT encrypted_private_key FROM identity WHERE name=?1), \
+                 remote_signer=(SELECT remote_signer FROM identity WHERE name=?1), \
+                 wallet_connection=(SELECT wallet_connection FROM identity WHERE name=?1), \
+                 last_contact_list_edit=(SELECT last_contact_list_edit FROM identity WHERE name=?1), \
+                 last_mute_list_edit=(SELECT last_mute_list_edit FROM identity WHERE name=?1), \
+                 last_bookmark_list_edit=(SELECT last_bookmark_list_edit FROM identity WHERE name=?1)",
+                (&to,),
+            )?;
+            tx.execute("DELETE FROM settings WHERE key='public_key'", [])?;
+            tx.execute(
+                "INSERT INTO settings (key, value) \
+                 SELECT 'public_key', public_key FROM identity \
+                 WHERE name=? AND public_key IS NOT NULL",
+                (&to,),
+            )?;
+            tx.execute(
+                "REPLACE INTO settings (key, value) \
+                 SELECT 'delegatee_tag', delegatee_tag FROM identity WHERE name=?",
+                (&to,),
+            )?;
+
+            let (public_key, delegatee_tag): (Option<String>, String) = tx.query_row(
+                "SELECT public_key, delegatee_tag FROM identity WHERE name=?",
+                (&to,),
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+
+            // The set-aside copies of the new identity are now live
+            tx.execute("DELETE FROM identity_person WHERE identity=?", (&to,))?;
+            tx.execute("DELETE FROM identity_relay WHERE identity=?", (&to,))?;
+            tx.execute("DELETE FROM identity_viewed WHERE identity=?", (&to,))?;
+            tx.execute("DELETE FROM identity_bookmark WHERE identity=?", (&to,))?;
+            tx.execute("DELETE FROM identity_mute WHERE identity=?", (&to,))?;
+            tx.execute("DELETE FROM identity_person_list WHERE identity=?", (&to,))?;
+            tx.execute(
+                "DELETE FROM identity_person_list_member WHERE identity=?",
+                (&to,),
+            )?;
+            tx.execute("DELETE FROM identity_dm_channel WHERE identity=?", (&to,))?;
+            tx.execute(
+                "UPDATE identity SET active=1, public_key=NULL, encrypted_private_key=NULL, \
+                 remote_signer=NULL, wallet_connection=NULL WHERE name=?",
+                (&to,),
+            )?;
+
+            tx.commit()?;
+            Ok((public_key, delegatee_tag))
+        })
+        .await?;
+
+        let (public_key, delegatee_tag) = output?;
+        let public_key = public_key.and_then(|pk| PublicKey::try_from_hex_string(&pk).ok());
+        Ok((public_key, delegatee_tag))
+    }
+}
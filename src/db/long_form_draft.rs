@@ -0,0 +1,97 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::PublicKeyHex;
+use tokio::task::spawn_blocking;
+
+/// A long-form article (NIP-23) as we are writing it
+#[derive(Debug, Clone, Default)]
+pub struct DbLongFormDraft {
+    pub author: String,
+    /// The article's d tag, which stays the same across edits
+    pub identifier: String,
+    pub title: String,
+    pub summary: String,
+    pub image: String,
+    /// Space separated, without the #
+    pub hashtags: String,
+    pub content: String,
+    /// When it was first published, if it has been
+    pub published_at: Option<i64>,
+    pub last_edit: i64,
+}
+
+impl DbLongFormDraft {
+    /// The drafts of this author, most recently edited first
+    pub async fn fetch_all(author: PublicKeyHex) -> Result<Vec<DbLongFormDraft>, Error> {
+        let sql = "SELECT author, identifier, title, summary, image, hashtags, content, \
+                   published_at, last_edit FROM long_form_draft WHERE author=? \
+                   ORDER BY last_edit DESC";
+
+        let output: Result<Vec<DbLongFormDraft>, Error> = spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(sql)?;
+            stmt.raw_bind_parameter(1, author.as_str())?;
+            let mut rows = stmt.raw_query();
+            let mut output: Vec<DbLongFormDraft> = Vec::new();
+            while let Some(row) = rows.next()? {
+                output.push(DbLongFormDraft {
+                    author: row.get(0)?,
+                    identifier: row.get(1)?,
+                    title: row.get(2)?,
+                    summary: row.get(3)?,
+                    image: row.get(4)?,
+                    hashtags: row.get(5)?,
+                    content: row.get(6)?,
+                    published_at: row.get(7)?,
+                    last_edit: row.get(8)?,
+                });
+            }
+            Ok(output)
+        })
+        .await?;
+
+        output
+    }
+
+    pub async fn save(draft: DbLongFormDraft) -> Result<(), Error> {
+        let sql = "INSERT INTO long_form_draft (author, identifier, title, summary, image, \
+                   hashtags, content, published_at, last_edit) \
+                   VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9) \
+                   ON CONFLICT(author, identifier) DO UPDATE SET title=?3, summary=?4, \
+                   image=?5, hashtags=?6, content=?7, published_at=?8, last_edit=?9";
+
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(sql)?;
+            stmt.execute((
+                &draft.author,
+                &draft.identifier,
+                &draft.title,
+                &draft.summary,
+                &draft.image,
+                &draft.hashtags,
+                &draft.content,
+                &draft.published_at,
+                &draft.last_edit,
+            ))?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    pub async fn delete(author: PublicKeyHex, identifier: String) -> Result<(), Error> {
+        let sql = "DELETE FROM long_form_draft WHERE author=? AND identifier=?";
+
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(sql)?;
+            stmt.execute((author.as_str(), &identifier))?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+}
@@ -1,5 +1,5 @@
 mod event;
-pub use event::DbEvent;
+pub use event::{DbEvent, EventSearch};
 
 mod event_flags;
 pub use event_flags::DbEventFlags;
@@ -25,13 +25,46 @@ pub use contact::DbContact;
 mod person_relay;
 pub use person_relay::DbPersonRelay;
 
+mod person_zapper;
+pub use person_zapper::DbPersonZapper;
+
+mod dm_channel;
+pub use dm_channel::DbDmChannel;
+
+mod identity;
+pub use identity::DbIdentity;
+
+mod bookmark;
+pub use bookmark::DbBookmark;
+
+mod mute;
+pub use mute::{DbMute, MuteKind};
+
+mod person_list;
+pub use person_list::DbPersonList;
+
+mod long_form_draft;
+pub use long_form_draft::DbLongFormDraft;
+
+mod outbox;
+pub use outbox::DbOutbox;
+
+mod relay_metric;
+pub use relay_metric::{DbRelayMetric, RelayMetricKind};
+
+mod retention;
+pub use retention::{
+    PruneStats, RetentionPolicy, RetentionPreview, RetentionRule, MAX_RETENTION_DAYS,
+};
+
 use crate::error::Error;
 use crate::globals::GLOBALS;
 use fallible_iterator::FallibleIterator;
+use humansize::{format_size, DECIMAL};
+use nostr_types::Unixtime;
 use rusqlite::Connection;
 use std::fs;
 use std::sync::atomic::Ordering;
-use tokio::task;
 
 pub fn init_database() -> Result<Connection, Error> {
     let mut data_dir = dirs::data_dir()
@@ -152,14 +185,87 @@ fn upgrade(db: &Connection, mut version: usize) -> Result<(), Error> {
 }
 
 pub async fn prune() -> Result<(), Error> {
-    task::spawn_blocking(move || {
-        let db = GLOBALS.db.blocking_lock();
-        db.execute_batch(include_str!("sql/prune.sql"))?;
-        Ok::<(), Error>(())
-    })
-    .await??;
+    let policy = GLOBALS.settings.read().retention.clone();
+    let (deleted, reclaimed) = policy.apply().await?;
+    *GLOBALS.retention_preview.write() = None;
+    *GLOBALS.prune_stats.write() = PruneStats::load().await?;
+
+    *GLOBALS.status_message.write().await = format!(
+        "Database prune has completed, {} events deleted, {} reclaimed.",
+        deleted,
+        format_size(reclaimed, DECIMAL)
+    );
+
+    Ok(())
+}
+
+// Don't keep pruning a database that stays over budget more often than this
+const BUDGET_PRUNE_MIN_INTERVAL: i64 = 60 * 60 * 6;
+
+/// Prune if one is scheduled, or if the database has outgrown its size budget.
+/// When over budget, the retention periods are halved until it fits again
+/// (this never touches what the policy keeps forever).
+pub async fn auto_prune() -> Result<(), Error> {
+    let (interval_days, budget) = {
+        let settings = GLOBALS.settings.read();
+        (
+            settings.prune_interval_days,
+            settings.database_size_budget_mb * 1_000_000,
+        )
+    };
+
+    let stats = PruneStats::load().await?;
+    let since_last = Unixtime::now().unwrap().0 - stats.last_prune;
+    let scheduled = interval_days > 0 && since_last >= interval_days as i64 * 86400;
+    let over_budget =
+        budget > 0 && stats.database_size > budget && since_last >= BUDGET_PRUNE_MIN_INTERVAL;
+    *GLOBALS.prune_stats.write() = stats;
+
+    if !scheduled && !over_budget {
+        return Ok(());
+    }
+
+    tracing::info!("Pruning the database automatically");
+
+    let mut policy = GLOBALS.settings.read().retention.clone();
+    let mut total_deleted: usize = 0;
+    loop {
+        total_deleted += policy.delete().await?;
+
+        // Deleted rows leave free pages behind until the vacuum below
+        let fits = budget == 0 || retention::used_database_size().await? <= budget;
+        if fits {
+            break;
+        }
+        if policy.default_days <= 1 {
+            tracing::warn!("The database is still over its size budget after pruning");
+            break;
+        }
+
+        policy.default_days /= 2;
+        // Halving must not reach 0, which would drop the protection of
+        // followed people altogether (unless the user already turned it off)
+        if policy.followed_days > 0 {
+            policy.followed_days = (policy.followed_days / 2).max(1);
+        }
+        for rule in policy.rules.iter_mut() {
+            if let Some(days) = rule.days {
+                rule.days = Some((days / 2).max(1));
+            }
+        }
+    }
+
+    let total_reclaimed = retention::vacuum().await?;
+    *GLOBALS.prune_stats.write() = PruneStats::load().await?;
+
+    // The preview no longer describes the database
+    *GLOBALS.retention_preview.write() = None;
 
-    *GLOBALS.status_message.write().await = "Database prune has completed.".to_owned();
+    *GLOBALS.status_message.write().await = format!(
+        "Automatic database prune deleted {} events, {} reclaimed.",
+        total_deleted,
+        format_size(total_reclaimed, DECIMAL)
+    );
 
     Ok(())
 }
@@ -244,7 +350,7 @@ fn normalize_urls() -> Result<(), Error> {
     Ok(())
 }
 
-const UPGRADE_SQL: [&str; 34] = [
+const UPGRADE_SQL: [&str; 50] = [
     include_str!("sql/schema1.sql"),
     include_str!("sql/schema2.sql"),
     include_str!("sql/schema3.sql"),
@@ -279,4 +385,20 @@ const UPGRADE_SQL: [&str; 34] = [
     include_str!("sql/schema32.sql"),
     include_str!("sql/schema33.sql"),
     include_str!("sql/schema34.sql"),
+    include_str!("sql/schema35.sql"),
+    include_str!("sql/schema36.sql"),
+    include_str!("sql/schema37.sql"),
+    include_str!("sql/schema38.sql"),
+    include_str!("sql/schema39.sql"),
+    include_str!("sql/schema40.sql"),
+    include_str!("sql/schema41.sql"),
+    include_str!("sql/schema42.sql"),
+    include_str!("sql/schema43.sql"),
+    include_str!("sql/schema44.sql"),
+    include_str!("sql/schema45.sql"),
+    include_str!("sql/schema46.sql"),
+    include_str!("sql/schema47.sql"),
+    include_str!("sql/schema48.sql"),
+    include_str!("sql/schema49.sql"),
+    include_str!("sql/schema50.sql"),
 ];
@@ -0,0 +1,102 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use tokio::task::spawn_blocking;
+
+/// What a mute applies to, other than people (who are muted in the person table)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MuteKind {
+    /// Notes containing this word, case-insensitively
+    Word,
+    /// Notes tagged with this hashtag
+    Hashtag,
+    /// Every note in the thread under this root event id
+    Thread,
+}
+
+impl MuteKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MuteKind::Word => "word",
+            MuteKind::Hashtag => "hashtag",
+            MuteKind::Thread => "thread",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<MuteKind> {
+        match s {
+            "word" => Some(MuteKind::Word),
+            "hashtag" => Some(MuteKind::Hashtag),
+            "thread" => Some(MuteKind::Thread),
+            _ => None,
+        }
+    }
+}
+
+/// A muted word, hashtag or thread
+#[derive(Debug, Clone)]
+pub struct DbMute {
+    pub kind: MuteKind,
+    pub value: String,
+}
+
+impl DbMute {
+    pub async fn load_all() -> Result<Vec<DbMute>, Error> {
+        let sql = "SELECT kind, value FROM mute ORDER BY kind, value".to_owned();
+        let output: Result<Vec<DbMute>, Error> = spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(&sql)?;
+            let mut output: Vec<DbMute> = Vec::new();
+            let mut rows = stmt.raw_query();
+            while let Some(row) = rows.next()? {
+                let kind: String = row.get(0)?;
+                if let Some(kind) = MuteKind::parse(&kind) {
+                    output.push(DbMute {
+                        kind,
+                        value: row.get(1)?,
+                    });
+                }
+            }
+            Ok(output)
+        })
+        .await?;
+
+        output
+    }
+
+    pub async fn insert(mute: DbMute) -> Result<(), Error> {
+        let sql = "INSERT OR IGNORE INTO mute (kind, value) VALUES (?, ?)";
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(sql)?;
+            stmt.execute((mute.kind.as_str(), &mute.value))?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    pub async fn delete(mute: DbMute) -> Result<(), Error> {
+        let sql = "DELETE FROM mute WHERE kind=? AND value=?";
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(sql)?;
+            stmt.execute((mute.kind.as_str(), &mute.value))?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    pub async fn delete_all() -> Result<(), Error> {
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            db.execute("DELETE FROM mute", [])?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, Id, RelayUrl};
+use tokio::task::spawn_blocking;
+
+/// One of our events waiting to be taken by one relay
+#[derive(Debug, Clone)]
+pub struct DbOutbox {
+    pub event: Event,
+    pub relay: RelayUrl,
+    pub queued_at: i64,
+    /// How many times we sent it without an answer
+    pub attempts: u64,
+}
+
+impl DbOutbox {
+    /// Everything waiting, oldest first
+    pub async fn load_all() -> Result<Vec<DbOutbox>, Error> {
+        let sql = "SELECT raw, relay, queued_at, attempts FROM outbox ORDER BY queued_at";
+        let output: Result<Vec<DbOutbox>, Error> = spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(sql)?;
+            let mut output: Vec<DbOutbox> = Vec::new();
+            let mut rows = stmt.raw_query();
+            while let Some(row) = rows.next()? {
+                let raw: String = row.get(0)?;
+                let relay: String = row.get(1)?;
+                // Just skip over anything unreadable
+                if let (Ok(event), Ok(relay)) = (
+                    serde_json::from_str::<Event>(&raw),
+                    RelayUrl::try_from_str(&relay),
+                ) {
+                    output.push(DbOutbox {
+                        event,
+                        relay,
+                        queued_at: row.get(2)?,
+                        attempts: row.get(3)?,
+                    });
+                }
+            }
+            Ok(output)
+        })
+        .await?;
+
+        output
+    }
+
+    /// Queue the event for the relay, or count another attempt if it is queued
+    pub async fn queue(event: Event, relay: RelayUrl, now: i64) -> Result<(), Error> {
+        let sql = "INSERT INTO outbox (event, relay, raw, queued_at, attempts) \
+                   VALUES (?1, ?2, ?3, ?4, 1) \
+                   ON CONFLICT(event, relay) DO UPDATE SET attempts=attempts+1";
+        let raw = serde_json::to_string(&event)?;
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(sql)?;
+            stmt.execute((event.id.as_hex_string(), relay.0.as_str(), &raw, now))?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    pub async fn delete(id: Id, relay: RelayUrl) -> Result<(), Error> {
+        let sql = "DELETE FROM outbox WHERE event=? AND relay=?";
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(sql)?;
+            stmt.execute((id.as_hex_string(), relay.0.as_str()))?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::PublicKeyHex;
+use tokio::task::spawn_blocking;
+
+/// A named list of people
+#[derive(Debug, Clone)]
+pub struct DbPersonList {
+    pub name: String,
+    /// When the list was last changed, here or by a list we took in
+    pub last_edit: i64,
+    pub members: Vec<PublicKeyHex>,
+}
+
+impl DbPersonList {
+    /// All lists with their members, by name
+    pub async fn fetch_all() -> Result<Vec<DbPersonList>, Error> {
+        let output: Result<Vec<DbPersonList>, Error> = spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+
+            let mut output: Vec<DbPersonList> = Vec::new();
+            let mut stmt = db.prepare("SELECT name, last_edit FROM person_list ORDER BY name")?;
+            let mut rows = stmt.raw_query();
+            while let Some(row) = rows.next()? {
+                output.push(DbPersonList {
+                    name: row.get(0)?,
+                    last_edit: row.get(1)?,
+                    members: Vec::new(),
+                });
+            }
+
+            let mut stmt = db.prepare("SELECT list, pubkey FROM person_list_member")?;
+            let mut rows = stmt.raw_query();
+            while let Some(row) = rows.next()? {
+                let list: String = row.get(0)?;
+                let pubkey: String = row.get(1)?;
+                if let Ok(pubkey) = PublicKeyHex::try_from_string(pubkey) {
+                    if let Some(dblist) = output.iter_mut().find(|l| l.name == list) {
+                        dblist.members.push(pubkey);
+                    }
+                }
+            }
+
+            Ok(output)
+        })
+        .await?;
+
+        output
+    }
+
+    pub async fn create(name: String, now: i64) -> Result<(), Error> {
+        let sql = "INSERT OR IGNORE INTO person_list (name, last_edit) VALUES (?, ?)";
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(sql)?;
+            stmt.execute((&name, now))?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    pub async fn delete(name: String) -> Result<(), Error> {
+        // Members go with it, by cascade
+        let sql = "DELETE FROM person_list WHERE name=?";
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(sql)?;
+            stmt.execute((&name,))?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    pub async fn add_member(name: String, pubkey: PublicKeyHex, now: i64) -> Result<(), Error> {
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            db.execute(
+                "INSERT OR IGNORE INTO person_list_member (list, pubkey) VALUES (?, ?)",
+                (&name, pubkey.as_str()),
+            )?;
+            db.execute(
+                "UPDATE person_list SET last_edit=? WHERE name=?",
+                (now, &name),
+            )?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    pub async fn remove_member(name: String, pubkey: PublicKeyHex, now: i64) -> Result<(), Error> {
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            db.execute(
+                "DELETE FROM person_list_member WHERE list=? AND pubkey=?",
+                (&name, pubkey.as_str()),
+            )?;
+            db.execute(
+                "UPDATE person_list SET last_edit=? WHERE name=?",
+                (now, &name),
+            )?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// Create or replace a list, with exactly these members
+    pub async fn replace(list: DbPersonList) -> Result<(), Error> {
+        spawn_blocking(move || {
+            let mut db = GLOBALS.db.blocking_lock();
+            let tx = db.transaction()?;
+            tx.execute(
+                "INSERT INTO person_list (name, last_edit) VALUES (?1, ?2) \
+                 ON CONFLICT(name) DO UPDATE SET last_edit=?2",
+                (&list.name, list.last_edit),
+            )?;
+            tx.execute("DELETE FROM person_list_member WHERE list=?", (&list.name,))?;
+            for pubkey in list.members.iter() {
+                tx.execute(
+                    "INSERT OR IGNORE INTO person_list_member (list, pubkey) VALUES (?, ?)",
+                    (&list.name, pubkey.as_str()),
+                )?;
+            }
+            tx.commit()?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{PublicKey, PublicKeyHex};
+use tokio::task::spawn_blocking;
+
+/// Who signs a person's zap receipts, for the lnurl we asked about
+#[derive(Debug, Clone)]
+pub struct DbPersonZapper {
+    pub person: PublicKeyHex,
+    pub lnurl: String,
+    /// None if their LNURL server does not take zaps
+    pub zapper: Option<PublicKey>,
+}
+
+impl DbPersonZapper {
+    pub async fn load_all() -> Result<Vec<DbPersonZapper>, Error> {
+        let sql = "SELECT person, lnurl, zapper FROM person_zapper".to_owned();
+        let output: Result<Vec<DbPersonZapper>, Error> = spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(&sql)?;
+            let mut output: Vec<DbPersonZapper> = Vec::new();
+            let mut rows = stmt.raw_query();
+            while let Some(row) = rows.next()? {
+                let person: String = row.get(0)?;
+                let zapper: Option<String> = row.get(2)?;
+                let zapper = match zapper {
+                    Some(zapper) => match PublicKey::try_from_hex_string(&zapper) {
+                        Ok(zapper) => Some(zapper),
+                        Err(_) => continue,
+                    },
+                    None => None,
+                };
+                if let Ok(person) = PublicKeyHex::try_from_string(person) {
+                    output.push(DbPersonZapper {
+                        person,
+                        lnurl: row.get(1)?,
+                        zapper,
+                    });
+                }
+            }
+            Ok(output)
+        })
+        .await?;
+
+        output
+    }
+
+    pub async fn save(&self) -> Result<(), Error> {
+        let sql = "INSERT INTO person_zapper (person, lnurl, zapper) VALUES (?, ?, ?) \
+                   ON CONFLICT(person) DO UPDATE SET lnurl=excluded.lnurl, zapper=excluded.zapper";
+        let person = self.person.as_str().to_owned();
+        let lnurl = self.lnurl.clone();
+        let zapper = self.zapper.map(|zapper| zapper.as_hex_string());
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(sql)?;
+            stmt.execute((&person, &lnurl, &zapper))?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    pub async fn delete(person: PublicKeyHex) -> Result<(), Error> {
+        let sql = "DELETE FROM person_zapper WHERE person=?";
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(sql)?;
+            stmt.execute((person.as_str(),))?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+}
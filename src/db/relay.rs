@@ -1,6 +1,6 @@
 use crate::error::Error;
 use crate::globals::GLOBALS;
-use nostr_types::{Id, RelayUrl};
+use nostr_types::{Id, RelayInformationDocument, RelayUrl};
 use tokio::task::spawn_blocking;
 
 #[derive(Debug, Clone)]
@@ -13,6 +13,7 @@ pub struct DbRelay {
     pub rank: u64,
     pub hidden: bool,
     pub usage_bits: u64,
+    pub nip11: Option<RelayInformationDocument>,
 }
 
 impl DbRelay {
@@ -35,6 +36,7 @@ impl DbRelay {
             rank: 3,
             hidden: false,
             usage_bits: 0,
+            nip11: None,
         }
     }
 
@@ -86,7 +88,7 @@ impl DbRelay {
 
     pub async fn fetch(criteria: Option<&str>) -> Result<Vec<DbRelay>, Error> {
         let sql = "SELECT url, success_count, failure_count, last_connected_at, \
-             last_general_eose_at, rank, hidden, usage_bits FROM relay"
+             last_general_eose_at, rank, hidden, usage_bits, nip11 FROM relay"
             .to_owned();
         let sql = match criteria {
             None => sql,
@@ -103,6 +105,7 @@ impl DbRelay {
                 let s: String = row.get(0)?;
                 // just skip over invalid relay URLs
                 if let Ok(url) = RelayUrl::try_from_str(&s) {
+                    let nip11: Option<String> = row.get(8)?;
                     output.push(DbRelay {
                         url,
                         success_count: row.get(1)?,
@@ -112,6 +115,7 @@ impl DbRelay {
                         rank: row.get(5)?,
                         hidden: row.get(6)?,
                         usage_bits: row.get(7)?,
+                        nip11: nip11.and_then(|json| serde_json::from_str(&json).ok()),
                     });
                 }
             }
@@ -235,6 +239,20 @@ impl DbRelay {
         Ok(())
     }
 
+    pub async fn update_nip11(url: RelayUrl, nip11: RelayInformationDocument) -> Result<(), Error> {
+        let sql = "UPDATE relay SET nip11 = ? WHERE url = ?";
+        let json = serde_json::to_string(&nip11)?;
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(sql)?;
+            rtry!(stmt.execute((&json, &url.0)));
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+
     /*
     pub async fn delete(criteria: &str) -> Result<(), Error> {
         let sql = format!("DELETE FROM relay WHERE {}", criteria);
@@ -0,0 +1,139 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{RelayUrl, Unixtime};
+use tokio::task::spawn_blocking;
+
+/// How long relay metrics are kept
+pub const RELAY_METRIC_RETENTION_DAYS: i64 = 30;
+
+/// What a relay metric measures
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelayMetricKind {
+    /// Milliseconds to open the websocket
+    ConnectLatency,
+    /// Milliseconds from a REQ to its EOSE, with the subscription handle as detail
+    EoseLatency,
+    /// A NOTICE, with the message as detail
+    Notice,
+    /// An OK=false for one of our events, with the message as detail
+    Rejected,
+    /// Bytes received since the last such record
+    BytesReceived,
+    /// The connection ended, with the reason as detail
+    Disconnect,
+}
+
+impl RelayMetricKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RelayMetricKind::ConnectLatency => "connect",
+            RelayMetricKind::EoseLatency => "eose",
+            RelayMetricKind::Notice => "notice",
+            RelayMetricKind::Rejected => "rejected",
+            RelayMetricKind::BytesReceived => "bytes",
+            RelayMetricKind::Disconnect => "disconnect",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<RelayMetricKind> {
+        match s {
+            "connect" => Some(RelayMetricKind::ConnectLatency),
+            "eose" => Some(RelayMetricKind::EoseLatency),
+            "notice" => Some(RelayMetricKind::Notice),
+            "rejected" => Some(RelayMetricKind::Rejected),
+            "bytes" => Some(RelayMetricKind::BytesReceived),
+            "disconnect" => Some(RelayMetricKind::Disconnect),
+            _ => None,
+        }
+    }
+}
+
+/// One measurement of a relay at one time
+#[derive(Debug, Clone)]
+pub struct DbRelayMetric {
+    pub relay: RelayUrl,
+    pub at: i64,
+    pub kind: RelayMetricKind,
+    pub value: i64,
+    pub detail: String,
+}
+
+impl DbRelayMetric {
+    pub fn new(
+        relay: RelayUrl,
+        kind: RelayMetricKind,
+        value: i64,
+        detail: String,
+    ) -> DbRelayMetric {
+        DbRelayMetric {
+            relay,
+            at: Unixtime::now().unwrap().0,
+            kind,
+            value,
+            detail,
+        }
+    }
+
+    pub async fn insert(metric: DbRelayMetric) -> Result<(), Error> {
+        let sql = "INSERT INTO relay_metric (relay, at, kind, value, detail) \
+                   VALUES (?1, ?2, ?3, ?4, ?5)";
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(sql)?;
+            stmt.execute((
+                metric.relay.0.as_str(),
+                metric.at,
+                metric.kind.as_str(),
+                metric.value,
+                &metric.detail,
+            ))?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// The relay's metrics since a time, oldest first
+    pub async fn fetch(relay: RelayUrl, since: i64) -> Result<Vec<DbRelayMetric>, Error> {
+        let sql = "SELECT at, kind, value, detail FROM relay_metric \
+                   WHERE relay=? AND at>=? ORDER BY at";
+        let output: Result<Vec<DbRelayMetric>, Error> = spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            let mut stmt = db.prepare(sql)?;
+            stmt.raw_bind_parameter(1, relay.0.as_str())?;
+            stmt.raw_bind_parameter(2, since)?;
+            let mut output: Vec<DbRelayMetric> = Vec::new();
+            let mut rows = stmt.raw_query();
+            while let Some(row) = rows.next()? {
+                let kind: String = row.get(1)?;
+                if let Some(kind) = RelayMetricKind::parse(&kind) {
+                    output.push(DbRelayMetric {
+                        relay: relay.clone(),
+                        at: row.get(0)?,
+                        kind,
+                        value: row.get(2)?,
+                        detail: row.get(3)?,
+                    });
+                }
+            }
+            Ok(output)
+        })
+        .await?;
+
+        output
+    }
+
+    /// Delete metrics older than we keep them
+    pub async fn prune() -> Result<(), Error> {
+        let before = Unixtime::now().unwrap().0 - RELAY_METRIC_RETENTION_DAYS * 86400;
+        spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            db.execute("DELETE FROM relay_metric WHERE at<?", (before,))?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,330 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{PublicKeyHex, Unixtime};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tokio::task::spawn_blocking;
+
+pub const DEFAULT_RETENTION_DAYS: u64 = 7;
+pub const DEFAULT_RETENTION_FOLLOWED_DAYS: u64 = 30;
+
+/// The longest a rule may keep events for; keeping them forever is done with no days
+pub const MAX_RETENTION_DAYS: u64 = 36500;
+
+// Public keys of all of our identities, active or not
+const OWN_PUBKEYS: &str = "SELECT value FROM settings WHERE key='public_key' \
+                           UNION SELECT public_key FROM identity WHERE public_key IS NOT NULL";
+
+// Replaceable events, of which we only ever keep the latest
+const REPLACEABLE: &str = "(kind IN (0,3) OR (kind>=10000 AND kind<20000))";
+
+// Ephemeral events, which we never keep
+const EPHEMERAL: &str = "(kind>=20000 AND kind<30000)";
+
+/// A rule overriding how long events of some kind and/or author are kept.
+/// A rule with neither a kind nor an author matches every event.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RetentionRule {
+    pub kind: Option<u32>,
+    pub author: Option<PublicKeyHex>,
+
+    /// Days to keep matching events, or None to keep them forever
+    pub days: Option<u64>,
+}
+
+impl RetentionRule {
+    fn condition(&self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        if let Some(kind) = self.kind {
+            parts.push(format!("kind={}", kind));
+        }
+        if let Some(ref author) = self.author {
+            // PublicKeyHex is validated hex, so it is safe to inline
+            parts.push(format!("pubkey='{}'", author.as_str()));
+        }
+        if parts.is_empty() {
+            "1".to_owned()
+        } else {
+            parts.join(" AND ")
+        }
+    }
+}
+
+/// Which events survive a database prune
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Events not otherwise covered are kept this many days
+    pub default_days: u64,
+
+    /// Events by people we follow are kept at least this many days
+    pub followed_days: u64,
+
+    /// Keep events by any of our identities forever
+    pub keep_own: bool,
+
+    /// Keep direct messages forever
+    pub keep_dms: bool,
+
+    /// Keep every event in threads we replied in forever
+    pub keep_replied_threads: bool,
+
+    /// Per-kind and per-author rules, the first matching rule wins
+    pub rules: Vec<RetentionRule>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> RetentionPolicy {
+        RetentionPolicy {
+            default_days: DEFAULT_RETENTION_DAYS,
+            followed_days: DEFAULT_RETENTION_FOLLOWED_DAYS,
+            keep_own: true,
+            keep_dms: true,
+            keep_replied_threads: true,
+            rules: Vec::new(),
+        }
+    }
+}
+
+/// What a prune under some policy would do
+#[derive(Clone, Debug, Default)]
+pub struct RetentionPreview {
+    pub total: u64,
+    pub superseded: u64,
+    pub expired: u64,
+
+    /// Number of expired events, per kind, most numerous first
+    pub expired_by_kind: Vec<(u32, u64)>,
+}
+
+impl RetentionPolicy {
+    // Old versions of replaceable events, and ephemeral events which should
+    // never have been stored
+    fn superseded_sql() -> String {
+        format!(
+            "({} AND created_at != (SELECT max(created_at) FROM event AS event_inner \
+             WHERE kind=event.kind AND pubkey=event.pubkey)) \
+             OR {}",
+            REPLACEABLE, EPHEMERAL
+        )
+    }
+
+    // Events which have outlived this policy
+    fn expired_sql(&self, now: i64) -> String {
+        // So many days that they would overflow keep everything
+        let cutoff = |days: u64| {
+            i64::try_from(days)
+                .ok()
+                .and_then(|days| days.checked_mul(86400))
+                .map(|secs| now.saturating_sub(secs))
+                .unwrap_or(i64::MIN)
+        };
+
+        // Bookmarks, of any of our identities, are always kept
+        let mut protected: Vec<String> = vec![
+            "id IN (SELECT event FROM bookmark UNION SELECT event FROM identity_bookmark)"
+                .to_owned(),
+        ];
+        if self.keep_own {
+            protected.push(format!("pubkey IN ({})", OWN_PUBKEYS));
+        }
+        if self.keep_dms {
+            protected.push("kind=4".to_owned());
+        }
+        if self.keep_replied_threads {
+            // Events we replied to and the roots of their threads, and then every
+            // reply under those, however deep. Quotes ('mention' markers) are not
+            // part of a thread.
+            let replied_to = format!(
+                "SELECT field0 FROM event_tag WHERE label='e' \
+                 AND coalesce(field2, '')!='mention' AND event IN \
+                 (SELECT id FROM event AS mine WHERE mine.kind=1 AND mine.pubkey IN ({}))",
+                OWN_PUBKEYS
+            );
+            protected.push(format!(
+                "id IN (WITH RECURSIVE thread(id) AS ({} \
+                 UNION SELECT field0 FROM event_tag WHERE label='e' AND field2='root' \
+                 AND event IN ({}) \
+                 UNION SELECT event_tag.event FROM event_tag JOIN thread \
+                 ON event_tag.field0=thread.id WHERE event_tag.label='e' \
+                 AND coalesce(event_tag.field2, '')!='mention') \
+                 SELECT id FROM thread)",
+                replied_to, replied_to
+            ));
+        }
+        if self.followed_days > 0 {
+            protected.push(format!(
+                "(pubkey IN (SELECT pubkey FROM person WHERE followed=1) AND created_at >= {})",
+                cutoff(self.followed_days)
+            ));
+        }
+
+        let mut age = "CASE".to_owned();
+        for rule in self.rules.iter() {
+            let expired = match rule.days {
+                Some(days) => format!("created_at < {}", cutoff(days)),
+                None => "0".to_owned(),
+            };
+            age.push_str(&format!(" WHEN {} THEN {}", rule.condition(), expired));
+        }
+        age.push_str(&format!(
+            " ELSE created_at < {} END",
+            cutoff(self.default_days)
+        ));
+
+        format!(
+            "NOT {} AND NOT {} AND ({}) AND NOT ({})",
+            REPLACEABLE,
+            EPHEMERAL,
+            age,
+            protected.join(" OR ")
+        )
+    }
+
+    /// Count what a prune under this policy would delete, without deleting anything
+    pub async fn preview(&self) -> Result<RetentionPreview, Error> {
+        let superseded_sql = Self::superseded_sql();
+        let expired_sql = self.expired_sql(Unixtime::now().unwrap().0);
+
+        let output: Result<RetentionPreview, Error> = spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+
+            let count = |sql: &str| -> Result<u64, Error> {
+                let count: u64 = db.query_row(
+                    &format!("SELECT count(*) FROM event WHERE {}", sql),
+                    [],
+                    |row| row.get(0),
+                )?;
+                Ok(count)
+            };
+
+            let mut preview = RetentionPreview {
+                total: count("1")?,
+                superseded: count(&superseded_sql)?,
+                expired: count(&expired_sql)?,
+                expired_by_kind: Vec::new(),
+            };
+
+            let mut stmt = db.prepare(&format!(
+                "SELECT kind, count(*) AS c FROM event WHERE {} GROUP BY kind ORDER BY c DESC",
+                expired_sql
+            ))?;
+            let mut rows = stmt.raw_query();
+            while let Some(row) = rows.next()? {
+                preview.expired_by_kind.push((row.get(0)?, row.get(1)?));
+            }
+
+            Ok(preview)
+        })
+        .await?;
+
+        output
+    }
+
+    /// Delete everything this policy does not keep, returning the number of deleted
+    /// events and the number of bytes reclaimed from the database
+    pub async fn apply(&self) -> Result<(usize, u64), Error> {
+        let deleted = self.delete().await?;
+        let reclaimed = vacuum().await?;
+        Ok((deleted, reclaimed))
+    }
+
+    /// Delete everything this policy does not keep, returning the number of deleted
+    /// events. The space is not given back to the filesystem until a vacuum.
+    pub async fn delete(&self) -> Result<usize, Error> {
+        let sql = format!(
+            "DELETE FROM event WHERE ({}) OR ({})",
+            Self::superseded_sql(),
+            self.expired_sql(Unixtime::now().unwrap().0)
+        );
+
+        let output: Result<usize, Error> = spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+
+            // Due to foreign keys and cascades, the following will also be cleaned up
+            //   event_relationship
+            //   event_hashtag
+            //   event_tag
+            //   event_seen
+            Ok(db.execute(&sql, [])?)
+        })
+        .await?;
+
+        output
+    }
+}
+
+/// Give the space of deleted data back to the filesystem and record the prune,
+/// returning the number of bytes reclaimed
+pub async fn vacuum() -> Result<u64, Error> {
+    let output: Result<u64, Error> = spawn_blocking(move || {
+        let db = GLOBALS.db.blocking_lock();
+
+        let before = database_size(&db)?;
+        db.execute_batch("VACUUM;")?;
+        let reclaimed = before.saturating_sub(database_size(&db)?);
+
+        db.execute(
+            "UPDATE local_settings SET last_prune=?, last_prune_reclaimed=?, \
+             total_prune_reclaimed=total_prune_reclaimed+?",
+            (Unixtime::now().unwrap().0, reclaimed, reclaimed),
+        )?;
+
+        Ok(reclaimed)
+    })
+    .await?;
+
+    output
+}
+
+/// How big the database would be after a vacuum
+pub async fn used_database_size() -> Result<u64, Error> {
+    let output: Result<u64, Error> = spawn_blocking(move || {
+        let db = GLOBALS.db.blocking_lock();
+        let free_pages: u64 = db.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+        let page_size: u64 = db.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        Ok(database_size(&db)?.saturating_sub(free_pages * page_size))
+    })
+    .await?;
+
+    output
+}
+
+/// How big the database is, and what pruning has reclaimed from it
+#[derive(Clone, Debug, Default)]
+pub struct PruneStats {
+    pub database_size: u64,
+    pub last_prune: i64,
+    pub last_reclaimed: u64,
+    pub total_reclaimed: u64,
+}
+
+impl PruneStats {
+    pub async fn load() -> Result<PruneStats, Error> {
+        let output: Result<PruneStats, Error> = spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+
+            let (last_prune, last_reclaimed, total_reclaimed) = db.query_row(
+                "SELECT last_prune, last_prune_reclaimed, total_prune_reclaimed \
+                 FROM local_settings LIMIT 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )?;
+
+            Ok(PruneStats {
+                database_size: database_size(&db)?,
+                last_prune,
+                last_reclaimed,
+                total_reclaimed,
+            })
+        })
+        .await?;
+
+        output
+    }
+}
+
+fn database_size(db: &Connection) -> Result<u64, Error> {
+    let page_count: u64 = db.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+    let page_size: u64 = db.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+    Ok(page_count * page_size)
+}
@@ -0,0 +1 @@
+ALTER TABLE local_settings ADD COLUMN remote_signer TEXT DEFAULT NULL;
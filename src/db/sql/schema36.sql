@@ -0,0 +1,4 @@
+CREATE TABLE dm_channel (
+    counterparty TEXT PRIMARY KEY NOT NULL,
+    encryption INTEGER NOT NULL DEFAULT 4
+) WITHOUT ROWID;
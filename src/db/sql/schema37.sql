@@ -0,0 +1,39 @@
+-- Identities. The active identity's data lives in the usual tables
+-- (local_settings, person.followed/muted, relay.usage_bits, event_flags).
+-- The data of the other identities is set aside here until switched to.
+CREATE TABLE identity (
+    name TEXT PRIMARY KEY NOT NULL,
+    active INTEGER NOT NULL DEFAULT 0,
+    public_key TEXT DEFAULT NULL,
+    encrypted_private_key TEXT DEFAULT NULL,
+    remote_signer TEXT DEFAULT NULL,
+    last_contact_list_edit INTEGER NOT NULL DEFAULT 0,
+    delegatee_tag TEXT NOT NULL DEFAULT ''
+) WITHOUT ROWID;
+
+CREATE TABLE identity_person (
+    identity TEXT NOT NULL,
+    person TEXT NOT NULL,
+    followed INTEGER NOT NULL DEFAULT 0,
+    muted INTEGER NOT NULL DEFAULT 0,
+    UNIQUE(identity, person),
+    CONSTRAINT identity_person_fk_identity FOREIGN KEY (identity) REFERENCES identity (name) ON DELETE CASCADE
+);
+
+CREATE TABLE identity_relay (
+    identity TEXT NOT NULL,
+    relay TEXT NOT NULL,
+    usage_bits INTEGER NOT NULL DEFAULT 0,
+    UNIQUE(identity, relay),
+    CONSTRAINT identity_relay_fk_identity FOREIGN KEY (identity) REFERENCES identity (name) ON DELETE CASCADE
+);
+
+CREATE TABLE identity_viewed (
+    identity TEXT NOT NULL,
+    event TEXT NOT NULL,
+    UNIQUE(identity, event),
+    CONSTRAINT identity_viewed_fk_identity FOREIGN KEY (identity) REFERENCES identity (name) ON DELETE CASCADE,
+    CONSTRAINT identity_viewed_fk_event FOREIGN KEY (event) REFERENCES event (id) ON DELETE CASCADE
+);
+
+INSERT INTO identity (name, active) VALUES ('Default', 1);
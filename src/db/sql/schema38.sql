@@ -0,0 +1,27 @@
+-- Full text search over event content.
+-- The event table has no integer rowid, so we map events to FTS document ids.
+CREATE TABLE event_fts_docid (
+    docid INTEGER PRIMARY KEY AUTOINCREMENT,
+    event TEXT UNIQUE NOT NULL
+);
+
+CREATE VIRTUAL TABLE event_fts USING fts5(content, tokenize = 'unicode61 remove_diacritics 2');
+
+-- Metadata and contact lists are JSON, DMs are ciphertext and reactions are noise
+CREATE TRIGGER event_fts_insert AFTER INSERT ON event
+WHEN new.kind NOT IN (0, 3, 4, 7) AND new.content != ''
+BEGIN
+    INSERT INTO event_fts_docid (event) VALUES (new.id);
+    INSERT INTO event_fts (rowid, content) VALUES (last_insert_rowid(), new.content);
+END;
+
+CREATE TRIGGER event_fts_delete AFTER DELETE ON event
+BEGIN
+    DELETE FROM event_fts WHERE rowid IN (SELECT docid FROM event_fts_docid WHERE event=old.id);
+    DELETE FROM event_fts_docid WHERE event=old.id;
+END;
+
+INSERT INTO event_fts_docid (event)
+    SELECT id FROM event WHERE kind NOT IN (0, 3, 4, 7) AND content != '';
+INSERT INTO event_fts (rowid, content)
+    SELECT docid, event.content FROM event_fts_docid INNER JOIN event ON event.id=event_fts_docid.event;
@@ -0,0 +1,3 @@
+ALTER TABLE local_settings ADD COLUMN last_prune INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE local_settings ADD COLUMN last_prune_reclaimed INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE local_settings ADD COLUMN total_prune_reclaimed INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,14 @@
+-- Bookmarked events. These are published as our NIP-51 bookmark list, and
+-- they are never pruned. The event itself may not have been fetched yet.
+CREATE TABLE bookmark (
+    event TEXT PRIMARY KEY NOT NULL,
+    bookmarked_at INTEGER NOT NULL
+) WITHOUT ROWID;
+
+CREATE TABLE identity_bookmark (
+    identity TEXT NOT NULL,
+    event TEXT NOT NULL,
+    bookmarked_at INTEGER NOT NULL,
+    UNIQUE(identity, event),
+    CONSTRAINT identity_bookmark_fk_identity FOREIGN KEY (identity) REFERENCES identity (name) ON DELETE CASCADE
+);
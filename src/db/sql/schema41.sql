@@ -0,0 +1,18 @@
+-- Muted words, hashtags and threads. Muted people are flagged in the person
+-- table. Together these are published as our NIP-51 mute list.
+CREATE TABLE mute (
+    kind TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (kind, value)
+) WITHOUT ROWID;
+
+CREATE TABLE identity_mute (
+    identity TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    value TEXT NOT NULL,
+    UNIQUE(identity, kind, value),
+    CONSTRAINT identity_mute_fk_identity FOREIGN KEY (identity) REFERENCES identity (name) ON DELETE CASCADE
+);
+
+ALTER TABLE local_settings ADD COLUMN last_mute_list_edit INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE identity ADD COLUMN last_mute_list_edit INTEGER NOT NULL DEFAULT 0;
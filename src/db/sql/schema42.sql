@@ -0,0 +1,29 @@
+-- Named lists of people, published as NIP-51 follow sets (kind 30000) under
+-- their name. Each list can be viewed as its own feed.
+CREATE TABLE person_list (
+    name TEXT PRIMARY KEY NOT NULL,
+    last_edit INTEGER NOT NULL DEFAULT 0
+) WITHOUT ROWID;
+
+CREATE TABLE person_list_member (
+    list TEXT NOT NULL,
+    pubkey TEXT NOT NULL,
+    UNIQUE(list, pubkey),
+    CONSTRAINT person_list_member_fk_list FOREIGN KEY (list) REFERENCES person_list (name) ON DELETE CASCADE
+);
+
+CREATE TABLE identity_person_list (
+    identity TEXT NOT NULL,
+    name TEXT NOT NULL,
+    last_edit INTEGER NOT NULL DEFAULT 0,
+    UNIQUE(identity, name),
+    CONSTRAINT identity_person_list_fk_identity FOREIGN KEY (identity) REFERENCES identity (name) ON DELETE CASCADE
+);
+
+CREATE TABLE identity_person_list_member (
+    identity TEXT NOT NULL,
+    list TEXT NOT NULL,
+    pubkey TEXT NOT NULL,
+    UNIQUE(identity, list, pubkey),
+    CONSTRAINT identity_person_list_member_fk_identity FOREIGN KEY (identity) REFERENCES identity (name) ON DELETE CASCADE
+);
@@ -0,0 +1,3 @@
+-- NIP-47 wallet connection, with its secret encrypted under the key passphrase
+ALTER TABLE local_settings ADD COLUMN wallet_connection TEXT DEFAULT NULL;
+ALTER TABLE identity ADD COLUMN wallet_connection TEXT DEFAULT NULL;
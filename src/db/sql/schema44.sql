@@ -0,0 +1,14 @@
+-- NIP-23 long-form articles being written. These stay local until published
+-- (and stay after, so the article can be edited and published again).
+CREATE TABLE long_form_draft (
+    author TEXT NOT NULL,
+    identifier TEXT NOT NULL,
+    title TEXT NOT NULL DEFAULT '',
+    summary TEXT NOT NULL DEFAULT '',
+    image TEXT NOT NULL DEFAULT '',
+    hashtags TEXT NOT NULL DEFAULT '',
+    content TEXT NOT NULL DEFAULT '',
+    published_at INTEGER DEFAULT NULL,
+    last_edit INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (author, identifier)
+) WITHOUT ROWID;
@@ -0,0 +1,10 @@
+-- Our signed events that a relay has not yet taken. They are sent again when
+-- we next connect to that relay, and dropped once it answers.
+CREATE TABLE outbox (
+    event TEXT NOT NULL,
+    relay TEXT NOT NULL,
+    raw TEXT NOT NULL,
+    queued_at INTEGER NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (event, relay)
+) WITHOUT ROWID;
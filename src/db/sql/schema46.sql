@@ -0,0 +1,10 @@
+-- Relay health over time: connect and EOSE latencies, notices, rejections of our
+-- events, bytes received and disconnects. See DbRelayMetric for what each kind holds.
+CREATE TABLE relay_metric (
+    relay TEXT NOT NULL,
+    at INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    value INTEGER NOT NULL DEFAULT 0,
+    detail TEXT NOT NULL DEFAULT ''
+);
+CREATE INDEX relay_metric_relay_at_idx ON relay_metric(relay, at);
@@ -0,0 +1,6 @@
+-- When we last changed our bookmarks here, to tell whether a bookmark list from
+-- relays is newer. Bookmarks we already have count from when they were made.
+ALTER TABLE local_settings ADD COLUMN last_bookmark_list_edit INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE identity ADD COLUMN last_bookmark_list_edit INTEGER NOT NULL DEFAULT 0;
+UPDATE local_settings SET last_bookmark_list_edit=coalesce((SELECT max(bookmarked_at) FROM bookmark), 0);
+UPDATE identity SET last_bookmark_list_edit=coalesce((SELECT max(bookmarked_at) FROM identity_bookmark WHERE identity=identity.name), 0);
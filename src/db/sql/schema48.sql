@@ -0,0 +1,9 @@
+-- The encryption chosen for each conversation belongs to the identity that
+-- chose it, so it is set aside along with the rest of an identity's data.
+CREATE TABLE identity_dm_channel (
+    identity TEXT NOT NULL,
+    counterparty TEXT NOT NULL,
+    encryption INTEGER NOT NULL DEFAULT 4,
+    UNIQUE(identity, counterparty),
+    CONSTRAINT identity_dm_channel_fk_identity FOREIGN KEY (identity) REFERENCES identity (name) ON DELETE CASCADE
+);
@@ -0,0 +1,3 @@
+-- The relay information document (NIP-11) the relay last served us, as JSON,
+-- so we know what a relay supports before connecting to it.
+ALTER TABLE relay ADD COLUMN nip11 TEXT DEFAULT NULL;
@@ -0,0 +1,8 @@
+-- Who signs each person's zap receipts, as their LNURL server told us, so that
+-- we need not ask it again every time we start. It holds for as long as the
+-- person's lightning address is the same lnurl.
+CREATE TABLE person_zapper (
+    person TEXT PRIMARY KEY NOT NULL,
+    lnurl TEXT NOT NULL,
+    zapper TEXT DEFAULT NULL
+) WITHOUT ROWID;
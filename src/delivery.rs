@@ -0,0 +1,116 @@
+use crate::globals::GLOBALS;
+use dashmap::DashMap;
+use nostr_types::{Event, Id, RelayUrl, Unixtime};
+
+/// How long we wait for a relay to answer a post before counting it as unanswered
+const DELIVERY_TIMEOUT_SECS: i64 = 60;
+
+/// What became of a post sent to one relay
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeliveryStatus {
+    /// Sent at this time, waiting on an OK
+    Pending(Unixtime),
+    Accepted,
+    /// Rejected, with the relay's message
+    Rejected(String),
+    /// The relay went away or never answered
+    NoAnswer,
+}
+
+impl DeliveryStatus {
+    pub fn failed(&self) -> bool {
+        matches!(self, DeliveryStatus::Rejected(_) | DeliveryStatus::NoAnswer)
+    }
+}
+
+/// What became of our posts at each relay we sent them to. This covers the posts
+/// of this session only.
+pub struct Deliveries {
+    posts: DashMap<Id, Vec<(RelayUrl, DeliveryStatus)>>,
+}
+
+impl Default for Deliveries {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deliveries {
+    pub fn new() -> Deliveries {
+        Deliveries {
+            posts: DashMap::new(),
+        }
+    }
+
+    /// Note that we are sending an event to a relay. Only our own events are tracked.
+    pub fn sent(&self, event: &Event, url: &RelayUrl) {
+        if GLOBALS.signer.public_key() != Some(event.pubkey) {
+            return;
+        }
+        self.set(
+            event.id,
+            url,
+            DeliveryStatus::Pending(Unixtime::now().unwrap()),
+        );
+    }
+
+    /// The relay answered with an OK message
+    pub fn answered(&self, id: Id, url: &RelayUrl, ok: bool, message: String) {
+        if !self.posts.contains_key(&id) {
+            return;
+        }
+        let status = if ok {
+            DeliveryStatus::Accepted
+        } else {
+            DeliveryStatus::Rejected(message)
+        };
+        self.set(id, url, status);
+    }
+
+    /// The relay disconnected, so whatever it has not answered never will be
+    pub fn relay_gone(&self, url: &RelayUrl) {
+        for mut post in self.posts.iter_mut() {
+            for (u, status) in post.value_mut().iter_mut() {
+                if u == url && matches!(status, DeliveryStatus::Pending(_)) {
+                    *status = DeliveryStatus::NoAnswer;
+                }
+            }
+        }
+    }
+
+    /// Where the post went and what became of it, if it is one we sent
+    pub fn get(&self, id: &Id) -> Option<Vec<(RelayUrl, DeliveryStatus)>> {
+        let now = Unixtime::now().unwrap().0;
+        self.posts.get(id).map(|post| {
+            post.value()
+                .iter()
+                .map(|(url, status)| match status {
+                    DeliveryStatus::Pending(sent) if now - sent.0 > DELIVERY_TIMEOUT_SECS => {
+                        (url.clone(), DeliveryStatus::NoAnswer)
+                    }
+                    _ => (url.clone(), status.clone()),
+                })
+                .collect()
+        })
+    }
+
+    /// The relays that rejected the post or never answered
+    pub fn failed_relays(&self, id: &Id) -> Vec<RelayUrl> {
+        match self.get(id) {
+            Some(statuses) => statuses
+                .into_iter()
+                .filter(|(_, status)| status.failed())
+                .map(|(url, _)| url)
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    fn set(&self, id: Id, url: &RelayUrl, status: DeliveryStatus) {
+        let mut post = self.posts.entry(id).or_default();
+        match post.iter_mut().find(|(u, _)| u == url) {
+            Some((_, s)) => *s = status,
+            None => post.push((url.clone(), status)),
+        }
+    }
+}
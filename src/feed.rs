@@ -1,9 +1,11 @@
 use crate::comms::{ToMinionMessage, ToMinionPayload, ToMinionPayloadDetail, ToOverlordMessage};
+use crate::db::{DbEvent, DbRelay, EventSearch};
 use crate::error::Error;
 use crate::globals::GLOBALS;
-use nostr_types::{EventDelegation, EventKind, Id, PublicKeyHex, RelayUrl, Unixtime};
+use crate::nip44::ContentEncryption;
+use nostr_types::{Event, EventDelegation, EventKind, Id, PublicKeyHex, RelayUrl, Tag, Unixtime};
 use parking_lot::RwLock;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
 use tokio::task;
@@ -14,6 +16,34 @@ pub enum FeedKind {
     Inbox(bool),    // indirect
     Thread { id: Id, referenced_by: Id },
     Person(PublicKeyHex),
+    Hashtag(String),
+    Bookmarks,
+    List(String), // name of a person list
+}
+
+/// A direct message conversation with one other person
+#[derive(Clone, Debug)]
+pub struct DmChannel {
+    pub counterparty: PublicKeyHex,
+    pub latest_message: Unixtime,
+    /// Message ids, oldest first
+    pub message_ids: Vec<Id>,
+    /// Messages from them that we have not viewed
+    pub unread: usize,
+    /// The encryption their latest message to us used
+    pub their_encryption: Option<ContentEncryption>,
+}
+
+/// A NIP-50 search sent to relays, with results as they stream in
+#[derive(Clone, Debug, Default)]
+pub struct RelaySearch {
+    pub query: String,
+    /// Relays that support search and were asked
+    pub relays: Vec<RelayUrl>,
+    /// Matching notes, newest first
+    pub note_ids: Vec<Id>,
+    /// People whose metadata matched
+    pub people: Vec<PublicKeyHex>,
 }
 
 pub struct Feed {
@@ -32,6 +62,12 @@ pub struct Feed {
     followed_feed: RwLock<Vec<Id>>,
     inbox_feed: RwLock<Vec<Id>>,
     person_feed: RwLock<Vec<Id>>,
+    hashtag_feed: RwLock<Vec<Id>>,
+    bookmarks_feed: RwLock<Vec<Id>>,
+    list_feed: RwLock<Vec<Id>>,
+    dm_channels: RwLock<Vec<DmChannel>>,
+    search_results: RwLock<Vec<Id>>,
+    relay_search: RwLock<Option<RelaySearch>>,
 
     // We only recompute the feed at specified intervals (or when they switch)
     interval_ms: RwLock<u32>,
@@ -49,6 +85,12 @@ impl Feed {
             followed_feed: RwLock::new(Vec::new()),
             inbox_feed: RwLock::new(Vec::new()),
             person_feed: RwLock::new(Vec::new()),
+            hashtag_feed: RwLock::new(Vec::new()),
+            bookmarks_feed: RwLock::new(Vec::new()),
+            list_feed: RwLock::new(Vec::new()),
+            dm_channels: RwLock::new(Vec::new()),
+            search_results: RwLock::new(Vec::new()),
+            relay_search: RwLock::new(None),
             interval_ms: RwLock::new(1000), // Every second, until we load from settings
             last_computed: RwLock::new(None),
             thread_parent: RwLock::new(None),
@@ -65,21 +107,8 @@ impl Feed {
         // Recompute as they switch
         self.sync_recompute();
 
-        // When going to Followed or Inbox, we stop listening for Thread/Person events
-        let _ = GLOBALS.to_minions.send(ToMinionMessage {
-            target: "all".to_string(),
-            payload: ToMinionPayload {
-                job_id: 0,
-                detail: ToMinionPayloadDetail::UnsubscribeThreadFeed,
-            },
-        });
-        let _ = GLOBALS.to_minions.send(ToMinionMessage {
-            target: "all".to_string(),
-            payload: ToMinionPayload {
-                job_id: 0,
-                detail: ToMinionPayloadDetail::UnsubscribePersonFeed,
-            },
-        });
+        // When going to Followed or Inbox, we stop listening for Thread/Person/Hashtag events
+        Self::unsubscribe_transient_feeds(&self.current_feed_kind.read());
     }
 
     pub fn set_feed_to_inbox(&self, indirect: bool) {
@@ -89,21 +118,8 @@ impl Feed {
         // Recompute as they switch
         self.sync_recompute();
 
-        // When going to Followed or Inbox, we stop listening for Thread/Person events
-        let _ = GLOBALS.to_minions.send(ToMinionMessage {
-            target: "all".to_string(),
-            payload: ToMinionPayload {
-                job_id: 0,
-                detail: ToMinionPayloadDetail::UnsubscribeThreadFeed,
-            },
-        });
-        let _ = GLOBALS.to_minions.send(ToMinionMessage {
-            target: "all".to_string(),
-            payload: ToMinionPayload {
-                job_id: 0,
-                detail: ToMinionPayloadDetail::UnsubscribePersonFeed,
-            },
-        });
+        // When going to Followed or Inbox, we stop listening for Thread/Person/Hashtag events
+        Self::unsubscribe_transient_feeds(&self.current_feed_kind.read());
     }
 
     pub fn set_feed_to_thread(&self, id: Id, referenced_by: Id, relays: Vec<RelayUrl>) {
@@ -116,13 +132,8 @@ impl Feed {
         // Recompute as they switch
         self.sync_recompute();
 
-        let _ = GLOBALS.to_minions.send(ToMinionMessage {
-            target: "all".to_string(),
-            payload: ToMinionPayload {
-                job_id: 0,
-                detail: ToMinionPayloadDetail::UnsubscribePersonFeed,
-            },
-        });
+        Self::unsubscribe_transient_feeds(&self.current_feed_kind.read());
+
         let _ =
             GLOBALS
                 .to_overlord
@@ -136,22 +147,131 @@ impl Feed {
         // Recompute as they switch
         self.sync_recompute();
 
+        Self::unsubscribe_transient_feeds(&self.current_feed_kind.read());
+
         let _ = GLOBALS.to_minions.send(ToMinionMessage {
             target: "all".to_string(),
             payload: ToMinionPayload {
                 job_id: 0,
-                detail: ToMinionPayloadDetail::UnsubscribeThreadFeed,
+                detail: ToMinionPayloadDetail::SubscribePersonFeed(pubkey),
             },
         });
+    }
+
+    pub fn set_feed_to_hashtag(&self, hashtag: String) {
+        *self.current_feed_kind.write() = FeedKind::Hashtag(hashtag.clone());
+        *self.thread_parent.write() = None;
+
+        // Bring in what we already have locally, then recompute
+        let local_hashtag = hashtag.clone();
+        task::spawn(async move {
+            if let Err(e) = Self::load_local_hashtag_events(local_hashtag).await {
+                tracing::error!("{}", e);
+            }
+            if let Err(e) = GLOBALS.feed.recompute().await {
+                tracing::error!("{}", e);
+            }
+        });
+
+        Self::unsubscribe_transient_feeds(&self.current_feed_kind.read());
+
         let _ = GLOBALS.to_minions.send(ToMinionMessage {
             target: "all".to_string(),
             payload: ToMinionPayload {
                 job_id: 0,
-                detail: ToMinionPayloadDetail::SubscribePersonFeed(pubkey),
+                detail: ToMinionPayloadDetail::SubscribeHashtagFeed(hashtag),
             },
         });
     }
 
+    pub fn set_feed_to_bookmarks(&self) {
+        *self.current_feed_kind.write() = FeedKind::Bookmarks;
+        *self.thread_parent.write() = None;
+
+        // Bring in what we already have locally, ask relays for the rest, then recompute
+        task::spawn(async move {
+            if let Err(e) = Self::load_bookmarked_events().await {
+                tracing::error!("{}", e);
+            }
+            if let Err(e) = GLOBALS.feed.recompute().await {
+                tracing::error!("{}", e);
+            }
+        });
+
+        Self::unsubscribe_transient_feeds(&self.current_feed_kind.read());
+    }
+
+    pub fn set_feed_to_list(&self, name: String) {
+        *self.current_feed_kind.write() = FeedKind::List(name);
+        *self.thread_parent.write() = None;
+
+        // Recompute as they switch
+        self.sync_recompute();
+
+        // List members are covered by the general feed subscription, so like
+        // Followed we stop listening for Thread/Person/Hashtag events
+        Self::unsubscribe_transient_feeds(&self.current_feed_kind.read());
+    }
+
+    // Stop listening for the thread, person and hashtag feeds, other than the
+    // kind of feed we are switching to
+    fn unsubscribe_transient_feeds(except: &FeedKind) {
+        let feeds = [
+            (
+                matches!(except, FeedKind::Thread { .. }),
+                ToMinionPayloadDetail::UnsubscribeThreadFeed,
+            ),
+            (
+                matches!(except, FeedKind::Person(_)),
+                ToMinionPayloadDetail::UnsubscribePersonFeed,
+            ),
+            (
+                matches!(except, FeedKind::Hashtag(_)),
+                ToMinionPayloadDetail::UnsubscribeHashtagFeed,
+            ),
+        ];
+        for (keep, detail) in feeds {
+            if keep {
+                continue;
+            }
+            let _ = GLOBALS.to_minions.send(ToMinionMessage {
+                target: "all".to_string(),
+                payload: ToMinionPayload { job_id: 0, detail },
+            });
+        }
+    }
+
+    async fn load_bookmarked_events() -> Result<(), Error> {
+        let mut read_relays: Option<Vec<RelayUrl>> = None;
+        for id in GLOBALS.bookmarks.get() {
+            if GLOBALS.events.get_local(id).await?.is_none() {
+                let relays = read_relays.get_or_insert_with(|| {
+                    GLOBALS.relays_url_filtered(|r| r.has_usage_bits(DbRelay::READ))
+                });
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::FetchEvent(id, relays.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    async fn load_local_hashtag_events(hashtag: String) -> Result<(), Error> {
+        let events = DbEvent::search(EventSearch {
+            hashtag: Some(hashtag),
+            limit: 500,
+            ..Default::default()
+        })
+        .await?;
+        for event in events.iter() {
+            if !GLOBALS.events.contains_key(&event.id) {
+                crate::process::process_new_event(event, false, None, None).await?;
+                GLOBALS.events.insert(event.clone(), None);
+            }
+        }
+        Ok(())
+    }
+
     pub fn get_feed_kind(&self) -> FeedKind {
         self.current_feed_kind.read().to_owned()
     }
@@ -171,6 +291,104 @@ impl Feed {
         self.person_feed.read().clone()
     }
 
+    pub fn get_hashtag_feed(&self) -> Vec<Id> {
+        self.sync_maybe_periodic_recompute();
+        self.hashtag_feed.read().clone()
+    }
+
+    /// Bookmarked events we have, most recently bookmarked first
+    pub fn get_bookmarks_feed(&self) -> Vec<Id> {
+        self.sync_maybe_periodic_recompute();
+        self.bookmarks_feed.read().clone()
+    }
+
+    pub fn get_list_feed(&self) -> Vec<Id> {
+        self.sync_maybe_periodic_recompute();
+        self.list_feed.read().clone()
+    }
+
+    /// Results of the last local search, newest first
+    pub fn get_search_results(&self) -> Vec<Id> {
+        self.search_results.read().clone()
+    }
+
+    pub fn set_search_results(&self, ids: Vec<Id>) {
+        *self.search_results.write() = ids;
+    }
+
+    /// The relay search in progress, if any
+    pub fn get_relay_search(&self) -> Option<RelaySearch> {
+        self.relay_search.read().clone()
+    }
+
+    pub fn start_relay_search(&self, query: String) {
+        *self.relay_search.write() = Some(RelaySearch {
+            query,
+            ..Default::default()
+        });
+    }
+
+    pub fn close_relay_search(&self) {
+        *self.relay_search.write() = None;
+    }
+
+    pub fn relay_search_asked(&self, url: &RelayUrl) {
+        if let Some(search) = self.relay_search.write().as_mut() {
+            if !search.relays.contains(url) {
+                search.relays.push(url.to_owned());
+            }
+        }
+    }
+
+    pub fn add_relay_search_result(&self, event: &Event) {
+        if let Some(search) = self.relay_search.write().as_mut() {
+            if event.kind == EventKind::Metadata {
+                let pubkey: PublicKeyHex = event.pubkey.into();
+                if !search.people.contains(&pubkey) {
+                    search.people.push(pubkey);
+                }
+            } else if !search.note_ids.contains(&event.id) {
+                // Keep newest first
+                let position = search
+                    .note_ids
+                    .iter()
+                    .position(|id| match GLOBALS.events.get(id) {
+                        Some(e) => e.created_at < event.created_at,
+                        None => true,
+                    })
+                    .unwrap_or(search.note_ids.len());
+                search.note_ids.insert(position, event.id);
+            }
+        }
+    }
+
+    /// Direct message conversations, most recently active first
+    pub fn get_dm_channels(&self) -> Vec<DmChannel> {
+        self.sync_maybe_periodic_recompute();
+        self.dm_channels.read().clone()
+    }
+
+    pub fn get_dm_channel(&self, counterparty: &PublicKeyHex) -> Option<DmChannel> {
+        self.get_dm_channels()
+            .drain(..)
+            .find(|c| c.counterparty == *counterparty)
+    }
+
+    /// The encryption to use for our next message to `counterparty`: what we
+    /// chose for this conversation, else what they last used, else NIP-04
+    /// (which every client can read)
+    pub fn dm_outgoing_encryption(&self, counterparty: &PublicKeyHex) -> ContentEncryption {
+        if let Some(encryption) = GLOBALS.dm_encryption.get(counterparty) {
+            return *encryption;
+        }
+        self.dm_channels
+            .read()
+            .iter()
+            .find(|c| c.counterparty == *counterparty)
+            .and_then(|c| c.their_encryption)
+            .unwrap_or(ContentEncryption::Nip04)
+    }
+
     pub fn get_thread_parent(&self) -> Option<Id> {
         self.sync_maybe_periodic_recompute();
         *self.thread_parent.read()
@@ -258,6 +476,7 @@ impl Feed {
                     .filter(|e| e.kind != EventKind::EncryptedDirectMessage) // except DMs
                     .filter(|e| !e.kind.augments_feed_related()) // not augmenting another event
                     .filter(|e| !dismissed.contains(&e.id)) // not dismissed
+                    .filter(|e| !GLOBALS.mutes.is_muted(e)) // no muted words, hashtags or threads
                     .filter(|e| {
                         if !with_replies {
                             !matches!(e.replies_to(), Some((_id, _))) // is not a reply
@@ -293,6 +512,7 @@ impl Feed {
                         .filter(|e| !e.kind.augments_feed_related()) // not augmenting another event
                         .filter(|e| !dismissed.contains(&e.value().id)) // not dismissed
                         .filter(|e| e.value().pubkey != my_pubkey) // not self-authored
+                        .filter(|e| !GLOBALS.mutes.is_muted(e.value())) // no muted words, hashtags or threads
                         .filter(|e| {
                             // Include if it directly replies to one of my events
                             if let Some((id, _)) = e.value().replies_to() {
@@ -365,10 +585,136 @@ impl Feed {
 
                 *self.person_feed.write() = events.iter().map(|e| e.1).collect();
             }
+            FeedKind::Hashtag(hashtag) => {
+                let hashtag = hashtag.to_lowercase();
+                let mut events: Vec<(Unixtime, Id)> = GLOBALS
+                    .events
+                    .iter()
+                    .filter(|e| e.created_at <= now) // no future events
+                    .filter(|e| kinds.contains(&e.kind)) // feed related
+                    .filter(|e| !e.kind.augments_feed_related()) // not augmenting another event
+                    .filter(|e| e.kind != EventKind::EncryptedDirectMessage) // not DMs
+                    .filter(|e| {
+                        e.value()
+                            .hashtags()
+                            .iter()
+                            .any(|h| h.to_lowercase() == hashtag)
+                    })
+                    .filter(|e| !dismissed.contains(&e.value().id)) // not dismissed
+                    .filter(|e| !GLOBALS.mutes.is_muted(e.value())) // no muted words, hashtags or threads
+                    .map(|e| (e.value().created_at, e.value().id))
+                    .collect();
+
+                // Sort
+                events.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+
+                *self.hashtag_feed.write() = events.iter().map(|e| e.1).collect();
+            }
+            FeedKind::Bookmarks => {
+                let ids: Vec<Id> = GLOBALS
+                    .bookmarks
+                    .get()
+                    .drain(..)
+                    .filter(|id| GLOBALS.events.contains_key(id))
+                    .filter(|id| !dismissed.contains(id)) // not dismissed
+                    .collect();
+
+                *self.bookmarks_feed.write() = ids;
+            }
+            FeedKind::List(name) => {
+                let members = GLOBALS.person_lists.members(&name);
+
+                let mut events: Vec<(Unixtime, Id)> = GLOBALS
+                    .events
+                    .iter()
+                    .map(|r| r.value().to_owned())
+                    .filter(|e| e.created_at <= now) // no future events
+                    .filter(|e| kinds.contains(&e.kind)) // feed related
+                    .filter(|e| e.kind != EventKind::EncryptedDirectMessage) // except DMs
+                    .filter(|e| !e.kind.augments_feed_related()) // not augmenting another event
+                    .filter(|e| !dismissed.contains(&e.id)) // not dismissed
+                    .filter(|e| !GLOBALS.mutes.is_muted(e)) // no muted words, hashtags or threads
+                    .filter(|e| members.contains(&e.pubkey.into())) // someone on the list
+                    .map(|e| (e.created_at, e.id))
+                    .collect();
+
+                // Sort
+                events.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+
+                *self.list_feed.write() = events.iter().map(|e| e.1).collect();
+            }
         }
 
+        // DM conversations are shown outside of the feed, so we keep them
+        // current whatever the feed kind is
+        self.recompute_dm_channels(now);
+
         self.recompute_lock.store(false, Ordering::Relaxed);
 
         Ok(())
     }
+
+    fn recompute_dm_channels(&self, now: Unixtime) {
+        let my_pubkey: PublicKeyHex = match GLOBALS.signer.public_key() {
+            Some(pk) => pk.into(),
+            None => {
+                self.dm_channels.write().clear();
+                return;
+            }
+        };
+
+        let mut messages: HashMap<PublicKeyHex, Vec<(Unixtime, Id, bool, ContentEncryption)>> =
+            HashMap::new();
+        for e in GLOBALS.events.iter() {
+            let event = e.value();
+            if event.kind != EventKind::EncryptedDirectMessage || event.created_at > now {
+                continue;
+            }
+            let author: PublicKeyHex = event.pubkey.into();
+            let recipient: Option<PublicKeyHex> = event.tags.iter().find_map(|t| match t {
+                Tag::Pubkey { pubkey, .. } => Some(pubkey.clone()),
+                _ => None,
+            });
+
+            // The counterparty is the author, unless we are the author
+            let (counterparty, from_me) = if author == my_pubkey {
+                match recipient {
+                    Some(r) => (r, true),
+                    None => continue,
+                }
+            } else if recipient.as_ref() == Some(&my_pubkey) {
+                (author, false)
+            } else {
+                continue; // not ours
+            };
+
+            messages.entry(counterparty).or_default().push((
+                event.created_at,
+                event.id,
+                from_me,
+                ContentEncryption::detect(&event.content),
+            ));
+        }
+
+        let mut channels: Vec<DmChannel> = messages
+            .drain()
+            .map(|(counterparty, mut msgs)| {
+                msgs.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+                DmChannel {
+                    counterparty,
+                    latest_message: msgs.last().map(|m| m.0).unwrap_or(Unixtime(0)),
+                    unread: msgs
+                        .iter()
+                        .filter(|m| !m.2 && !GLOBALS.viewed_events.contains(&m.1))
+                        .count(),
+                    their_encryption: msgs.iter().rev().find(|m| !m.2).map(|m| m.3),
+                    message_ids: msgs.iter().map(|m| m.1).collect(),
+                }
+            })
+            .collect();
+
+        channels.sort_unstable_by(|a, b| b.latest_message.cmp(&a.latest_message));
+
+        *self.dm_channels.write() = channels;
+    }
 }
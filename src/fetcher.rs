@@ -203,4 +203,33 @@ impl Fetcher {
 
         Ok(())
     }
+
+    /// Get a JSON answer from a web service (e.g. a LNURL server), bypassing the cache
+    pub async fn get_json(url: url::Url) -> Result<serde_json::Value, Error> {
+        // Error if we are dead
+        if let Some(reason) = &GLOBALS.fetcher.dead {
+            return Err((format!("Fetcher is dead: {}", reason), file!(), line!()).into());
+        }
+
+        // Do not fetch if offline
+        if GLOBALS.settings.read().offline {
+            return Err(("You are offline", file!(), line!()).into());
+        }
+
+        let client = GLOBALS.fetcher.client.clone();
+
+        let req = client.get(url);
+
+        let req = if GLOBALS.settings.read().set_user_agent {
+            req.header("User-Agent", USER_AGENT)
+        } else {
+            req
+        };
+
+        let bytes = req.send().await?.bytes().await?;
+
+        GLOBALS.bytes_read.fetch_add(bytes.len(), Ordering::Relaxed);
+
+        Ok(serde_json::from_slice(&bytes)?)
+    }
 }
@@ -1,15 +1,27 @@
-use crate::comms::{RelayJob, ToMinionMessage, ToOverlordMessage};
-use crate::db::DbRelay;
+use crate::bookmarks::Bookmarks;
+use crate::comms::{PostingResponse, RelayJob, ToMinionMessage, ToOverlordMessage};
+use crate::db::{DbIdentity, DbPersonZapper, DbRelay, DbRelayMetric, PruneStats, RetentionPreview};
 use crate::delegation::Delegation;
+use crate::delivery::Deliveries;
 use crate::events::Events;
 use crate::feed::Feed;
 use crate::fetcher::Fetcher;
+use crate::long_form::LongForm;
 use crate::media::Media;
+use crate::mutes::Mutes;
+use crate::nip44::ContentEncryption;
+use crate::outbox::Outbox;
 use crate::people::People;
+use crate::person_lists::PersonLists;
+use crate::post_relays::PostRelay;
 use crate::relationship::Relationship;
+use crate::relay_notices::RelayNotices;
 use crate::relay_picker_hooks::Hooks;
+use crate::republish::Republish;
 use crate::settings::Settings;
 use crate::signer::Signer;
+use crate::wallet::Wallet;
+use crate::zap::ZapInvoice;
 use dashmap::{DashMap, DashSet};
 use gossip_relay_picker::RelayPicker;
 use nostr_types::{Event, Id, Profile, PublicKeyHex, RelayUrl};
@@ -36,6 +48,23 @@ pub struct Globals {
     /// To create a sender, just clone() it.
     pub to_overlord: mpsc::UnboundedSender<ToOverlordMessage>,
 
+    /// This is a broadcast channel. Minions send relay answers to our postings
+    /// on it. To create a receiver, just run .subscribe() on it.
+    pub posting_responses: broadcast::Sender<PostingResponse>,
+
+    /// This is a broadcast channel. The ids of the events we sign to post on the
+    /// user's command go out on it, so the command line can follow what it posted.
+    pub posted_events: broadcast::Sender<Id>,
+
+    /// What became of our posts at each relay
+    pub deliveries: Deliveries,
+
+    /// Our posts that relays have not taken yet
+    pub outbox: Outbox,
+
+    /// What relays have told us in NOTICE and CLOSED messages
+    pub relay_notices: RelayNotices,
+
     /// This is ephemeral. It is filled during lazy_static initialization,
     /// and stolen away when the Overlord is created.
     pub tmp_overlord_receiver: Mutex<Option<mpsc::UnboundedReceiver<ToOverlordMessage>>>,
@@ -44,6 +73,10 @@ pub struct Globals {
     /// information about if they are new or not, and functions
     pub events: Events,
 
+    /// This is a broadcast channel. Newly processed events are sent on it (only
+    /// while somebody is listening). To create a receiver, just run .subscribe() on it.
+    pub new_events: broadcast::Sender<Event>,
+
     /// Events coming in from relays that are not processed yet
     /// stored with Url they came from and Subscription they came in on
     pub incoming_events: RwLock<Vec<(Event, RelayUrl, Option<String>)>>,
@@ -80,9 +113,33 @@ pub struct Globals {
     /// Signer
     pub signer: Signer,
 
+    /// NIP-47 wallet connection
+    pub wallet: Wallet,
+
+    /// Our identities (only one is active at a time)
+    pub identities: PRwLock<Vec<DbIdentity>>,
+
     /// Dismissed Events
     pub dismissed: RwLock<Vec<Id>>,
 
+    /// Bookmarked Events
+    pub bookmarks: Bookmarks,
+
+    /// Muted words, hashtags and threads
+    pub mutes: Mutes,
+
+    /// Named lists of people
+    pub person_lists: PersonLists,
+
+    /// The relays the post being composed would go to, for the people it tags
+    pub post_relays: PRwLock<Option<(Vec<PublicKeyHex>, Vec<PostRelay>)>>,
+
+    /// Our long-form article drafts
+    pub long_form: LongForm,
+
+    /// Chosen encryption for outgoing direct messages, per conversation
+    pub dm_encryption: DashMap<PublicKeyHex, ContentEncryption>,
+
     /// Feed
     pub feed: Feed,
 
@@ -99,6 +156,28 @@ pub struct Globals {
     /// UI status message
     pub status_message: RwLock<String>,
 
+    /// What a prune under the retention policy being edited would delete
+    pub retention_preview: PRwLock<Option<RetentionPreview>>,
+
+    /// Database size and what pruning has reclaimed
+    pub prune_stats: PRwLock<PruneStats>,
+
+    /// Progress of republishing our history to a relay
+    pub republish: PRwLock<Option<Republish>>,
+
+    /// The health history of the relay shown on the relay health page, and
+    /// the time it goes back to
+    pub relay_metrics: PRwLock<Option<(RelayUrl, i64, Vec<DbRelayMetric>)>>,
+
+    /// The zap invoice being requested or shown
+    pub current_zap: PRwLock<Option<ZapInvoice>>,
+
+    /// Who signs the zap receipts of each person we asked the LNURL server of
+    pub zappers: DashMap<PublicKeyHex, DbPersonZapper>,
+
+    /// People whose LNURL server we are asking (or have asked) this run
+    pub zapper_lookups: DashSet<PublicKeyHex>,
+
     pub bytes_read: AtomicUsize,
 
     /// Delegation handling
@@ -117,13 +196,28 @@ lazy_static! {
         // Setup a communications channel from the Minions to the Overlord.
         let (to_overlord, tmp_overlord_receiver) = mpsc::unbounded_channel();
 
+        // Setup a communications channel for relay answers to our postings.
+        let (posting_responses, _) = broadcast::channel(256);
+
+        // Setup a communications channel for the ids of events we post.
+        let (posted_events, _) = broadcast::channel(256);
+
+        // Setup a communications channel for newly processed events.
+        let (new_events, _) = broadcast::channel(1024);
+
         Globals {
             first_run: AtomicBool::new(false),
             db: Mutex::new(crate::db::init_database().expect("Failed to setup database connection")),
             to_minions,
             to_overlord,
+            posting_responses,
+            posted_events,
+            deliveries: Deliveries::new(),
+            outbox: Outbox::new(),
+            relay_notices: RelayNotices::new(),
             tmp_overlord_receiver: Mutex::new(Some(tmp_overlord_receiver)),
             events: Events::new(),
+            new_events,
             incoming_events: RwLock::new(Vec::new()),
             viewed_events: DashSet::new(),
             new_viewed_events: RwLock::new(DashSet::new()),
@@ -135,12 +229,27 @@ lazy_static! {
             shutting_down: AtomicBool::new(false),
             settings: PRwLock::new(Settings::default()),
             signer: Signer::default(),
+            wallet: Wallet::default(),
+            identities: PRwLock::new(Vec::new()),
             dismissed: RwLock::new(Vec::new()),
+            bookmarks: Bookmarks::new(),
+            mutes: Mutes::new(),
+            person_lists: PersonLists::new(),
+            post_relays: PRwLock::new(None),
+            long_form: LongForm::new(),
+            dm_encryption: DashMap::new(),
             feed: Feed::new(),
             fetcher: Fetcher::new(),
             failed_avatars: RwLock::new(HashSet::new()),
             pixels_per_point_times_100: AtomicU32::new(139), // 100 dpi, 1/72th inch => 1.38888
             status_message: RwLock::new("Welcome to Gossip. Status messages will appear here. Click them to dismiss them.".to_owned()),
+            retention_preview: PRwLock::new(None),
+            prune_stats: PRwLock::new(PruneStats::default()),
+            republish: PRwLock::new(None),
+            relay_metrics: PRwLock::new(None),
+            current_zap: PRwLock::new(None),
+            zappers: DashMap::new(),
+            zapper_lookups: DashSet::new(),
             bytes_read: AtomicUsize::new(0),
             delegation: Delegation::default(),
             media: Media::new(),
@@ -231,6 +340,21 @@ impl Globals {
         (v, self_already_reacted)
     }
 
+    /// Returns the total millisats zapped to this event, and by how many zap receipts
+    pub fn get_zaps_sync(id: Id) -> (u64, usize) {
+        let mut total: u64 = 0;
+        let mut count: usize = 0;
+        if let Some(relationships) = GLOBALS.relationships.blocking_read().get(&id) {
+            for (_id, relationship) in relationships.iter() {
+                if let Relationship::ZapReceipt(msats) = relationship {
+                    total = total.saturating_add(*msats);
+                    count += 1;
+                }
+            }
+        }
+        (total, count)
+    }
+
     pub fn get_deletion_sync(id: Id) -> Option<String> {
         if let Some(relationships) = GLOBALS.relationships.blocking_read().get(&id) {
             for (_id, relationship) in relationships.iter() {
@@ -0,0 +1,202 @@
+use crate::db::DbLongFormDraft;
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventKind, PreEvent, Tag, Unixtime};
+use parking_lot::RwLock;
+
+/// The title, summary and such of a NIP-23 article, from its tags
+#[derive(Debug, Clone, Default)]
+pub struct LongFormMeta {
+    pub identifier: String,
+    pub title: String,
+    pub summary: String,
+    pub image: String,
+    pub published_at: Option<i64>,
+    pub hashtags: Vec<String>,
+}
+
+impl LongFormMeta {
+    pub fn from_event(event: &Event) -> LongFormMeta {
+        let mut meta = LongFormMeta::default();
+        for tag in event.tags.iter() {
+            // Read them as plain strings, whatever nostr_types makes of them
+            let v: Vec<String> = match serde_json::to_string(tag)
+                .ok()
+                .and_then(|s| serde_json::from_str(&s).ok())
+            {
+                Some(v) => v,
+                None => continue,
+            };
+            if v.len() < 2 {
+                continue;
+            }
+            match v[0].as_str() {
+                "d" => meta.identifier = v[1].clone(),
+                "title" => meta.title = v[1].clone(),
+                "summary" => meta.summary = v[1].clone(),
+                "image" => meta.image = v[1].clone(),
+                "published_at" => meta.published_at = v[1].trim().parse::<i64>().ok(),
+                "t" => meta.hashtags.push(v[1].clone()),
+                _ => (),
+            }
+        }
+        meta
+    }
+
+    /// When it was first published, or else when this version was
+    pub fn published_at(&self, event: &Event) -> i64 {
+        self.published_at.unwrap_or(event.created_at.0)
+    }
+
+    /// A draft to edit the article from, when we have none of our own
+    pub fn to_draft(&self, event: &Event) -> DbLongFormDraft {
+        DbLongFormDraft {
+            author: event.pubkey.as_hex_string(),
+            identifier: self.identifier.clone(),
+            title: self.title.clone(),
+            summary: self.summary.clone(),
+            image: self.image.clone(),
+            hashtags: self.hashtags.join(" "),
+            content: event.content.clone(),
+            published_at: Some(self.published_at(event)),
+            last_edit: event.created_at.0,
+        }
+    }
+}
+
+/// Our long-form drafts, most recently edited first
+pub struct LongForm {
+    drafts: RwLock<Vec<DbLongFormDraft>>,
+}
+
+impl Default for LongForm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LongForm {
+    pub fn new() -> LongForm {
+        LongForm {
+            drafts: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Load the drafts of our current identity (again, after switching identities)
+    pub async fn load(&self) -> Result<(), Error> {
+        let drafts = match GLOBALS.signer.public_key() {
+            Some(pk) => DbLongFormDraft::fetch_all(pk.into()).await?,
+            None => Vec::new(),
+        };
+        *self.drafts.write() = drafts;
+        Ok(())
+    }
+
+    pub fn drafts(&self) -> Vec<DbLongFormDraft> {
+        self.drafts.read().clone()
+    }
+
+    pub fn get(&self, identifier: &str) -> Option<DbLongFormDraft> {
+        self.drafts
+            .read()
+            .iter()
+            .find(|d| d.identifier == identifier)
+            .cloned()
+    }
+
+    /// A new, empty draft for our current identity, with an identifier of its own
+    pub fn new_draft() -> DbLongFormDraft {
+        DbLongFormDraft {
+            author: match GLOBALS.signer.public_key() {
+                Some(pk) => pk.as_hex_string(),
+                None => "".to_owned(),
+            },
+            identifier: format!("{:016x}", rand::random::<u64>()),
+            ..Default::default()
+        }
+    }
+
+    pub async fn save(&self, mut draft: DbLongFormDraft) -> Result<(), Error> {
+        // The editor may not know it has been published meanwhile
+        if draft.published_at.is_none() {
+            draft.published_at = self.get(&draft.identifier).and_then(|d| d.published_at);
+        }
+        draft.last_edit = Unixtime::now().unwrap().0;
+        DbLongFormDraft::save(draft.clone()).await?;
+        let mut drafts = self.drafts.write();
+        drafts.retain(|d| d.identifier != draft.identifier);
+        drafts.insert(0, draft);
+        Ok(())
+    }
+
+    pub async fn delete(&self, identifier: String) -> Result<(), Error> {
+        let public_key = match GLOBALS.signer.public_key() {
+            Some(pk) => pk,
+            None => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()),
+        };
+        DbLongFormDraft::delete(public_key.into(), identifier.clone()).await?;
+        self.drafts.write().retain(|d| d.identifier != identifier);
+        Ok(())
+    }
+
+    /// Save the draft and sign it as an article. An article keeps the
+    /// published_at of its first publication.
+    pub async fn generate_event(&self, mut draft: DbLongFormDraft) -> Result<Event, Error> {
+        let public_key = match GLOBALS.signer.public_key() {
+            Some(pk) => pk,
+            None => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()), // not even a public key
+        };
+
+        if draft.title.trim().is_empty() {
+            return Err(("An article needs a title.", file!(), line!()).into());
+        }
+
+        let now = Unixtime::now().unwrap();
+        if draft.published_at.is_none() {
+            draft.published_at = self.get(&draft.identifier).and_then(|d| d.published_at);
+        }
+        let published_at = *draft.published_at.get_or_insert(now.0);
+        self.save(draft.clone()).await?;
+
+        let mut tags: Vec<Tag> = vec![
+            Tag::Identifier(draft.identifier.clone()),
+            Tag::Other {
+                tag: "title".to_owned(),
+                data: vec![draft.title.trim().to_owned()],
+            },
+        ];
+        if !draft.summary.trim().is_empty() {
+            tags.push(Tag::Other {
+                tag: "summary".to_owned(),
+                data: vec![draft.summary.trim().to_owned()],
+            });
+        }
+        if !draft.image.trim().is_empty() {
+            tags.push(Tag::Other {
+                tag: "image".to_owned(),
+                data: vec![draft.image.trim().to_owned()],
+            });
+        }
+        tags.push(Tag::Other {
+            tag: "published_at".to_owned(),
+            data: vec![format!("{}", published_at)],
+        });
+        for hashtag in draft.hashtags.split_whitespace() {
+            let hashtag = hashtag.trim_start_matches('#').to_lowercase();
+            if !hashtag.is_empty() {
+                tags.push(Tag::Hashtag(hashtag));
+            }
+        }
+
+        let pre_event = PreEvent {
+            pubkey: public_key,
+            created_at: now,
+            kind: EventKind::LongFormContent,
+            tags,
+            content: draft.content,
+            ots: None,
+        };
+
+        GLOBALS.signer.sign_preevent(pre_event, None, None).await
+    }
+}
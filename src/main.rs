@@ -23,26 +23,43 @@ macro_rules! rtry {
 extern crate lazy_static;
 
 mod about;
+mod archive;
+mod bookmarks;
+mod cli;
 mod comms;
+mod control;
 mod date_ago;
 mod db;
 mod delegation;
+mod delivery;
 mod error;
 mod events;
 mod feed;
 mod fetcher;
 mod globals;
+mod long_form;
 mod media;
+mod mutes;
 mod nip05;
+mod nip44;
+mod nip46;
+mod nip47;
+mod outbox;
 mod overlord;
 mod people;
+mod person_lists;
+mod post_relays;
 mod process;
 mod relationship;
+mod relay_notices;
 mod relay_picker_hooks;
+mod republish;
 mod settings;
 mod signer;
 mod tags;
 mod ui;
+mod wallet;
+mod zap;
 
 use crate::comms::ToOverlordMessage;
 use crate::error::Error;
@@ -57,6 +74,21 @@ pub const AVATAR_SIZE_F32: f32 = 48.0; // points, not pixels
 static USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
 
 fn main() -> Result<(), Error> {
+    // Check command line arguments
+    let args: Vec<String> = env::args().skip(1).collect();
+    let headless = args.iter().any(|arg| arg == "--headless");
+    let command = match crate::cli::Command::parse(&args) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, crate::cli::USAGE);
+            std::process::exit(1);
+        }
+    };
+    if let Some(crate::cli::Command::Help) = command {
+        println!("{}", crate::cli::USAGE);
+        return Ok(());
+    }
+
     if env::var("RUST_LOG").is_err() {
         env::set_var("RUST_LOG", "info");
     }
@@ -91,16 +123,32 @@ fn main() -> Result<(), Error> {
     // separate task. This leave the main thread for UI work only.
     // egui is most portable when it is on the main thread.
     let async_thread = thread::spawn(move || {
-        rt.block_on(tokio_main());
+        rt.block_on(tokio_main(headless));
     });
 
-    if let Err(e) = ui::run() {
-        tracing::error!("{}", e);
-    }
+    if let Some(command) = command {
+        // Run the command in place of the UI
+        if let Err(e) = crate::cli::run(command) {
+            eprintln!("{}", e);
+        }
 
-    // Tell the async parties to close down
-    if let Err(e) = initiate_shutdown() {
-        tracing::error!("{}", e);
+        // Tell the async parties to close down
+        if let Err(e) = initiate_shutdown() {
+            tracing::error!("{}", e);
+        }
+    } else if headless {
+        // There is no UI. The async side shuts itself down when it gets a
+        // termination signal, so we just wait for it below.
+        tracing::info!("Running headless. Send SIGTERM or SIGINT to stop.");
+    } else {
+        if let Err(e) = ui::run() {
+            tracing::error!("{}", e);
+        }
+
+        // Tell the async parties to close down
+        if let Err(e) = initiate_shutdown() {
+            tracing::error!("{}", e);
+        }
     }
 
     // Wait for the async thread to complete
@@ -109,10 +157,24 @@ fn main() -> Result<(), Error> {
     Ok(())
 }
 
-async fn tokio_main() {
+async fn tokio_main(headless: bool) {
     // Start periodic tasks in people manager
     crate::people::People::start();
 
+    // Start the local control API, if enabled
+    crate::control::start();
+
+    // Without a UI, a termination signal is our only way to be told to stop
+    if headless {
+        std::mem::drop(tokio::spawn(async move {
+            wait_for_termination_signal().await;
+            tracing::info!("Termination signal received, shutting down.");
+            if let Err(e) = initiate_shutdown() {
+                tracing::error!("{}", e);
+            }
+        }));
+    }
+
     // Steal `tmp_overlord_receiver` from the GLOBALS, and give it to a new Overlord
     let overlord_receiver = {
         let mut mutex_option = GLOBALS.tmp_overlord_receiver.lock().await;
@@ -125,6 +187,30 @@ async fn tokio_main() {
     overlord.run().await;
 }
 
+#[cfg(unix)]
+async fn wait_for_termination_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sigterm = match signal(SignalKind::terminate()) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::error!("Unable to listen for SIGTERM: {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        }
+    };
+
+    tokio::select! {
+        _ = sigterm.recv() => {},
+        _ = tokio::signal::ctrl_c() => {},
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_termination_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
 // Any task can call this to shutdown
 pub fn initiate_shutdown() -> Result<(), Error> {
     let to_overlord = GLOBALS.to_overlord.clone();
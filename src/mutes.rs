@@ -0,0 +1,392 @@
+use crate::db::{DbEvent, DbMute, MuteKind};
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventKind, Id, PreEvent, PublicKeyHex, Tag, Unixtime};
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+use tokio::task;
+
+/// NIP-51 mute list
+pub const MUTE_LIST_KIND: u64 = 10000;
+
+pub fn mute_list_kind() -> EventKind {
+    EventKind::from(MUTE_LIST_KIND)
+}
+
+/// Everything a mute list mutes, public and private together
+#[derive(Debug, Default)]
+pub struct MuteListItems {
+    pub pubkeys: Vec<PublicKeyHex>,
+    pub words: Vec<String>,
+    pub hashtags: Vec<String>,
+    pub threads: Vec<Id>,
+}
+
+impl MuteListItems {
+    fn take_tags(&mut self, tags: &[Tag]) {
+        for tag in tags.iter() {
+            match tag {
+                Tag::Pubkey { pubkey, .. } => self.pubkeys.push(pubkey.to_owned()),
+                Tag::Hashtag(hashtag) => self.hashtags.push(hashtag.to_lowercase()),
+                Tag::Event { id, .. } => self.threads.push(*id),
+                Tag::Other { tag, data } if tag == "word" && !data.is_empty() => {
+                    self.words.push(data[0].to_lowercase())
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn to_tags(&self) -> Vec<Tag> {
+        let mut tags: Vec<Tag> = Vec::new();
+        for pubkey in self.pubkeys.iter() {
+            tags.push(Tag::Pubkey {
+                pubkey: pubkey.to_owned(),
+                recommended_relay_url: None,
+                petname: None,
+            });
+        }
+        for hashtag in self.hashtags.iter() {
+            tags.push(Tag::Hashtag(hashtag.to_owned()));
+        }
+        for word in self.words.iter() {
+            tags.push(Tag::Other {
+                tag: "word".to_owned(),
+                data: vec![word.to_owned()],
+            });
+        }
+        for id in self.threads.iter() {
+            tags.push(Tag::Event {
+                id: *id,
+                recommended_relay_url: None,
+                marker: None,
+            });
+        }
+        tags
+    }
+
+    /// Read a mute list, decrypting its private part
+    pub async fn from_event(event: &Event) -> Result<MuteListItems, Error> {
+        let mut items = MuteListItems::default();
+        items.take_tags(&event.tags);
+        if !event.content.is_empty() {
+            let plaintext = GLOBALS.signer.decrypt_from_self(&event.content).await?;
+            let tags: Vec<Tag> = serde_json::from_str(&plaintext)?;
+            items.take_tags(&tags);
+        }
+        Ok(items)
+    }
+}
+
+/// Our muted words, hashtags and threads, and what we know of our published mute
+/// list. Muted people are kept with the people.
+pub struct Mutes {
+    words: RwLock<Vec<String>>,
+    hashtags: RwLock<Vec<String>>,
+    threads: RwLock<Vec<Id>>,
+
+    /// The created_at of the last mute list we fetched from relays
+    pub last_mute_list_asof: AtomicI64,
+
+    /// The number of public entries in that list
+    pub last_mute_list_size: AtomicUsize,
+
+    /// When we last changed our mutes here
+    pub last_mute_list_edit: AtomicI64,
+}
+
+impl Default for Mutes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mutes {
+    pub fn new() -> Mutes {
+        Mutes {
+            words: RwLock::new(Vec::new()),
+            hashtags: RwLock::new(Vec::new()),
+            threads: RwLock::new(Vec::new()),
+            last_mute_list_asof: AtomicI64::new(0),
+            last_mute_list_size: AtomicUsize::new(0),
+            last_mute_list_edit: AtomicI64::new(0),
+        }
+    }
+
+    /// Load mutes from the database (again, after switching identities)
+    pub async fn load(&self) -> Result<(), Error> {
+        self.load_lists().await?;
+
+        self.last_mute_list_asof.store(0, Ordering::Relaxed);
+        self.last_mute_list_size.store(0, Ordering::Relaxed);
+        let last_edit = task::spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            db.query_row(
+                "SELECT last_mute_list_edit FROM local_settings LIMIT 1",
+                [],
+                |row| row.get::<usize, i64>(0),
+            )
+        })
+        .await??;
+        self.last_mute_list_edit.store(last_edit, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    // Load just the words, hashtags and threads
+    async fn load_lists(&self) -> Result<(), Error> {
+        let mut words: Vec<String> = Vec::new();
+        let mut hashtags: Vec<String> = Vec::new();
+        let mut threads: Vec<Id> = Vec::new();
+        for mute in DbMute::load_all().await?.drain(..) {
+            match mute.kind {
+                MuteKind::Word => words.push(mute.value),
+                MuteKind::Hashtag => hashtags.push(mute.value),
+                MuteKind::Thread => {
+                    if let Ok(id) = Id::try_from_hex_string(&mute.value) {
+                        threads.push(id);
+                    }
+                }
+            }
+        }
+        *self.words.write() = words;
+        *self.hashtags.write() = hashtags;
+        *self.threads.write() = threads;
+        Ok(())
+    }
+
+    pub fn get_words(&self) -> Vec<String> {
+        self.words.read().clone()
+    }
+
+    pub fn get_hashtags(&self) -> Vec<String> {
+        self.hashtags.read().clone()
+    }
+
+    pub fn get_threads(&self) -> Vec<Id> {
+        self.threads.read().clone()
+    }
+
+    /// Whether a word, hashtag or thread we muted covers this event. Muted
+    /// authors are checked separately, against the person.
+    pub fn is_muted(&self, event: &Event) -> bool {
+        {
+            let threads = self.threads.read();
+            if !threads.is_empty() {
+                if threads.contains(&event.id) {
+                    return true;
+                }
+                if let Some((root, _)) = event.replies_to_root() {
+                    if threads.contains(&root) {
+                        return true;
+                    }
+                }
+                if let Some((parent, _)) = event.replies_to() {
+                    if threads.contains(&parent) {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        {
+            let hashtags = self.hashtags.read();
+            if !hashtags.is_empty()
+                && event
+                    .hashtags()
+                    .iter()
+                    .any(|h| hashtags.contains(&h.to_lowercase()))
+            {
+                return true;
+            }
+        }
+
+        let words = self.words.read();
+        if !words.is_empty() {
+            let content = event.content.to_lowercase();
+            if words.iter().any(|w| contains_word(&content, w)) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    pub async fn add(&self, kind: MuteKind, value: String) -> Result<(), Error> {
+        let value = match kind {
+            MuteKind::Word => value.trim().to_lowercase(),
+            MuteKind::Hashtag => value.trim().trim_start_matches('#').to_lowercase(),
+            MuteKind::Thread => value,
+        };
+        if value.is_empty() {
+            return Ok(());
+        }
+        DbMute::insert(DbMute { kind, value }).await?;
+        self.load_lists().await?;
+        self.mark_edited().await
+    }
+
+    pub async fn remove(&self, kind: MuteKind, value: String) -> Result<(), Error> {
+        DbMute::delete(DbMute { kind, value }).await?;
+        self.load_lists().await?;
+        self.mark_edited().await
+    }
+
+    /// Record that our mutes changed here, so they are newer than the list on relays
+    pub async fn mark_edited(&self) -> Result<(), Error> {
+        self.set_last_edit(Unixtime::now().unwrap()).await
+    }
+
+    /// Record when our mutes here last changed. Publishing our list counts, as
+    /// relays then hold exactly what we have.
+    pub async fn set_last_edit(&self, when: Unixtime) -> Result<(), Error> {
+        self.last_mute_list_edit.store(when.0, Ordering::Relaxed);
+        task::spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            db.execute("UPDATE local_settings SET last_mute_list_edit=?", (when.0,))?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+        Ok(())
+    }
+
+    /// Note a mute list of ours that came in from a relay. Like the contact
+    /// list, it is not applied until the user says so.
+    pub fn note_remote_list(&self, event: &Event) {
+        if event.created_at.0 > self.last_mute_list_asof.load(Ordering::Relaxed) {
+            self.last_mute_list_asof
+                .store(event.created_at.0, Ordering::Relaxed);
+            self.last_mute_list_size
+                .store(event.tags.len(), Ordering::Relaxed);
+        }
+    }
+
+    /// Whether relays have a mute list of ours newer than our last change here
+    pub fn remote_is_newer(&self) -> bool {
+        self.last_mute_list_asof.load(Ordering::Relaxed)
+            > self.last_mute_list_edit.load(Ordering::Relaxed)
+    }
+
+    /// Apply our latest mute list from relays. With merge we only add to our
+    /// mutes, otherwise our mutes become exactly that list.
+    pub async fn update_from_list(&self, merge: bool) -> Result<(), Error> {
+        let event = match last_mute_list().await? {
+            Some(event) => event,
+            None => return Ok(()), // we have no mute list to update from
+        };
+
+        let items = MuteListItems::from_event(&event).await?;
+
+        GLOBALS.people.mute_all(&items.pubkeys, merge).await?;
+
+        if !merge {
+            DbMute::delete_all().await?;
+        }
+        for value in items.words.iter() {
+            DbMute::insert(DbMute {
+                kind: MuteKind::Word,
+                value: value.to_owned(),
+            })
+            .await?;
+        }
+        for value in items.hashtags.iter() {
+            DbMute::insert(DbMute {
+                kind: MuteKind::Hashtag,
+                value: value.to_owned(),
+            })
+            .await?;
+        }
+        for id in items.threads.iter() {
+            DbMute::insert(DbMute {
+                kind: MuteKind::Thread,
+                value: id.as_hex_string(),
+            })
+            .await?;
+        }
+        self.load_lists().await?;
+
+        let last_edit = if merge {
+            Unixtime::now().unwrap() // now, since superior to the last event
+        } else {
+            event.created_at
+        };
+        self.set_last_edit(last_edit).await
+    }
+
+    /// Sign a mute list of our current mutes, in the tags or, if the user keeps
+    /// it private, encrypted to ourselves in the content
+    pub async fn generate_list_event(&self) -> Result<Event, Error> {
+        let public_key = match GLOBALS.signer.public_key() {
+            Some(pk) => pk,
+            None => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()), // not even a public key
+        };
+
+        let items = MuteListItems {
+            pubkeys: GLOBALS
+                .people
+                .get_all()
+                .drain(..)
+                .filter(|p| p.muted == 1)
+                .map(|p| p.pubkey)
+                .collect(),
+            words: self.get_words(),
+            hashtags: self.get_hashtags(),
+            threads: self.get_threads(),
+        };
+
+        let (tags, content) = if GLOBALS.settings.read().mute_list_private {
+            let private = serde_json::to_string(&items.to_tags())?;
+            (Vec::new(), GLOBALS.signer.encrypt_to_self(&private).await?)
+        } else {
+            (items.to_tags(), "".to_owned())
+        };
+
+        let pre_event = PreEvent {
+            pubkey: public_key,
+            created_at: Unixtime::now().unwrap(),
+            kind: mute_list_kind(),
+            tags,
+            content,
+            ots: None,
+        };
+
+        GLOBALS.signer.sign_preevent(pre_event, None, None).await
+    }
+}
+
+/// Whether the word (or phrase) appears in the text as a whole word, so that
+/// muting "cat" does not hide posts about "education"
+fn contains_word(text: &str, word: &str) -> bool {
+    if word.is_empty() {
+        return false;
+    }
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let word_starts = word.chars().next().map(is_word_char).unwrap_or(false);
+    let word_ends = word.chars().next_back().map(is_word_char).unwrap_or(false);
+    text.match_indices(word).any(|(start, _)| {
+        let end = start + word.len();
+        let before = text[..start].chars().next_back();
+        let after = text[end..].chars().next();
+        !(word_starts && before.map(is_word_char).unwrap_or(false))
+            && !(word_ends && after.map(is_word_char).unwrap_or(false))
+    })
+}
+
+/// Our latest mute list that we have stored
+pub async fn last_mute_list() -> Result<Option<Event>, Error> {
+    let public_key = match GLOBALS.signer.public_key() {
+        Some(pk) => pk,
+        None => return Ok(None),
+    };
+    match DbEvent::fetch(Some(&format!(
+        "pubkey='{}' AND kind={} ORDER BY created_at DESC LIMIT 1",
+        public_key.as_hex_string(),
+        MUTE_LIST_KIND
+    )))
+    .await?
+    .pop()
+    {
+        Some(dbevent) => Ok(Some(serde_json::from_str(&dbevent.raw)?)),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,435 @@
+// NIP-44 versioned encryption (version 2)
+//
+// conversation_key = HKDF-extract(salt="nip44-v2", ikm=ECDH(a, B).x)
+// per message: chacha_key || chacha_nonce || hmac_key = HKDF-expand(conversation_key, nonce, 76)
+// payload = base64(0x02 || nonce || chacha20(pad(plaintext)) || HMAC-SHA256(hmac_key, nonce || ciphertext))
+
+use crate::error::Error;
+use base64::Engine;
+use chacha20::cipher::{KeyIvInit, StreamCipher};
+use chacha20::ChaCha20;
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use nostr_types::PublicKey;
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+const VERSION: u8 = 2;
+const SALT: &[u8] = b"nip44-v2";
+const MIN_PLAINTEXT_LEN: usize = 1;
+const MAX_PLAINTEXT_LEN: usize = 65535;
+
+/// How the content of a direct message is encrypted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContentEncryption {
+    Nip04,
+    Nip44,
+}
+
+impl ContentEncryption {
+    /// Detect the encryption used by a message from its payload. NIP-04
+    /// payloads carry their IV after "?iv=", NIP-44 payloads are plain base64
+    /// starting with a version byte.
+    pub fn detect(content: &str) -> ContentEncryption {
+        if content.contains("?iv=") {
+            ContentEncryption::Nip04
+        } else {
+            ContentEncryption::Nip44
+        }
+    }
+
+    /// The number we store in the database (the NIP number)
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            ContentEncryption::Nip04 => 4,
+            ContentEncryption::Nip44 => 44,
+        }
+    }
+
+    pub fn from_u8(u: u8) -> Option<ContentEncryption> {
+        match u {
+            4 => Some(ContentEncryption::Nip04),
+            44 => Some(ContentEncryption::Nip44),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ContentEncryption::Nip04 => "NIP-04",
+            ContentEncryption::Nip44 => "NIP-44",
+        }
+    }
+}
+
+/// Compute the conversation key shared between our secret key (hex) and their
+/// public key. This is the same in both directions.
+pub fn conversation_key(secret_hex: &str, other: &PublicKey) -> Result<[u8; 32], Error> {
+    let mut secret_bytes =
+        hex::decode(secret_hex).map_err(|_| Error::from("Invalid secret key"))?;
+    let secret = k256::SecretKey::from_slice(&secret_bytes);
+    secret_bytes.zeroize();
+    let secret = secret.map_err(|_| Error::from("Invalid secret key"))?;
+
+    // Nostr public keys are x-only, so we take the even y
+    let mut sec1 = vec![0x02];
+    sec1.extend(hex::decode(other.as_hex_string()).map_err(|_| Error::from("Invalid public key"))?);
+    let other =
+        k256::PublicKey::from_sec1_bytes(&sec1).map_err(|_| Error::from("Invalid public key"))?;
+
+    let shared = k256::ecdh::diffie_hellman(secret.to_nonzero_scalar(), other.as_affine());
+    let (prk, _) = Hkdf::<Sha256>::extract(Some(SALT), shared.raw_secret_bytes());
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&prk);
+    Ok(key)
+}
+
+/// Encrypt plaintext under a conversation key, returning the base64 payload
+pub fn encrypt(conversation_key: &[u8; 32], plaintext: &str) -> Result<String, Error> {
+    let nonce: [u8; 32] = rand::random();
+    encrypt_with_nonce(conversation_key, plaintext, &nonce)
+}
+
+fn encrypt_with_nonce(
+    conversation_key: &[u8; 32],
+    plaintext: &str,
+    nonce: &[u8; 32],
+) -> Result<String, Error> {
+    let (mut chacha_key, chacha_nonce, mut hmac_key) = message_keys(conversation_key, nonce)?;
+
+    let mut buffer = pad(plaintext.as_bytes())?;
+    let mut cipher = ChaCha20::new(&chacha_key.into(), &chacha_nonce.into());
+    cipher.apply_keystream(&mut buffer);
+
+    let mac = hmac_aad(&hmac_key, nonce, &buffer)?;
+    chacha_key.zeroize();
+    hmac_key.zeroize();
+
+    let mut payload: Vec<u8> = Vec::with_capacity(1 + 32 + buffer.len() + 32);
+    payload.push(VERSION);
+    payload.extend_from_slice(nonce);
+    payload.extend_from_slice(&buffer);
+    payload.extend_from_slice(&mac);
+
+    Ok(base64::engine::general_purpose::STANDARD.encode(payload))
+}
+
+/// Decrypt a base64 payload under a conversation key
+pub fn decrypt(conversation_key: &[u8; 32], payload: &str) -> Result<String, Error> {
+    if payload.starts_with('#') {
+        return Err("Unsupported NIP-44 encryption version")?;
+    }
+    if payload.len() < 132 || payload.len() > 87472 {
+        return Err("Invalid NIP-44 payload size")?;
+    }
+
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(payload)
+        .map_err(|_| Error::from("Invalid NIP-44 payload encoding"))?;
+    if data.len() < 99 || data.len() > 65603 {
+        return Err("Invalid NIP-44 payload size")?;
+    }
+    if data[0] != VERSION {
+        return Err(format!("Unsupported NIP-44 encryption version {}", data[0]))?;
+    }
+
+    let mut nonce = [0u8; 32];
+    nonce.copy_from_slice(&data[1..33]);
+    let ciphertext = &data[33..data.len() - 32];
+    let mac = &data[data.len() - 32..];
+
+    let (mut chacha_key, chacha_nonce, mut hmac_key) = message_keys(conversation_key, &nonce)?;
+
+    let mut hmac = <Hmac<Sha256> as Mac>::new_from_slice(&hmac_key)
+        .map_err(|_| Error::from("Invalid HMAC key"))?;
+    hmac.update(&nonce);
+    hmac.update(ciphertext);
+    let verified = hmac.verify_slice(mac).is_ok();
+    hmac_key.zeroize();
+    if !verified {
+        chacha_key.zeroize();
+        return Err("NIP-44 message authentication failed")?;
+    }
+
+    let mut buffer = ciphertext.to_vec();
+    let mut cipher = ChaCha20::new(&chacha_key.into(), &chacha_nonce.into());
+    cipher.apply_keystream(&mut buffer);
+    chacha_key.zeroize();
+
+    let plaintext = unpad(&buffer)?;
+    buffer.zeroize();
+    Ok(plaintext)
+}
+
+// chacha_key, chacha_nonce, hmac_key
+type MessageKeys = ([u8; 32], [u8; 12], [u8; 32]);
+
+fn message_keys(conversation_key: &[u8; 32], nonce: &[u8; 32]) -> Result<MessageKeys, Error> {
+    let hkdf = Hkdf::<Sha256>::from_prk(conversation_key)
+        .map_err(|_| Error::from("Invalid NIP-44 conversation key"))?;
+    let mut keys = [0u8; 76];
+    hkdf.expand(nonce, &mut keys)
+        .map_err(|_| Error::from("NIP-44 key expansion failed"))?;
+
+    let mut chacha_key = [0u8; 32];
+    let mut chacha_nonce = [0u8; 12];
+    let mut hmac_key = [0u8; 32];
+    chacha_key.copy_from_slice(&keys[0..32]);
+    chacha_nonce.copy_from_slice(&keys[32..44]);
+    hmac_key.copy_from_slice(&keys[44..76]);
+    keys.zeroize();
+
+    Ok((chacha_key, chacha_nonce, hmac_key))
+}
+
+fn hmac_aad(hmac_key: &[u8; 32], nonce: &[u8; 32], ciphertext: &[u8]) -> Result<[u8; 32], Error> {
+    let mut hmac = <Hmac<Sha256> as Mac>::new_from_slice(hmac_key)
+        .map_err(|_| Error::from("Invalid HMAC key"))?;
+    hmac.update(nonce);
+    hmac.update(ciphertext);
+    let mut mac = [0u8; 32];
+    mac.copy_from_slice(&hmac.finalize().into_bytes());
+    Ok(mac)
+}
+
+fn padded_len(unpadded_len: usize) -> usize {
+    if unpadded_len <= 32 {
+        return 32;
+    }
+    let next_power = 1_usize << (usize::BITS - (unpadded_len - 1).leading_zeros());
+    let chunk = if next_power <= 256 {
+        32
+    } else {
+        next_power / 8
+    };
+    chunk * ((unpadded_len - 1) / chunk + 1)
+}
+
+fn pad(plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+    let len = plaintext.len();
+    if !(MIN_PLAINTEXT_LEN..=MAX_PLAINTEXT_LEN).contains(&len) {
+        return Err("Message is empty or too long to encrypt")?;
+    }
+    let mut padded: Vec<u8> = Vec::with_capacity(2 + padded_len(len));
+    padded.extend_from_slice(&(len as u16).to_be_bytes());
+    padded.extend_from_slice(plaintext);
+    padded.resize(2 + padded_len(len), 0);
+    Ok(padded)
+}
+
+fn unpad(padded: &[u8]) -> Result<String, Error> {
+    if padded.len() < 2 {
+        return Err("Invalid NIP-44 padding")?;
+    }
+    let len = u16::from_be_bytes([padded[0], padded[1]]) as usize;
+    if len < MIN_PLAINTEXT_LEN || padded.len() != 2 + padded_len(len) {
+        return Err("Invalid NIP-44 padding")?;
+    }
+    String::from_utf8(padded[2..2 + len].to_vec())
+        .map_err(|_| Error::from("NIP-44 message is not UTF-8"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Vectors from the official NIP-44 v2 test vectors (nip44.vectors.json)
+
+    fn key(hex_str: &str) -> [u8; 32] {
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&hex::decode(hex_str).unwrap());
+        key
+    }
+
+    fn pubkey(hex_str: &str) -> PublicKey {
+        PublicKey::try_from_hex_string(hex_str).unwrap()
+    }
+
+    // sec1, pub2, conversation_key
+    const CONVERSATION_KEYS: [(&str, &str, &str); 3] = [
+        (
+            "315e59ff51cb9209768cf7da80791ddcaae56ac9775eb25b6dee1234bc5d2268",
+            "c2f9d9948dc8c7c38321e4b85c8558872eafa0641cd269db76848a6073e69133",
+            "3dfef0ce2a4d80a25e7a328accf73448ef67096f65f79588e358d9a0eb9013f1",
+        ),
+        (
+            "a1e37752c9fdc1273be53f68c5f74be7c8905728e8de75800b94262f9497c86e",
+            "03bb7947065dde12ba991ea045132581d0954f042c84e06d8c00066e23c1a800",
+            "4d14f36e81b8452128da64fe6f1eae873baae2f444b02c950b90e43553f2178b",
+        ),
+        (
+            "98a5902fd67518a0c900f0fb62158f278f94a21d6f9d33d30cd3091195500311",
+            "aae65c15f98e5e677b5050de82e3aba47a6fe49b3dab7863cf35d9478ba9f7d1",
+            "9c00b769d5f54d02bf175b7284a1cbd28b6911b06cda6666b2243561ac96bad7",
+        ),
+    ];
+
+    // sec1, pub2 (of sec2), conversation_key, nonce, plaintext, payload
+    const ENCRYPT_DECRYPT: [(&str, &str, &str, &str, &str, &str); 3] = [
+        (
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+            "c41c775356fd92eadc63ff5a0dc1da211b268cbea22316767095b2871ea1412d",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "a",
+            "AgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABee0G5VSK0/9YypIObAtDKfYEAjD35uVkHyB0F4DwrcNaCXlCWZKaArsGrY6M9wnuTMxWfp1RTN9Xga8no+kF5Vsb",
+        ),
+        (
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            "c41c775356fd92eadc63ff5a0dc1da211b268cbea22316767095b2871ea1412d",
+            "f00000000000000000000000000000f00000000000000000000000000000000f",
+            "🍕🫃",
+            "AvAAAAAAAAAAAAAAAAAAAPAAAAAAAAAAAAAAAAAAAAAPSKSK6is9ngkX2+cSq85Th16oRTISAOfhStnixqZziKMDvB0QQzgFZdjLTPicCJaV8nDITO+QfaQ61+KbWQIOO2Yj",
+        ),
+        (
+            "5c0c523f52a5b6fad39ed2403092df8cebc36318b39383bca6c00808626fab3a",
+            "fa3b4f81a620c66514bda0302847df167ed02a483141b5939e57bdd0cf76ad3b",
+            "3e2b52a63be47d34fe0a80e34e73d436d6963bc8f39827f327057a9986c20a45",
+            "b635236c42db20f021bb8d1cdff5ca75dd1a0cc72ea742ad750f33010b24f73b",
+            "表ポあA鷗ŒéＢ逍Üßªąñ丂㐀𠀀",
+            "ArY1I2xC2yDwIbuNHN/1ynXdGgzHLqdCrXUPMwELJPc7s7JqlCMJBAIIjfkpHReBPXeoMCyuClwgbT419jUWU1PwaNl4FEQYKCDKVJz+97Mp3K+Q2YGa77B6gpxB/lr1QgoqpDf7wDVrDmOqGoiPjWDqy8KzLueKDcm9BVP8xeTJIxs=",
+        ),
+    ];
+
+    #[test]
+    fn test_conversation_key() {
+        for (sec1, pub2, expected) in CONVERSATION_KEYS.iter() {
+            let ck = conversation_key(sec1, &pubkey(pub2)).unwrap();
+            assert_eq!(hex::encode(ck), *expected);
+        }
+        for (sec1, pub2, expected, _, _, _) in ENCRYPT_DECRYPT.iter() {
+            let ck = conversation_key(sec1, &pubkey(pub2)).unwrap();
+            assert_eq!(hex::encode(ck), *expected);
+        }
+    }
+
+    #[test]
+    fn test_conversation_key_invalid() {
+        let pub2 = pubkey(CONVERSATION_KEYS[0].1);
+        // Zero, and not below the curve order
+        assert!(conversation_key(&"00".repeat(32), &pub2).is_err());
+        assert!(conversation_key(&"ff".repeat(32), &pub2).is_err());
+        assert!(conversation_key("not hex", &pub2).is_err());
+        // x = 0 is not on the curve
+        if let Ok(pub2) = PublicKey::try_from_hex_string(&"00".repeat(32)) {
+            assert!(conversation_key(CONVERSATION_KEYS[0].0, &pub2).is_err());
+        }
+    }
+
+    #[test]
+    fn test_padded_len() {
+        let vectors: [(usize, usize); 24] = [
+            (16, 32),
+            (32, 32),
+            (33, 64),
+            (37, 64),
+            (45, 64),
+            (49, 64),
+            (64, 64),
+            (65, 96),
+            (100, 128),
+            (111, 128),
+            (200, 224),
+            (250, 256),
+            (320, 320),
+            (383, 384),
+            (384, 384),
+            (400, 448),
+            (500, 512),
+            (512, 512),
+            (515, 640),
+            (700, 768),
+            (800, 896),
+            (900, 1024),
+            (1020, 1024),
+            (65536, 65536),
+        ];
+        for (unpadded, padded) in vectors.iter() {
+            assert_eq!(padded_len(*unpadded), *padded);
+        }
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        for (_, _, ck, nonce, plaintext, payload) in ENCRYPT_DECRYPT.iter() {
+            let ck = key(ck);
+            assert_eq!(
+                encrypt_with_nonce(&ck, plaintext, &key(nonce)).unwrap(),
+                *payload
+            );
+            assert_eq!(decrypt(&ck, payload).unwrap(), *plaintext);
+            // With a random nonce it still comes back
+            assert_eq!(
+                decrypt(&ck, &encrypt(&ck, plaintext).unwrap()).unwrap(),
+                *plaintext
+            );
+        }
+    }
+
+    #[test]
+    fn test_encrypt_invalid_length() {
+        let ck = key(ENCRYPT_DECRYPT[0].2);
+        assert!(encrypt(&ck, "").is_err());
+        assert!(encrypt(&ck, &"a".repeat(MAX_PLAINTEXT_LEN)).is_ok());
+        assert!(encrypt(&ck, &"a".repeat(MAX_PLAINTEXT_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_decrypt_invalid() {
+        let (_, _, ck, _, _, payload) = ENCRYPT_DECRYPT[0];
+        let ck = key(ck);
+        let engine = base64::engine::general_purpose::STANDARD;
+
+        // Unknown version marker
+        assert!(decrypt(&ck, &format!("#{}", payload)).is_err());
+
+        // Unsupported version byte
+        let mut data = engine.decode(payload).unwrap();
+        data[0] = 1;
+        assert!(decrypt(&ck, &engine.encode(&data)).is_err());
+
+        // Invalid base64
+        assert!(decrypt(&ck, &payload.replace('A', "*")).is_err());
+
+        // Too short and too long
+        assert!(decrypt(&ck, &payload[..131]).is_err());
+        assert!(decrypt(&ck, &"A".repeat(87473)).is_err());
+
+        // Invalid MAC
+        let mut data = engine.decode(payload).unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        assert!(decrypt(&ck, &engine.encode(&data)).is_err());
+
+        // Tampered ciphertext
+        let mut data = engine.decode(payload).unwrap();
+        data[40] ^= 1;
+        assert!(decrypt(&ck, &engine.encode(&data)).is_err());
+
+        // Wrong conversation key
+        let other = key(ENCRYPT_DECRYPT[2].2);
+        assert!(decrypt(&other, payload).is_err());
+    }
+
+    #[test]
+    fn test_decrypt_invalid_padding() {
+        // Authentic, but the length prefix disagrees with the padding
+        let ck = key(ENCRYPT_DECRYPT[0].2);
+        let nonce = key(ENCRYPT_DECRYPT[0].3);
+        let (chacha_key, chacha_nonce, hmac_key) = message_keys(&ck, &nonce).unwrap();
+        let mut buffer = pad(b"a").unwrap();
+        buffer[1] = 40;
+        let mut cipher = ChaCha20::new(&chacha_key.into(), &chacha_nonce.into());
+        cipher.apply_keystream(&mut buffer);
+        let mac = hmac_aad(&hmac_key, &nonce, &buffer).unwrap();
+
+        let mut data = vec![VERSION];
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&buffer);
+        data.extend_from_slice(&mac);
+        let payload = base64::engine::general_purpose::STANDARD.encode(data);
+        assert!(decrypt(&ck, &payload).is_err());
+    }
+}
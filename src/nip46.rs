@@ -0,0 +1,274 @@
+use crate::error::Error;
+use nostr_types::{Event, EventKind, PreEvent, PrivateKey, PublicKey, RelayUrl, Tag, Unixtime};
+use serde::{Deserialize, Serialize};
+
+/// NIP-46 requests and responses travel in events of this (ephemeral) kind
+pub const NOSTR_CONNECT_KIND: u64 = 24133;
+
+pub fn nostr_connect_kind() -> EventKind {
+    EventKind::from(NOSTR_CONNECT_KIND)
+}
+
+/// A connection to a remote signer (a NIP-46 "bunker").
+///
+/// We talk to the remote signer with our own throwaway keypair (`local_key`), which
+/// is not the user's key. The user's key never leaves the remote signer.
+pub struct Nip46Connection {
+    /// The remote signer's pubkey. This is None while we wait for a remote signer
+    /// to answer a nostrconnect:// URI that we handed out.
+    pub remote_pubkey: Option<PublicKey>,
+
+    /// The relay we talk to the remote signer through
+    pub relay: RelayUrl,
+
+    /// Our keypair for talking to the remote signer
+    pub local_key: PrivateKey,
+
+    /// The user's pubkey, once the remote signer has told us
+    pub user_pubkey: Option<PublicKey>,
+
+    /// A secret from the bunker URI, which the remote signer uses to recognize us.
+    /// While we wait on a nostrconnect:// URI, it is the secret we put in it,
+    /// which the remote signer must send back to us.
+    pub secret: Option<String>,
+}
+
+impl Nip46Connection {
+    /// Create a connection from a `bunker://<remote-pubkey>?relay=wss://...&secret=...` URI
+    pub fn from_bunker_uri(uri: &str) -> Result<Nip46Connection, Error> {
+        let url = url::Url::parse(uri.trim()).map_err(|_| "Invalid bunker URI")?;
+        if url.scheme() != "bunker" {
+            return Err("Invalid bunker URI: it must start with bunker://")?;
+        }
+        let remote_pubkey = match url.host_str() {
+            Some(host) => PublicKey::try_from_hex_string(host)?,
+            None => return Err("Invalid bunker URI: it has no remote signer pubkey")?,
+        };
+
+        let mut relay: Option<RelayUrl> = None;
+        let mut secret: Option<String> = None;
+        for (key, value) in url.query_pairs() {
+            match &*key {
+                "relay" => {
+                    if relay.is_none() {
+                        relay = Some(RelayUrl::try_from_str(&value)?);
+                    }
+                }
+                "secret" => secret = Some(value.into_owned()),
+                _ => {}
+            }
+        }
+        let relay = match relay {
+            Some(r) => r,
+            None => return Err("Invalid bunker URI: it has no relay")?,
+        };
+
+        Ok(Nip46Connection {
+            remote_pubkey: Some(remote_pubkey),
+            relay,
+            local_key: PrivateKey::generate(),
+            user_pubkey: None,
+            secret,
+        })
+    }
+
+    /// Create a connection that waits for a remote signer to scan our nostrconnect:// URI
+    pub fn new_for_nostrconnect(relay: RelayUrl) -> Nip46Connection {
+        Nip46Connection {
+            remote_pubkey: None,
+            relay,
+            local_key: PrivateKey::generate(),
+            user_pubkey: None,
+            secret: Some(hex::encode(rand::random::<[u8; 16]>())),
+        }
+    }
+
+    /// The nostrconnect:// URI to hand to a remote signer
+    pub fn nostrconnect_uri(&self) -> String {
+        let metadata = serde_json::json!({ "name": "gossip" }).to_string();
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        query
+            .append_pair("relay", self.relay.as_str())
+            .append_pair("metadata", &metadata);
+        if let Some(secret) = &self.secret {
+            query.append_pair("secret", secret);
+        }
+        let query: String = query.finish();
+        format!(
+            "nostrconnect://{}?{}",
+            self.local_key.public_key().as_hex_string(),
+            query
+        )
+    }
+
+    /// Whether a `connect` request answers our nostrconnect:// URI. Anybody who saw
+    /// the URI could send one, so it must send back the secret we put in it.
+    pub fn accepts_connect(&self, message: &Nip46Message) -> bool {
+        if self.remote_pubkey.is_some() || message.method.as_deref() != Some("connect") {
+            return false;
+        }
+        match &self.secret {
+            Some(secret) => message
+                .params
+                .iter()
+                .any(|p| p.as_str() == Some(secret.as_str())),
+            None => false,
+        }
+    }
+
+    /// Whether the remote signer is paired and has told us whose key it holds
+    pub fn is_paired(&self) -> bool {
+        self.remote_pubkey.is_some() && self.user_pubkey.is_some()
+    }
+
+    /// Build a signed, encrypted request event addressed to the remote signer
+    pub fn request_event(&self, request: &Nip46Request) -> Result<Event, Error> {
+        let remote_pubkey = match self.remote_pubkey {
+            Some(pk) => pk,
+            None => return Err("The remote signer has not connected yet")?,
+        };
+        let content = self
+            .local_key
+            .nip04_encrypt(&remote_pubkey, serde_json::to_string(request)?.as_bytes())?;
+        let pre_event = PreEvent {
+            pubkey: self.local_key.public_key(),
+            created_at: Unixtime::now().unwrap(),
+            kind: nostr_connect_kind(),
+            tags: vec![Tag::Pubkey {
+                pubkey: remote_pubkey.into(),
+                recommended_relay_url: None,
+                petname: None,
+            }],
+            content,
+            ots: None,
+        };
+        Ok(Event::new(pre_event, &self.local_key)?)
+    }
+
+    /// Decrypt an event the remote signer sent us
+    pub fn decrypt_message(&self, event: &Event) -> Result<Nip46Message, Error> {
+        let bytes = self
+            .local_key
+            .nip04_decrypt(&event.pubkey, &event.content)?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Serialize for storage in the database. This includes our throwaway private key,
+    /// which only lets its holder ask the remote signer for things.
+    pub fn to_json(&mut self) -> String {
+        serde_json::json!({
+            "remote_pubkey": self.remote_pubkey.map(|pk| pk.as_hex_string()),
+            "relay": self.relay.as_str(),
+            "local_key": self.local_key.as_hex_string(),
+            "user_pubkey": self.user_pubkey.map(|pk| pk.as_hex_string()),
+            "secret": self.secret,
+        })
+        .to_string()
+    }
+
+    pub fn from_json(json: &str) -> Result<Nip46Connection, Error> {
+        let stored: StoredConnection = serde_json::from_str(json)?;
+        Ok(Nip46Connection {
+            remote_pubkey: match stored.remote_pubkey {
+                Some(s) => Some(PublicKey::try_from_hex_string(&s)?),
+                None => None,
+            },
+            relay: RelayUrl::try_from_str(&stored.relay)?,
+            local_key: PrivateKey::try_from_hex_string(&stored.local_key)?,
+            user_pubkey: match stored.user_pubkey {
+                Some(s) => Some(PublicKey::try_from_hex_string(&s)?),
+                None => None,
+            },
+            secret: stored.secret,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct StoredConnection {
+    remote_pubkey: Option<String>,
+    relay: String,
+    local_key: String,
+    user_pubkey: Option<String>,
+    secret: Option<String>,
+}
+
+/// A request to the remote signer
+#[derive(Debug, Serialize)]
+pub struct Nip46Request {
+    pub id: String,
+    pub method: String,
+    pub params: Vec<serde_json::Value>,
+}
+
+impl Nip46Request {
+    pub fn new(method: &str, params: Vec<serde_json::Value>) -> Nip46Request {
+        Nip46Request {
+            id: hex::encode(rand::random::<[u8; 16]>()),
+            method: method.to_owned(),
+            params,
+        }
+    }
+}
+
+/// A message from the remote signer. Usually a response to one of our requests, but
+/// it can also be a `connect` request when it answers our nostrconnect:// URI.
+#[derive(Debug, Deserialize)]
+pub struct Nip46Message {
+    pub id: String,
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default)]
+    pub params: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub result: Option<serde_json::Value>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl Nip46Message {
+    /// The result as a string. Remote signers differ on whether a result holding JSON
+    /// is sent as a string or as an object, so we accept either.
+    pub fn result_string(&self) -> Result<String, Error> {
+        if let Some(e) = &self.error {
+            if !e.is_empty() {
+                return Err(format!("Remote signer: {}", e))?;
+            }
+        }
+        match &self.result {
+            Some(serde_json::Value::String(s)) => Ok(s.to_owned()),
+            Some(v) => Ok(v.to_string()),
+            None => Err("Remote signer sent no result")?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn connect(params: Vec<serde_json::Value>) -> Nip46Message {
+        Nip46Message {
+            id: "1".to_owned(),
+            method: Some("connect".to_owned()),
+            params,
+            result: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_nostrconnect_needs_the_secret() {
+        let relay = RelayUrl::try_from_str("wss://relay.example.com").unwrap();
+        let connection = Nip46Connection::new_for_nostrconnect(relay);
+        let secret = connection.secret.clone().unwrap();
+        assert!(connection
+            .nostrconnect_uri()
+            .contains(&format!("secret={}", secret)));
+
+        let remote = PrivateKey::generate().public_key().as_hex_string();
+        assert!(connection.accepts_connect(&connect(vec![remote.clone().into(), secret.into()])));
+        assert!(!connection.accepts_connect(&connect(vec![remote.clone().into()])));
+        assert!(!connection.accepts_connect(&connect(vec![remote.into(), "guessed".into()])));
+    }
+}
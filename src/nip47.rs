@@ -0,0 +1,196 @@
+use crate::error::Error;
+use nostr_types::{
+    EncryptedPrivateKey, Event, EventKind, PreEvent, PrivateKey, PublicKey, RelayUrl, Tag, Unixtime,
+};
+use serde::{Deserialize, Serialize};
+
+/// NIP-47 requests to the wallet travel in events of this (ephemeral) kind
+pub const WALLET_REQUEST_KIND: u64 = 23194;
+
+/// and the wallet answers in events of this kind
+pub const WALLET_RESPONSE_KIND: u64 = 23195;
+
+pub fn wallet_request_kind() -> EventKind {
+    EventKind::from(WALLET_REQUEST_KIND)
+}
+
+pub fn wallet_response_kind() -> EventKind {
+    EventKind::from(WALLET_RESPONSE_KIND)
+}
+
+/// A connection to a NIP-47 wallet service, from a
+/// `nostr+walletconnect://<wallet-pubkey>?relay=wss://...&secret=...` URI.
+///
+/// The secret is a private key the wallet handed out for us to sign our requests
+/// with. Whoever holds it can spend from the wallet (up to the wallet's limits).
+pub struct Nip47Connection {
+    /// The wallet service's pubkey
+    pub wallet_pubkey: PublicKey,
+
+    /// The relay we talk to the wallet service through
+    pub relay: RelayUrl,
+
+    /// The key we sign requests with
+    pub secret: PrivateKey,
+}
+
+impl Nip47Connection {
+    pub fn from_uri(uri: &str) -> Result<Nip47Connection, Error> {
+        let url = url::Url::parse(uri.trim()).map_err(|_| "Invalid wallet connect URI")?;
+        if url.scheme() != "nostr+walletconnect" && url.scheme() != "nostrwalletconnect" {
+            return Err("Invalid wallet connect URI: it must start with nostr+walletconnect://")?;
+        }
+        let wallet_pubkey = match url.host_str() {
+            Some(host) => PublicKey::try_from_hex_string(host)?,
+            None => return Err("Invalid wallet connect URI: it has no wallet pubkey")?,
+        };
+
+        let mut relay: Option<RelayUrl> = None;
+        let mut secret: Option<PrivateKey> = None;
+        for (key, value) in url.query_pairs() {
+            match &*key {
+                "relay" => {
+                    if relay.is_none() {
+                        relay = Some(RelayUrl::try_from_str(&value)?);
+                    }
+                }
+                "secret" => secret = Some(PrivateKey::try_from_hex_string(&value)?),
+                _ => {}
+            }
+        }
+        let relay = match relay {
+            Some(r) => r,
+            None => return Err("Invalid wallet connect URI: it has no relay")?,
+        };
+        let secret = match secret {
+            Some(s) => s,
+            None => return Err("Invalid wallet connect URI: it has no secret")?,
+        };
+
+        Ok(Nip47Connection {
+            wallet_pubkey,
+            relay,
+            secret,
+        })
+    }
+
+    /// Build a signed, encrypted request event addressed to the wallet service
+    pub fn request_event(&self, request: &Nip47Request) -> Result<Event, Error> {
+        let content = self.secret.nip04_encrypt(
+            &self.wallet_pubkey,
+            serde_json::to_string(request)?.as_bytes(),
+        )?;
+        let pre_event = PreEvent {
+            pubkey: self.secret.public_key(),
+            created_at: Unixtime::now().unwrap(),
+            kind: wallet_request_kind(),
+            tags: vec![Tag::Pubkey {
+                pubkey: self.wallet_pubkey.into(),
+                recommended_relay_url: None,
+                petname: None,
+            }],
+            content,
+            ots: None,
+        };
+        Ok(Event::new(pre_event, &self.secret)?)
+    }
+
+    /// Decrypt an event the wallet service sent us
+    pub fn decrypt_response(&self, event: &Event) -> Result<Nip47Response, Error> {
+        let bytes = self
+            .secret
+            .nip04_decrypt(&self.wallet_pubkey, &event.content)?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Encrypt the secret with the passphrase, for storage in the database
+    pub fn to_stored(&self, pass: &str, log_n: u8) -> Result<StoredNip47Connection, Error> {
+        Ok(StoredNip47Connection {
+            wallet_pubkey: self.wallet_pubkey.as_hex_string(),
+            relay: self.relay.as_str().to_owned(),
+            client_pubkey: self.secret.public_key().as_hex_string(),
+            encrypted_secret: self.secret.export_encrypted(pass, log_n)?.0,
+        })
+    }
+
+    pub fn from_stored(
+        stored: &StoredNip47Connection,
+        pass: &str,
+    ) -> Result<Nip47Connection, Error> {
+        Ok(Nip47Connection {
+            wallet_pubkey: PublicKey::try_from_hex_string(&stored.wallet_pubkey)?,
+            relay: RelayUrl::try_from_str(&stored.relay)?,
+            secret: EncryptedPrivateKey(stored.encrypted_secret.clone()).decrypt(pass)?,
+        })
+    }
+}
+
+/// A wallet connection as kept in the database. Everything but the secret is in
+/// the clear, so that we can listen to the wallet before the key is unlocked.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StoredNip47Connection {
+    pub wallet_pubkey: String,
+    pub relay: String,
+    pub client_pubkey: String,
+    pub encrypted_secret: String,
+}
+
+impl StoredNip47Connection {
+    pub fn relay(&self) -> Result<RelayUrl, Error> {
+        Ok(RelayUrl::try_from_str(&self.relay)?)
+    }
+
+    pub fn client_pubkey(&self) -> Result<PublicKey, Error> {
+        Ok(PublicKey::try_from_hex_string(&self.client_pubkey)?)
+    }
+}
+
+/// A request to the wallet service
+#[derive(Debug, Serialize)]
+pub struct Nip47Request {
+    pub method: String,
+    pub params: serde_json::Value,
+}
+
+impl Nip47Request {
+    pub fn pay_invoice(invoice: &str) -> Nip47Request {
+        Nip47Request {
+            method: "pay_invoice".to_owned(),
+            params: serde_json::json!({ "invoice": invoice }),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Nip47Error {
+    pub code: String,
+    #[serde(default)]
+    pub message: String,
+}
+
+/// A response from the wallet service
+#[derive(Debug, Deserialize)]
+pub struct Nip47Response {
+    #[serde(default)]
+    pub error: Option<Nip47Error>,
+    #[serde(default)]
+    pub result: Option<serde_json::Value>,
+}
+
+impl Nip47Response {
+    /// The preimage of a paid invoice
+    pub fn preimage(&self) -> Result<String, Error> {
+        if let Some(e) = &self.error {
+            return Err(format!("Wallet: {} {}", e.code, e.message))?;
+        }
+        match self
+            .result
+            .as_ref()
+            .and_then(|r| r.get("preimage"))
+            .and_then(|p| p.as_str())
+        {
+            Some(preimage) => Ok(preimage.to_owned()),
+            None => Err("Wallet sent no preimage")?,
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use crate::db::DbOutbox;
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, Id, RelayUrl, Unixtime};
+use parking_lot::RwLock;
+
+/// How many times a post is sent to a relay that never answers before we give up
+pub const MAX_OUTBOX_ATTEMPTS: u64 = 10;
+
+/// Whether a relay that turned a post down (by its OK message) may take it if we
+/// send it again later: it wanted us to authenticate first, we were sending too
+/// fast, or it had trouble of its own (NIP-01, NIP-42)
+pub fn rejected_for_now(message: &str) -> bool {
+    message.starts_with("auth-required:")
+        || message.starts_with("rate-limited:")
+        || message.starts_with("error:")
+}
+
+/// Our posts that relays have not taken yet, oldest first. Each is kept until
+/// its relay takes it or turns it down for good, so that posts made offline (or
+/// to relays that are down or busy) go out once we connect again. Posts of other identities stay queued, but
+/// are only sent once we switch back to them.
+pub struct Outbox {
+    queued: RwLock<Vec<DbOutbox>>,
+}
+
+impl Default for Outbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Outbox {
+    pub fn new() -> Outbox {
+        Outbox {
+            queued: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub async fn load(&self) -> Result<(), Error> {
+        *self.queued.write() = DbOutbox::load_all().await?;
+        Ok(())
+    }
+
+    /// The posts of the active identity that are waiting
+    pub fn get(&self) -> Vec<DbOutbox> {
+        let public_key = GLOBALS.signer.public_key();
+        self.queued
+            .read()
+            .iter()
+            .filter(|q| Some(q.event.pubkey) == public_key)
+            .cloned()
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let public_key = GLOBALS.signer.public_key();
+        !self
+            .queued
+            .read()
+            .iter()
+            .any(|q| Some(q.event.pubkey) == public_key)
+    }
+
+    /// The relays that have posts of the active identity waiting
+    pub fn relays(&self) -> Vec<RelayUrl> {
+        let mut relays: Vec<RelayUrl> = Vec::new();
+        for queued in self.get().drain(..) {
+            if !relays.contains(&queued.relay) {
+                relays.push(queued.relay);
+            }
+        }
+        relays
+    }
+
+    /// The posts of the active identity waiting for this relay
+    pub fn queued_for(&self, url: &RelayUrl) -> Vec<Event> {
+        self.get()
+            .drain(..)
+            .filter(|q| q.relay == *url)
+            .map(|q| q.event)
+            .collect()
+    }
+
+    /// Keep the event until the relay answers, counting another attempt if
+    /// it is already queued. Only our own events are newly queued.
+    /// Returns false if the relay has had it too many times already, in which
+    /// case we give up on it and it should not be sent.
+    pub async fn queue(&self, event: &Event, url: &RelayUrl) -> Result<bool, Error> {
+        let attempts = self
+            .queued
+            .read()
+            .iter()
+            .find(|q| q.event.id == event.id && q.relay == *url)
+            .map(|q| q.attempts);
+        if attempts.is_none() && GLOBALS.signer.public_key() != Some(event.pubkey) {
+            return Ok(true);
+        }
+        let attempts = attempts.unwrap_or(0);
+        if attempts >= MAX_OUTBOX_ATTEMPTS {
+            tracing::warn!(
+                "Giving up on sending {} to {} after {} attempts",
+                event.id.as_hex_string(),
+                url,
+                attempts
+            );
+            self.remove(event.id, url).await?;
+            return Ok(false);
+        }
+        let now = Unixtime::now().unwrap().0;
+        DbOutbox::queue(event.clone(), url.clone(), now).await?;
+        let mut queued = self.queued.write();
+        match queued
+            .iter_mut()
+            .find(|q| q.event.id == event.id && q.relay == *url)
+        {
+            Some(q) => q.attempts += 1,
+            None => queued.push(DbOutbox {
+                event: event.clone(),
+                relay: url.clone(),
+                queued_at: now,
+                attempts: 1,
+            }),
+        }
+        Ok(true)
+    }
+
+    /// The relay took it or turned it down for good, or the user gave up on it
+    pub async fn remove(&self, id: Id, url: &RelayUrl) -> Result<(), Error> {
+        let found = self
+            .queued
+            .read()
+            .iter()
+            .any(|q| q.event.id == id && q.relay == *url);
+        if found {
+            DbOutbox::delete(id, url.clone()).await?;
+            self.queued
+                .write()
+                .retain(|q| !(q.event.id == id && q.relay == *url));
+        }
+        Ok(())
+    }
+}
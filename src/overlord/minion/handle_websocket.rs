@@ -1,10 +1,9 @@
 use super::Minion;
-use crate::db::{DbEventRelay, DbRelay};
+use crate::comms::{PostingResponse, ToMinionMessage, ToMinionPayload, ToMinionPayloadDetail};
+use crate::db::{DbEventRelay, DbRelay, RelayMetricKind};
 use crate::error::Error;
 use crate::globals::GLOBALS;
-use futures_util::sink::SinkExt;
-use nostr_types::{ClientMessage, EventKind, PreEvent, RelayMessage, Tag, Unixtime};
-use tungstenite::protocol::Message as WsMessage;
+use nostr_types::{EventKind, PreEvent, RelayMessage, Tag, Unixtime};
 
 impl Minion {
     pub(super) async fn handle_nostr_message(&mut self, ws_message: String) -> Result<(), Error> {
@@ -14,6 +13,15 @@ impl Minion {
         let relay_message: RelayMessage = match serde_json::from_str(&ws_message) {
             Ok(rm) => rm,
             Err(e) => {
+                // nostr-types does not know CLOSED (NIP-01) yet
+                if let Ok((tag, subid, reason)) =
+                    serde_json::from_str::<(String, String, String)>(&ws_message)
+                {
+                    if tag == "CLOSED" {
+                        return self.handle_closed(subid, reason).await;
+                    }
+                }
+
                 tracing::error!(
                     "RELAY MESSAGE NOT DESERIALIZING: starts with \"{}\"",
                     &ws_message.chars().take(300).collect::<String>()
@@ -58,6 +66,8 @@ impl Minion {
                         }
                     }
 
+                    let is_search = handle == "temp_search";
+
                     // Try processing everything immediately
                     crate::process::process_new_event(
                         &event,
@@ -66,10 +76,20 @@ impl Minion {
                         Some(handle),
                     )
                     .await?;
+
+                    // Search results are wanted even if they are not new to us
+                    if is_search {
+                        GLOBALS.feed.add_relay_search_result(&event);
+                    }
                 }
             }
             RelayMessage::Notice(msg) => {
                 tracing::info!("{}: NOTICE: {}", &self.url, msg);
+                GLOBALS
+                    .relay_notices
+                    .add(&self.url, msg.clone(), None)
+                    .await;
+                self.record_metric(RelayMetricKind::Notice, 0, msg).await;
             }
             RelayMessage::Eose(subid) => {
                 let handle = self
@@ -84,6 +104,8 @@ impl Minion {
                 match self.subscriptions.get_mut_by_id(&subid.0) {
                     Some(sub) => {
                         tracing::debug!("{}: {}: EOSE: {:?}", &self.url, handle, subid);
+                        let first_eose = !sub.eose();
+                        let age_ms = sub.age_ms();
                         if close {
                             self.unsubscribe(&handle).await?;
                         } else {
@@ -93,6 +115,10 @@ impl Minion {
                             let now = Unixtime::now().unwrap().0 as u64;
                             DbRelay::update_general_eose(self.dbrelay.url.clone(), now).await?;
                         }
+                        if first_eose {
+                            self.record_metric(RelayMetricKind::EoseLatency, age_ms, handle)
+                                .await;
+                        }
                     }
                     None => {
                         tracing::debug!(
@@ -133,6 +159,28 @@ impl Minion {
                         self.bump_failure_count().await;
                     }
                     self.postings.remove(&id);
+                    GLOBALS
+                        .deliveries
+                        .answered(id, &self.url, ok, ok_message.clone());
+
+                    // It need not be sent again, unless the relay only turned it down
+                    // for now, in which case it stays queued for another attempt
+                    if ok || !crate::outbox::rejected_for_now(&ok_message) {
+                        GLOBALS.outbox.remove(id, &self.url).await?;
+                    }
+
+                    // Let anybody watching know (ignore errors, there may be nobody listening)
+                    let _ = GLOBALS.posting_responses.send(PostingResponse {
+                        relay: self.url.clone(),
+                        id,
+                        ok,
+                        message: ok_message.clone(),
+                    });
+                }
+
+                if !ok {
+                    self.record_metric(RelayMetricKind::Rejected, 0, ok_message)
+                        .await;
                 }
 
                 match ok {
@@ -149,6 +197,19 @@ impl Minion {
                     Some(pk) => pk,
                     None => return Ok(()),
                 };
+                // The remote signer answers through this relay, which will not take
+                // its request before we authenticate
+                if GLOBALS.signer.is_remote() {
+                    if let Some((relay, _)) = GLOBALS.signer.remote_signer_status() {
+                        if relay == self.url {
+                            tracing::warn!(
+                                "AUTH required on {}, but it is our remote signer relay",
+                                &self.url
+                            );
+                            return Ok(());
+                        }
+                    }
+                }
                 let pre_event = PreEvent {
                     pubkey,
                     created_at: Unixtime::now().unwrap(),
@@ -166,15 +227,47 @@ impl Minion {
                     content: "".to_string(),
                     ots: None,
                 };
-                let event = GLOBALS.signer.sign_preevent(pre_event, None, None)?;
-                let msg = ClientMessage::Auth(Box::new(event));
-                let wire = serde_json::to_string(&msg)?;
-                let ws_stream = self.stream.as_mut().unwrap();
-                ws_stream.send(WsMessage::Text(wire)).await?;
-                tracing::info!("Authenticated to {}", &self.url);
+                // A remote signer may take a while, so we sign in a task of its own
+                // and send the AUTH when it comes back to us
+                let url = self.url.clone();
+                std::mem::drop(tokio::spawn(async move {
+                    match GLOBALS.signer.sign_preevent(pre_event, None, None).await {
+                        Ok(event) => {
+                            let _ = GLOBALS.to_minions.send(ToMinionMessage {
+                                target: url.0,
+                                payload: ToMinionPayload {
+                                    job_id: 0,
+                                    detail: ToMinionPayloadDetail::Authenticate(Box::new(event)),
+                                },
+                            });
+                        }
+                        Err(e) => tracing::warn!("Could not sign AUTH for {}: {}", &url, e),
+                    }
+                }));
             }
         }
 
         Ok(())
     }
+
+    /// The relay closed one of our subscriptions, and says why
+    async fn handle_closed(&mut self, subid: String, reason: String) -> Result<(), Error> {
+        let handle = self
+            .subscriptions
+            .get_handle_by_id(&subid)
+            .unwrap_or_else(|| "_".to_owned());
+        tracing::warn!("{}: {}: CLOSED: {}", &self.url, handle, reason);
+
+        // It is gone at the relay, so there is nothing to CLOSE on our side
+        self.subscriptions.remove(&handle);
+
+        self.record_metric(RelayMetricKind::Notice, 0, format!("CLOSED: {}", reason))
+            .await;
+        GLOBALS
+            .relay_notices
+            .add(&self.url, reason, Some(handle))
+            .await;
+
+        Ok(())
+    }
 }
@@ -2,7 +2,7 @@ mod handle_websocket;
 mod subscription;
 
 use crate::comms::{ToMinionMessage, ToMinionPayload, ToMinionPayloadDetail, ToOverlordMessage};
-use crate::db::DbRelay;
+use crate::db::{DbRelay, DbRelayMetric, RelayMetricKind};
 use crate::error::Error;
 use crate::globals::GLOBALS;
 use crate::USER_AGENT;
@@ -21,7 +21,7 @@ use reqwest::Response;
 use std::borrow::Cow;
 use std::collections::HashSet;
 use std::sync::atomic::Ordering;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use subscription::Subscriptions;
 use tokio::net::TcpStream;
 use tokio::select;
@@ -30,6 +30,9 @@ use tokio::sync::mpsc::UnboundedSender;
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 use tungstenite::protocol::{Message as WsMessage, WebSocketConfig};
 
+// How often the bytes received on a long-lived connection are recorded
+const BYTES_RECORD_INTERVAL: Duration = Duration::from_secs(60 * 10);
+
 pub struct Minion {
     url: RelayUrl,
     to_overlord: UnboundedSender<ToOverlordMessage>,
@@ -41,6 +44,9 @@ pub struct Minion {
     next_events_subscription_id: u32,
     keepgoing: bool,
     postings: HashSet<Id>,
+    bytes_received: usize,
+    bytes_recorded_at: Instant,
+    disconnect_reason: Option<String>,
 }
 
 impl Minion {
@@ -67,6 +73,9 @@ impl Minion {
             next_events_subscription_id: 0,
             keepgoing: true,
             postings: HashSet::new(),
+            bytes_received: 0,
+            bytes_recorded_at: Instant::now(),
+            disconnect_reason: None,
         })
     }
 }
@@ -74,10 +83,21 @@ impl Minion {
 impl Minion {
     pub async fn handle(&mut self, messages: Vec<ToMinionPayload>) {
         // Catch errors, Return nothing.
-        if let Err(e) = self.handle_inner(messages).await {
-            tracing::error!("{}: ERROR: {}", &self.url, e);
-            self.bump_failure_count().await;
-        }
+        let reason = match self.handle_inner(messages).await {
+            Ok(()) => self
+                .disconnect_reason
+                .take()
+                .unwrap_or_else(|| "closed".to_owned()),
+            Err(e) => {
+                tracing::error!("{}: ERROR: {}", &self.url, e);
+                self.bump_failure_count().await;
+                format!("{}", e)
+            }
+        };
+
+        self.record_bytes_received().await;
+        self.record_metric(RelayMetricKind::Disconnect, 0, reason)
+            .await;
 
         tracing::info!("{}: minion exiting", self.url);
     }
@@ -122,6 +142,16 @@ impl Minion {
                         match serde_json::from_str::<RelayInformationDocument>(&text) {
                             Ok(nip11) => {
                                 tracing::info!("{}: {}", &self.url, nip11);
+                                // Remember it, so we know what the relay supports
+                                // without connecting to it
+                                if let Err(e) =
+                                    DbRelay::update_nip11(self.url.clone(), nip11.clone()).await
+                                {
+                                    tracing::error!("{}: ERROR saving NIP-11: {}", &self.url, e);
+                                }
+                                if let Some(mut dbrelay) = GLOBALS.all_relays.get_mut(&self.url) {
+                                    dbrelay.nip11 = Some(nip11.clone());
+                                }
                                 self.nip11 = Some(nip11);
                             }
                             Err(e) => {
@@ -187,12 +217,19 @@ impl Minion {
                 accept_unmasked_frames: false,       // default is false which is the standard
             };
 
+            let connect_start = Instant::now();
             let (websocket_stream, _response) = tokio::time::timeout(
                 std::time::Duration::new(15, 0),
                 tokio_tungstenite::connect_async_with_config(req, Some(config)),
             )
             .await??;
             tracing::info!("{}: Connected", &self.url);
+            self.record_metric(
+                RelayMetricKind::ConnectLatency,
+                connect_start.elapsed().as_millis() as i64,
+                "".to_owned(),
+            )
+            .await;
 
             websocket_stream
         };
@@ -245,6 +282,9 @@ impl Minion {
         select! {
             _ = timer.tick() => {
                 ws_stream.send(WsMessage::Ping(vec![0x1])).await?;
+                if self.bytes_recorded_at.elapsed() >= BYTES_RECORD_INTERVAL {
+                    self.record_bytes_received().await;
+                }
             },
             ws_message = ws_stream.next() => {
                 let ws_message = match ws_message {
@@ -253,6 +293,7 @@ impl Minion {
                         if ws_stream.is_terminated() {
                             // possibly connection reset
                             tracing::info!("{}: connected terminated", &self.url);
+                            self.disconnect_reason = Some("connection terminated".to_owned());
                             self.keepgoing = false;
                         }
                         return Ok(());
@@ -260,6 +301,7 @@ impl Minion {
                 }?;
 
                 GLOBALS.bytes_read.fetch_add(ws_message.len(), Ordering::Relaxed);
+                self.bytes_received += ws_message.len();
 
                 tracing::trace!("{}: Handling message", &self.url);
                 match ws_message {
@@ -273,7 +315,15 @@ impl Minion {
                     WsMessage::Binary(_) => tracing::warn!("{}, Unexpected binary message", &self.url),
                     WsMessage::Ping(_) => { }, // tungstenite automatically pongs.
                     WsMessage::Pong(_) => { }, // Verify it is 0x1? Nah. It's just for keep-alive.
-                    WsMessage::Close(_) => self.keepgoing = false,
+                    WsMessage::Close(frame) => {
+                        self.disconnect_reason = Some(match frame {
+                            Some(frame) if !frame.reason.is_empty() => {
+                                format!("closed by relay: {}", frame.reason)
+                            }
+                            _ => "closed by relay".to_owned(),
+                        });
+                        self.keepgoing = false;
+                    },
                     WsMessage::Frame(_) => tracing::warn!("{}: Unexpected frame message", &self.url),
                 }
             },
@@ -281,6 +331,7 @@ impl Minion {
                 let to_minion_message = match to_minion_message {
                     Ok(m) => m,
                     Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                        self.disconnect_reason = Some("shutting down".to_owned());
                         self.keepgoing = false;
                         return Ok(());
                     },
@@ -294,6 +345,9 @@ impl Minion {
 
         // Don't continue if we have no more subscriptions
         if self.subscriptions.is_empty() {
+            if self.disconnect_reason.is_none() {
+                self.disconnect_reason = Some("no more subscriptions".to_owned());
+            }
             self.keepgoing = false;
         }
 
@@ -302,6 +356,13 @@ impl Minion {
 
     pub async fn handle_overlord_message(&mut self, message: ToMinionPayload) -> Result<(), Error> {
         match message.detail {
+            ToMinionPayloadDetail::Authenticate(event) => {
+                let msg = ClientMessage::Auth(event);
+                let wire = serde_json::to_string(&msg)?;
+                let ws_stream = self.stream.as_mut().unwrap();
+                ws_stream.send(WsMessage::Text(wire)).await?;
+                tracing::info!("Authenticated to {}", &self.url);
+            }
             ToMinionPayloadDetail::FetchEvent(id) => {
                 self.get_event(message.job_id, id).await?;
             }
@@ -323,11 +384,15 @@ impl Minion {
             }
             ToMinionPayloadDetail::Shutdown => {
                 tracing::info!("{}: Websocket listener shutting down", &self.url);
+                self.disconnect_reason = Some("shutting down".to_owned());
                 self.keepgoing = false;
             }
             ToMinionPayloadDetail::SubscribeGeneralFeed(pubkeys) => {
                 self.subscribe_general_feed(message.job_id, pubkeys).await?;
             }
+            ToMinionPayloadDetail::SubscribeHashtagFeed(hashtag) => {
+                self.subscribe_hashtag_feed(message.job_id, hashtag).await?;
+            }
             ToMinionPayloadDetail::SubscribeMentions => {
                 self.subscribe_mentions(message.job_id).await?;
             }
@@ -341,20 +406,42 @@ impl Minion {
                 self.subscribe_person_feed(message.job_id, pubkeyhex)
                     .await?;
             }
+            ToMinionPayloadDetail::SubscribeRemoteSigner(pubkeyhex) => {
+                self.subscribe_remote_signer(message.job_id, pubkeyhex)
+                    .await?;
+            }
+            ToMinionPayloadDetail::SubscribeSearch(query) => {
+                self.subscribe_search(message.job_id, query).await?;
+            }
             ToMinionPayloadDetail::SubscribeThreadFeed(main, parents) => {
                 self.subscribe_thread_feed(message.job_id, main, parents)
                     .await?;
             }
+            ToMinionPayloadDetail::SubscribeWallet(pubkeyhex) => {
+                self.subscribe_wallet(message.job_id, pubkeyhex).await?;
+            }
             ToMinionPayloadDetail::TempSubscribeMetadata(pubkeyhexs) => {
                 self.temp_subscribe_metadata(message.job_id, pubkeyhexs)
                     .await?;
             }
+            ToMinionPayloadDetail::UnsubscribeHashtagFeed => {
+                self.unsubscribe("hashtag_feed").await?;
+            }
             ToMinionPayloadDetail::UnsubscribePersonFeed => {
                 self.unsubscribe("person_feed").await?;
             }
+            ToMinionPayloadDetail::UnsubscribeRemoteSigner => {
+                self.unsubscribe("remote_signer").await?;
+            }
+            ToMinionPayloadDetail::UnsubscribeSearch => {
+                self.unsubscribe("temp_search").await?;
+            }
             ToMinionPayloadDetail::UnsubscribeThreadFeed => {
                 self.unsubscribe("thread_feed").await?;
             }
+            ToMinionPayloadDetail::UnsubscribeWallet => {
+                self.unsubscribe("wallet").await?;
+            }
         }
 
         Ok(())
@@ -417,11 +504,9 @@ impl Minion {
 
         // Allow all feed related event kinds
         let mut event_kinds = GLOBALS.settings.read().feed_related_event_kinds();
-        // But exclude DMs in the general feed
-        event_kinds.retain(|f| *f != EventKind::EncryptedDirectMessage);
 
         if let Some(pubkey) = GLOBALS.signer.public_key() {
-            // feed related by me
+            // feed related by me, including DMs I sent (so conversations show both sides)
             // FIXME copy this to listening to my write relays
             let pkh: PublicKeyHex = pubkey.into();
             filters.push(Filter {
@@ -432,6 +517,9 @@ impl Minion {
             });
         }
 
+        // But exclude DMs in the rest of the general feed
+        event_kinds.retain(|f| *f != EventKind::EncryptedDirectMessage);
+
         if !followed_pubkeys.is_empty() {
             let pkp: Vec<PublicKeyHexPrefix> = followed_pubkeys
                 .iter()
@@ -582,6 +670,10 @@ impl Minion {
                     //EventKind::RecommendRelay,
                     EventKind::ContactList,
                     EventKind::RelayList,
+                    crate::bookmarks::bookmark_list_kind(),
+                    crate::bookmarks::categorized_bookmark_list_kind(),
+                    crate::mutes::mute_list_kind(),
+                    crate::person_lists::follow_set_kind(),
                 ],
                 // these are all replaceable, no since required
                 ..Default::default()
@@ -681,6 +773,117 @@ impl Minion {
         Ok(())
     }
 
+    async fn subscribe_hashtag_feed(&mut self, job_id: u64, hashtag: String) -> Result<(), Error> {
+        // NOTE we do not unsubscribe to the general feed
+
+        // Allow all feed related event kinds
+        let mut event_kinds = GLOBALS.settings.read().feed_related_event_kinds();
+        // Exclude DMs and reactions (we wouldn't see the post it reacted to) in hashtag feed
+        event_kinds
+            .retain(|f| *f != EventKind::EncryptedDirectMessage && *f != EventKind::Reaction);
+
+        // Tags are matched exactly by relays, and most clients lowercase them
+        let mut hashtags = vec![hashtag.to_lowercase()];
+        if !hashtags.contains(&hashtag) {
+            hashtags.push(hashtag);
+        }
+
+        let filters: Vec<Filter> = vec![Filter {
+            t: hashtags,
+            kinds: event_kinds,
+            // No since, just a limit on quantity of posts
+            limit: Some(100),
+            ..Default::default()
+        }];
+
+        self.subscribe(filters, "hashtag_feed", job_id).await?;
+
+        Ok(())
+    }
+
+    // Subscribe to messages from a NIP-46 remote signer, addressed to our throwaway key
+    async fn subscribe_remote_signer(
+        &mut self,
+        job_id: u64,
+        pubkey: PublicKeyHex,
+    ) -> Result<(), Error> {
+        let filters: Vec<Filter> = vec![Filter {
+            kinds: vec![crate::nip46::nostr_connect_kind()],
+            p: vec![pubkey],
+            // These are ephemeral, we only want new ones
+            since: Some(Unixtime::now().unwrap() - Duration::from_secs(60)),
+            ..Default::default()
+        }];
+
+        self.subscribe(filters, "remote_signer", job_id).await?;
+
+        Ok(())
+    }
+
+    // Subscribe to responses from our NIP-47 wallet, addressed to our wallet key
+    async fn subscribe_wallet(&mut self, job_id: u64, pubkey: PublicKeyHex) -> Result<(), Error> {
+        let filters: Vec<Filter> = vec![Filter {
+            kinds: vec![crate::nip47::wallet_response_kind()],
+            p: vec![pubkey],
+            // These are ephemeral, we only want new ones
+            since: Some(Unixtime::now().unwrap() - Duration::from_secs(60)),
+            ..Default::default()
+        }];
+
+        self.subscribe(filters, "wallet", job_id).await?;
+
+        Ok(())
+    }
+
+    // NIP-50 search, only on relays that say they support it
+    async fn subscribe_search(&mut self, job_id: u64, query: String) -> Result<(), Error> {
+        let supported = match &self.nip11 {
+            Some(nip11) => nip11.supports_nip(50),
+            None => false,
+        };
+        if !supported {
+            tracing::debug!("{}: does not support NIP-50 search", &self.url);
+            self.to_overlord.send(ToOverlordMessage::MinionJobComplete(
+                self.url.clone(),
+                job_id,
+            ))?;
+            return Ok(());
+        }
+
+        let filter = Filter {
+            kinds: vec![EventKind::Metadata, EventKind::TextNote],
+            limit: Some(100),
+            ..Default::default()
+        };
+
+        // The handle starts with temp_ so we close it after EOSE
+        let handle = "temp_search";
+        if self.subscriptions.has(handle) {
+            self.unsubscribe(handle).await?;
+        }
+        let id = self.subscriptions.add(handle, job_id, vec![filter.clone()]);
+        tracing::debug!(
+            "NEW SUBSCRIPTION on {} handle={}, id={}",
+            &self.url,
+            handle,
+            &id
+        );
+
+        // Filter has no NIP-50 search field, so we add it to the JSON ourselves
+        let mut filter_json = serde_json::to_value(&filter)?;
+        if let Some(object) = filter_json.as_object_mut() {
+            object.insert("search".to_owned(), serde_json::Value::String(query));
+        }
+        let wire = serde_json::to_string(&serde_json::json!(["REQ", id, filter_json]))?;
+        let websocket_stream = self.stream.as_mut().unwrap();
+        tracing::trace!("{}: Sending {}", &self.url, &wire);
+        websocket_stream.send(WsMessage::Text(wire)).await?;
+
+        GLOBALS.feed.relay_search_asked(&self.url);
+
+        Ok(())
+    }
+
     async fn subscribe_thread_feed(
         &mut self,
         job_id: u64,
@@ -1012,6 +1215,26 @@ impl Minion {
         }
     }
 
+    async fn record_metric(&self, kind: RelayMetricKind, value: i64, detail: String) {
+        let metric = DbRelayMetric::new(self.url.clone(), kind, value, detail);
+        if let Err(e) = DbRelayMetric::insert(metric).await {
+            tracing::error!("{}: ERROR recording relay metric: {}", &self.url, e);
+        }
+    }
+
+    async fn record_bytes_received(&mut self) {
+        if self.bytes_received > 0 {
+            self.record_metric(
+                RelayMetricKind::BytesReceived,
+                self.bytes_received as i64,
+                "".to_owned(),
+            )
+            .await;
+        }
+        self.bytes_received = 0;
+        self.bytes_recorded_at = Instant::now();
+    }
+
     async fn bump_failure_count(&mut self) {
         // Update in self
         self.dbrelay.failure_count += 1;
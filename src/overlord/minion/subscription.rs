@@ -1,5 +1,6 @@
 use nostr_types::{ClientMessage, Filter, SubscriptionId};
 use std::collections::HashMap;
+use std::time::Instant;
 
 pub struct Subscriptions {
     handle_to_id: HashMap<String, String>,
@@ -94,6 +95,7 @@ pub struct Subscription {
     job_id: u64,
     filters: Vec<Filter>,
     eose: bool,
+    started: Instant,
 }
 
 impl Subscription {
@@ -103,6 +105,7 @@ impl Subscription {
             job_id,
             filters: vec![],
             eose: false,
+            started: Instant::now(),
         }
     }
 
@@ -122,6 +125,11 @@ impl Subscription {
         self.eose
     }
 
+    /// Milliseconds since the subscription was made
+    pub fn age_ms(&self) -> i64 {
+        self.started.elapsed().as_millis() as i64
+    }
+
     pub fn req_message(&self) -> ClientMessage {
         ClientMessage::Req(SubscriptionId(self.get_id()), self.filters.clone())
     }
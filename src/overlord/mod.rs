@@ -3,10 +3,14 @@ mod minion;
 use crate::comms::{
     RelayJob, ToMinionMessage, ToMinionPayload, ToMinionPayloadDetail, ToOverlordMessage,
 };
-use crate::db::{DbEvent, DbEventFlags, DbEventRelay, DbPersonRelay, DbRelay};
+use crate::db::{
+    DbDmChannel, DbEvent, DbEventFlags, DbEventRelay, DbIdentity, DbLongFormDraft, DbPersonRelay,
+    DbPersonZapper, DbRelay, DbRelayMetric, EventSearch, PruneStats,
+};
 use crate::error::{Error, ErrorKind};
 use crate::globals::GLOBALS;
 use crate::people::People;
+use crate::post_relays::select_post_relays;
 use crate::tags::{
     add_event_to_tags, add_pubkey_hex_to_tags, add_pubkey_to_tags, add_subject_to_tags_if_missing,
 };
@@ -18,6 +22,7 @@ use nostr_types::{
     NostrUrl, PreEvent, PrivateKey, Profile, PublicKey, PublicKeyHex, RelayUrl, Tag, Unixtime,
 };
 use std::collections::HashMap;
+use std::future::Future;
 use std::sync::atomic::Ordering;
 use std::sync::mpsc;
 use tokio::sync::broadcast::Sender;
@@ -84,6 +89,10 @@ impl Overlord {
     pub async fn run_inner(&mut self) -> Result<(), Error> {
         // Load signer from settings
         GLOBALS.signer.load_from_settings().await;
+        GLOBALS.wallet.load_from_settings().await;
+
+        // Load our identities
+        *GLOBALS.identities.write() = DbIdentity::fetch_all().await?;
 
         // FIXME - if this needs doing, it should be done dynamically as
         //         new people are encountered, not batch-style on startup.
@@ -108,6 +117,18 @@ impl Overlord {
         // Load followed people from the database
         GLOBALS.people.load_all_followed().await?;
 
+        // Load people lists, whose members are picked for along with followed people
+        GLOBALS.person_lists.load().await?;
+
+        // Load the posts still waiting to be taken by relays
+        GLOBALS.outbox.load().await?;
+
+        // Forget relay health history older than we keep
+        DbRelayMetric::prune().await?;
+
+        // Load our long-form drafts
+        GLOBALS.long_form.load().await?;
+
         // Load contact list from the database
         if let Some(pk) = GLOBALS.signer.public_key() {
             if let Some(event) = DbEvent::fetch_last_contact_list(pk.into()).await? {
@@ -209,6 +230,29 @@ impl Overlord {
             GLOBALS.viewed_events.insert(*id);
         }
 
+        // Load bookmarks into memory
+        GLOBALS.bookmarks.load().await?;
+
+        // Load mutes into memory, and note our last mute list
+        GLOBALS.mutes.load().await?;
+        if let Some(event) = crate::mutes::last_mute_list().await? {
+            GLOBALS.mutes.note_remote_list(&event);
+        }
+
+        // Load direct message encryption choices into memory
+        for channel in DbDmChannel::load_all().await?.drain(..) {
+            GLOBALS
+                .dm_encryption
+                .insert(channel.counterparty, channel.encryption);
+        }
+
+        // Load who signs people's zap receipts into memory
+        for person_zapper in DbPersonZapper::load_all().await?.drain(..) {
+            GLOBALS
+                .zappers
+                .insert(person_zapper.person.clone(), person_zapper);
+        }
+
         // Load event-seen data into memory
         GLOBALS.events.load_event_seen_data().await?;
 
@@ -233,6 +277,22 @@ impl Overlord {
             }
         }));
 
+        // Load what pruning has reclaimed so far
+        *GLOBALS.prune_stats.write() = PruneStats::load().await?;
+
+        // Start a pruner that applies the retention policy on schedule, and whenever
+        // the database outgrows its size budget
+        std::mem::drop(tokio::spawn(async move {
+            loop {
+                // check every 10 minutes
+                tokio::time::sleep(std::time::Duration::new(600, 0)).await;
+
+                if let Err(e) = crate::db::auto_prune().await {
+                    tracing::error!("Automatic prune failed: {}", e);
+                }
+            }
+        }));
+
         // Load relay lists from the database and process
         {
             let events: Vec<Event> = DbEvent::fetch_relay_lists().await?;
@@ -247,6 +307,33 @@ impl Overlord {
         }
 
         // Pick Relays and start Minions
+        self.start_minions().await?;
+
+        // Send whatever was left in the outbox last time
+        if !GLOBALS.settings.read().offline && !GLOBALS.outbox.is_empty() {
+            self.flush_outbox().await?;
+        }
+
+        'mainloop: loop {
+            match self.loop_handler().await {
+                Ok(keepgoing) => {
+                    if !keepgoing {
+                        break 'mainloop;
+                    }
+                }
+                Err(e) => {
+                    // Log them and keep looping
+                    tracing::error!("{}", e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Pick relays for the people we follow, and subscribe to our discovery,
+    // config, mentions and remote signer on our own relays
+    async fn start_minions(&mut self) -> Result<(), Error> {
         if !GLOBALS.settings.read().offline {
             self.pick_relays().await;
         }
@@ -265,6 +352,7 @@ impl Overlord {
                         detail: ToMinionPayloadDetail::SubscribeDiscover(followed.clone()),
                     },
                     persistent: true,
+                    outbox: false,
                 }],
             )
             .await?;
@@ -283,6 +371,7 @@ impl Overlord {
                         detail: ToMinionPayloadDetail::SubscribeConfig,
                     },
                     persistent: true,
+                    outbox: false,
                 }],
             )
             .await?;
@@ -303,25 +392,82 @@ impl Overlord {
                         detail: ToMinionPayloadDetail::SubscribeMentions,
                     },
                     persistent: true,
+                    outbox: false,
                 }],
             )
             .await?;
         }
 
-        'mainloop: loop {
-            match self.loop_handler().await {
-                Ok(keepgoing) => {
-                    if !keepgoing {
-                        break 'mainloop;
-                    }
-                }
-                Err(e) => {
-                    // Log them and keep looping
-                    tracing::error!("{}", e);
-                }
+        // Listen to our remote signer, if we have one
+        self.subscribe_remote_signer().await?;
+
+        // Listen to our wallet, if we have one
+        self.subscribe_wallet().await?;
+
+        Ok(())
+    }
+
+    // Shutdown every minion and wait for them to finish, without restarting
+    // any of their jobs
+    async fn stop_all_minions(&mut self) {
+        let _ = self.to_minions.send(ToMinionMessage {
+            target: "all".to_string(),
+            payload: ToMinionPayload {
+                job_id: 0,
+                detail: ToMinionPayloadDetail::Shutdown,
+            },
+        });
+
+        let deadline = tokio::time::Instant::now() + std::time::Duration::new(10, 0);
+        while !self.minions.is_empty() {
+            match tokio::time::timeout_at(deadline, self.minions.join_next()).await {
+                Ok(Some(_)) => continue,
+                _ => break,
             }
         }
+        self.minions.shutdown().await;
+
+        for (_, url) in self.minions_task_url.drain() {
+            GLOBALS.relay_picker.relay_disconnected(&url);
+        }
+        GLOBALS.connected_relays.clear();
+    }
+
+    async fn subscribe_remote_signer(&mut self) -> Result<(), Error> {
+        if let Some((relay_url, local_pubkey)) = GLOBALS.signer.remote_signer_subscription() {
+            self.engage_minion(
+                relay_url,
+                vec![RelayJob {
+                    reason: "remote-signer",
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::SubscribeRemoteSigner(local_pubkey.into()),
+                    },
+                    persistent: true,
+                    outbox: false,
+                }],
+            )
+            .await?;
+        }
+        Ok(())
+    }
 
+    async fn subscribe_wallet(&mut self) -> Result<(), Error> {
+        if let Some((relay_url, client_pubkey)) = GLOBALS.wallet.subscription() {
+            self.engage_minion(
+                relay_url,
+                vec![RelayJob {
+                    reason: "wallet",
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::SubscribeWallet(client_pubkey.into()),
+                    },
+                    persistent: true,
+                    outbox: false,
+                }],
+            )
+            .await?;
+        }
         Ok(())
     }
 
@@ -367,6 +513,7 @@ impl Overlord {
                         ),
                     },
                     persistent: false,
+                    outbox: false,
                 },
                 RelayJob {
                     // Until NIP-65 is in widespread use, we should listen for mentions
@@ -377,6 +524,7 @@ impl Overlord {
                         detail: ToMinionPayloadDetail::SubscribeMentions,
                     },
                     persistent: false,
+                    outbox: false,
                 },
             ],
         )
@@ -385,9 +533,24 @@ impl Overlord {
         Ok(())
     }
 
-    async fn engage_minion(&mut self, url: RelayUrl, mut jobs: Vec<RelayJob>) -> Result<(), Error> {
+    async fn engage_minion(&mut self, url: RelayUrl, jobs: Vec<RelayJob>) -> Result<(), Error> {
+        // Track what becomes of the events we post. The user's posts are also
+        // kept until the relay answers, unless it has had them too often.
+        let mut wanted: Vec<RelayJob> = Vec::with_capacity(jobs.len());
+        for job in jobs.into_iter() {
+            if let ToMinionPayloadDetail::PostEvent(event) = &job.payload.detail {
+                if job.outbox && !GLOBALS.outbox.queue(event, &url).await? {
+                    continue;
+                }
+                GLOBALS.deliveries.sent(event, &url);
+            }
+            wanted.push(job);
+        }
+        let mut jobs = wanted;
+
         // Do not connect if we are offline
         if GLOBALS.settings.read().offline {
+            GLOBALS.deliveries.relay_gone(&url);
             return Ok(());
         }
 
@@ -403,6 +566,26 @@ impl Overlord {
                 refmut.value_mut().push(job);
             }
         } else {
+            // Whatever is still waiting for this relay goes out with it
+            for event in GLOBALS.outbox.queued_for(&url) {
+                let included = jobs.iter().any(|job| {
+                    matches!(&job.payload.detail,
+                             ToMinionPayloadDetail::PostEvent(e) if e.id == event.id)
+                });
+                if !included && GLOBALS.outbox.queue(&event, &url).await? {
+                    GLOBALS.deliveries.sent(&event, &url);
+                    jobs.push(RelayJob {
+                        reason: "posting-queued",
+                        payload: ToMinionPayload {
+                            job_id: rand::random::<u64>(),
+                            detail: ToMinionPayloadDetail::PostEvent(Box::new(event)),
+                        },
+                        persistent: false,
+                        outbox: true,
+                    });
+                }
+            }
+
             // Start up the minion
             let mut minion = Minion::new(url.clone()).await?;
             let payloads = jobs.iter().map(|job| job.payload.clone()).collect();
@@ -418,6 +601,55 @@ impl Overlord {
         Ok(())
     }
 
+    // Jobs from tasks the overlord spawned go through the inbox like any other
+    fn engage_minion_later(url: RelayUrl, jobs: Vec<RelayJob>) {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::ReengageMinion(url, jobs));
+    }
+
+    // Everything we post on the user's command goes out through here, so that
+    // the command line hears about it
+    fn post_to_relays(
+        event: &Event,
+        relay_urls: Vec<RelayUrl>,
+        reason: &'static str,
+        outbox: bool,
+    ) {
+        let _ = GLOBALS.posted_events.send(event.id);
+
+        for url in relay_urls {
+            tracing::debug!("Asking {} to post ({})", &url, reason);
+
+            Self::engage_minion_later(
+                url,
+                vec![RelayJob {
+                    reason,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvent(Box::new(event.clone())),
+                    },
+                    persistent: false,
+                    outbox,
+                }],
+            );
+        }
+    }
+
+    // Signing may wait on a remote signer for a minute (it may be asking a
+    // human), so anything that signs runs in a task of its own instead of
+    // holding up the overlord loop.
+    fn spawn_signing<F>(job: F)
+    where
+        F: Future<Output = Result<(), Error>> + Send + 'static,
+    {
+        std::mem::drop(tokio::spawn(async move {
+            if let Err(e) = job.await {
+                tracing::error!("{}", e);
+            }
+        }));
+    }
+
     #[allow(unused_assignments)]
     async fn loop_handler(&mut self) -> Result<bool, Error> {
         let mut keepgoing: bool = true;
@@ -516,6 +748,9 @@ impl Overlord {
     }
 
     async fn recover_from_minion_exit(&mut self, url: RelayUrl, jobs: Option<Vec<RelayJob>>) {
+        // Posts it has not answered yet, it will not
+        GLOBALS.deliveries.relay_gone(&url);
+
         // For people we are following, pick relays
         GLOBALS.relay_picker.relay_disconnected(&url);
         if let Err(e) = GLOBALS.relay_picker.refresh_person_relay_scores().await {
@@ -547,11 +782,23 @@ impl Overlord {
 
     async fn handle_message(&mut self, message: ToOverlordMessage) -> Result<bool, Error> {
         match message {
+            ToOverlordMessage::AddBookmark(id) => {
+                GLOBALS.bookmarks.add(id).await?;
+                Self::spawn_signing(Self::push_bookmarks());
+            }
+            ToOverlordMessage::AddMute(kind, value) => {
+                GLOBALS.mutes.add(kind, value).await?;
+                GLOBALS.feed.recompute().await?;
+            }
             ToOverlordMessage::AddRelay(relay_str) => {
                 let dbrelay = DbRelay::new(relay_str.clone());
                 DbRelay::insert(dbrelay.clone()).await?;
                 GLOBALS.all_relays.insert(relay_str, dbrelay);
             }
+            ToOverlordMessage::AddToPersonList(name, pubkey) => {
+                GLOBALS.person_lists.add(&name, pubkey).await?;
+                Self::spawn_signing(Self::push_person_list(name));
+            }
             ToOverlordMessage::AdjustRelayUsageBit(relay_url, bit, value) => {
                 if let Some(mut dbrelay) = GLOBALS.all_relays.get_mut(&relay_url) {
                     dbrelay.adjust_usage_bit_memory_only(bit, value);
@@ -561,24 +808,108 @@ impl Overlord {
                 }
             }
             ToOverlordMessage::AdvertiseRelayList => {
-                self.advertise_relay_list().await?;
+                Self::spawn_signing(Self::advertise_relay_list());
             }
             ToOverlordMessage::ChangePassphrase(mut old, mut new) => {
                 GLOBALS.signer.change_passphrase(&old, &new)?;
+                if let Err(e) = GLOBALS.wallet.change_passphrase(&old, &new).await {
+                    *GLOBALS.status_message.write().await =
+                        format!("Could not re-encrypt your wallet connection: {}", e);
+                }
                 old.zeroize();
                 new.zeroize();
             }
             ToOverlordMessage::ClearFollowing => {
                 self.clear_following().await?;
             }
+            ToOverlordMessage::ConnectRemoteSigner(uri) => {
+                match GLOBALS.signer.set_remote_signer_from_bunker_uri(&uri) {
+                    Ok(_) => {
+                        self.subscribe_remote_signer().await?;
+                        std::mem::drop(tokio::spawn(async move {
+                            if let Err(e) = GLOBALS.signer.pair_remote_signer().await {
+                                tracing::error!("{}", e);
+                                *GLOBALS.status_message.write().await =
+                                    format!("Remote signer pairing failed: {}", e);
+                            }
+                        }));
+                    }
+                    Err(e) => *GLOBALS.status_message.write().await = format!("{}", e),
+                }
+            }
+            ToOverlordMessage::CloseRelaySearch => {
+                GLOBALS.feed.close_relay_search();
+                let _ = self.to_minions.send(ToMinionMessage {
+                    target: "all".to_string(),
+                    payload: ToMinionPayload {
+                        job_id: 0,
+                        detail: ToMinionPayloadDetail::UnsubscribeSearch,
+                    },
+                });
+            }
+            ToOverlordMessage::ConnectWallet(uri, mut password) => {
+                let result = GLOBALS.wallet.connect(&uri, &password).await;
+                password.zeroize();
+                match result {
+                    Ok(()) => {
+                        self.subscribe_wallet().await?;
+                        *GLOBALS.status_message.write().await = "Wallet connected.".to_owned();
+                    }
+                    Err(e) => {
+                        *GLOBALS.status_message.write().await =
+                            format!("Could not connect wallet: {}", e)
+                    }
+                }
+            }
+            ToOverlordMessage::CreateIdentity(name) => {
+                let name = name.trim().to_owned();
+                if name.is_empty() {
+                    *GLOBALS.status_message.write().await = "An identity needs a name.".to_owned();
+                } else if let Err(e) = DbIdentity::create(name).await {
+                    *GLOBALS.status_message.write().await =
+                        format!("Could not create identity: {}", e);
+                }
+                *GLOBALS.identities.write() = DbIdentity::fetch_all().await?;
+            }
+            ToOverlordMessage::CreatePersonList(name) => {
+                if name.trim().is_empty() {
+                    *GLOBALS.status_message.write().await = "A list needs a name.".to_owned();
+                } else {
+                    GLOBALS.person_lists.create(name).await?;
+                }
+            }
             ToOverlordMessage::DelegationReset => {
                 Self::delegation_reset().await?;
             }
+            ToOverlordMessage::DeleteIdentity(name) => {
+                if let Err(e) = DbIdentity::delete(name).await {
+                    *GLOBALS.status_message.write().await =
+                        format!("Could not delete identity: {}", e);
+                }
+                *GLOBALS.identities.write() = DbIdentity::fetch_all().await?;
+            }
+            ToOverlordMessage::DeleteBookmark(id) => {
+                GLOBALS.bookmarks.remove(id).await?;
+                Self::spawn_signing(Self::push_bookmarks());
+            }
+            ToOverlordMessage::DeleteMute(kind, value) => {
+                GLOBALS.mutes.remove(kind, value).await?;
+                GLOBALS.feed.recompute().await?;
+            }
+            ToOverlordMessage::DeleteLongFormDraft(identifier) => {
+                GLOBALS.long_form.delete(identifier).await?;
+            }
+            ToOverlordMessage::DeletePersonList(name) => {
+                GLOBALS.person_lists.delete(&name).await?;
+                Self::spawn_signing(Self::push_person_list(name));
+            }
             ToOverlordMessage::DeletePost(id) => {
-                self.delete(id).await?;
+                Self::spawn_signing(Self::delete(id));
             }
             ToOverlordMessage::DeletePriv => {
                 GLOBALS.signer.delete_identity();
+                // Its secret was encrypted with the passphrase of the deleted key
+                GLOBALS.wallet.disconnect().await?;
                 Self::delegation_reset().await?;
                 *GLOBALS.status_message.write().await = "Identity deleted.".to_string()
             }
@@ -587,6 +918,33 @@ impl Overlord {
                 Self::delegation_reset().await?;
                 GLOBALS.signer.save_through_settings().await?;
             }
+            ToOverlordMessage::DisconnectRemoteSigner => {
+                if let Some((relay_url, _)) = GLOBALS.signer.remote_signer_status() {
+                    let _ = self.to_minions.send(ToMinionMessage {
+                        target: relay_url.0,
+                        payload: ToMinionPayload {
+                            job_id: 0,
+                            detail: ToMinionPayloadDetail::UnsubscribeRemoteSigner,
+                        },
+                    });
+                }
+                GLOBALS.signer.disconnect_remote_signer();
+            }
+            ToOverlordMessage::DisconnectWallet => {
+                if let Some(relay_url) = GLOBALS.wallet.relay() {
+                    let _ = self.to_minions.send(ToMinionMessage {
+                        target: relay_url.0,
+                        payload: ToMinionPayload {
+                            job_id: 0,
+                            detail: ToMinionPayloadDetail::UnsubscribeWallet,
+                        },
+                    });
+                }
+                GLOBALS.wallet.disconnect().await?;
+            }
+            ToOverlordMessage::DropFromOutbox(id, relay_url) => {
+                GLOBALS.outbox.remove(id, &relay_url).await?;
+            }
             ToOverlordMessage::DropRelay(relay_url) => {
                 let _ = self.to_minions.send(ToMinionMessage {
                     target: relay_url.0,
@@ -596,6 +954,17 @@ impl Overlord {
                     },
                 });
             }
+            ToOverlordMessage::ExportEvents(export, path) => {
+                std::mem::drop(tokio::spawn(async move {
+                    *GLOBALS.status_message.write().await =
+                        match crate::archive::export(export, path.clone()).await {
+                            Ok(count) => {
+                                format!("Exported {} events to {}", count, path.display())
+                            }
+                            Err(e) => format!("Export failed: {}", e),
+                        };
+                }));
+            }
             ToOverlordMessage::FetchEvent(id, relay_urls) => {
                 // We presume the caller already checked GLOBALS.events.get() and it was not there
                 for url in relay_urls.iter() {
@@ -608,11 +977,22 @@ impl Overlord {
                                 detail: ToMinionPayloadDetail::FetchEvent(id.into()),
                             },
                             persistent: false,
+                            outbox: false,
                         }],
                     )
                     .await?;
                 }
             }
+            ToOverlordMessage::FindZapper(pubkey) => {
+                std::mem::drop(tokio::spawn(async move {
+                    if let Err(e) = crate::zap::find_zapper(pubkey).await {
+                        tracing::error!("{}", e);
+                    }
+                }));
+            }
+            ToOverlordMessage::FlushOutbox => {
+                self.flush_outbox().await?;
+            }
             ToOverlordMessage::FollowPubkeyAndRelay(pubkeystr, relay) => {
                 self.follow_pubkey_and_relay(pubkeystr, relay).await?;
             }
@@ -640,6 +1020,20 @@ impl Overlord {
                 }
                 DbRelay::update_hidden(relay_url, hidden).await?;
             }
+            ToOverlordMessage::ImportEvents(path) => {
+                std::mem::drop(tokio::spawn(async move {
+                    *GLOBALS.status_message.write().await =
+                        match crate::archive::import(path.clone()).await {
+                            Ok((imported, rejected)) => format!(
+                                "Imported {} events from {}, rejected {}",
+                                imported,
+                                path.display(),
+                                rejected
+                            ),
+                            Err(e) => format!("Import failed: {}", e),
+                        };
+                }));
+            }
             ToOverlordMessage::ImportPriv(mut import_priv, mut password) => {
                 if import_priv.starts_with("ncryptsec") {
                     let epk = EncryptedPrivateKey(import_priv);
@@ -675,7 +1069,15 @@ impl Overlord {
                 }
             }
             ToOverlordMessage::Like(id, pubkey) => {
-                self.post_like(id, pubkey).await?;
+                Self::spawn_signing(Self::post_like(id, pubkey));
+            }
+            ToOverlordMessage::LoadRelayMetrics(url, since) => {
+                std::mem::drop(tokio::spawn(async move {
+                    match DbRelayMetric::fetch(url.clone(), since).await {
+                        Ok(metrics) => *GLOBALS.relay_metrics.write() = Some((url, since, metrics)),
+                        Err(e) => tracing::error!("{}", e),
+                    }
+                }));
             }
             ToOverlordMessage::MinionIsReady => {
                 // currently ignored
@@ -690,6 +1092,10 @@ impl Overlord {
                     }
                 }
             }
+            ToOverlordMessage::NostrConnect(relay_url) => {
+                GLOBALS.signer.set_remote_signer_for_nostrconnect(relay_url);
+                self.subscribe_remote_signer().await?;
+            }
             ToOverlordMessage::PickRelays => {
                 // When manually doing this, we refresh person_relay scores first which
                 // often change if the user just added follows.
@@ -706,6 +1112,14 @@ impl Overlord {
                     }
                 }));
             }
+            ToOverlordMessage::PreviewRetention(policy) => {
+                std::mem::drop(tokio::spawn(async move {
+                    match policy.preview().await {
+                        Ok(preview) => *GLOBALS.retention_preview.write() = Some(preview),
+                        Err(e) => tracing::error!("{}", e),
+                    }
+                }));
+            }
             ToOverlordMessage::PruneDatabase => {
                 std::mem::drop(tokio::spawn(async move {
                     if let Err(e) = crate::db::prune().await {
@@ -713,17 +1127,29 @@ impl Overlord {
                     }
                 }));
             }
-            ToOverlordMessage::Post(content, tags, reply_to) => {
-                self.post(content, tags, reply_to).await?;
+            ToOverlordMessage::PublishLongForm(draft) => {
+                Self::spawn_signing(async move {
+                    if let Err(e) = Self::publish_long_form(draft).await {
+                        *GLOBALS.status_message.write().await =
+                            format!("Could not publish the article: {}", e);
+                    }
+                    Ok(())
+                });
+            }
+            ToOverlordMessage::Post(content, tags, reply_to, relays) => {
+                Self::spawn_signing(Self::post(content, tags, reply_to, relays));
             }
             ToOverlordMessage::PullFollow => {
                 self.pull_following().await?;
             }
             ToOverlordMessage::PushFollow => {
-                self.push_following().await?;
+                Self::spawn_signing(Self::push_following());
+            }
+            ToOverlordMessage::PushMuteList => {
+                Self::spawn_signing(Self::push_mute_list());
             }
             ToOverlordMessage::PushMetadata(metadata) => {
-                self.push_metadata(metadata).await?;
+                Self::spawn_signing(Self::push_metadata(metadata));
             }
             ToOverlordMessage::RankRelay(relay_url, rank) => {
                 if let Some(mut dbrelay) = GLOBALS.all_relays.get_mut(&relay_url) {
@@ -737,13 +1163,52 @@ impl Overlord {
             ToOverlordMessage::RefreshFollowedMetadata => {
                 self.refresh_followed_metadata().await?;
             }
+            ToOverlordMessage::RemoveFromPersonList(name, pubkey) => {
+                GLOBALS.person_lists.remove(&name, pubkey).await?;
+                Self::spawn_signing(Self::push_person_list(name));
+            }
+            ToOverlordMessage::RetryDelivery(id) => {
+                self.retry_delivery(id).await?;
+            }
             ToOverlordMessage::Repost(id) => {
-                self.repost(id).await?;
+                Self::spawn_signing(Self::repost(id));
+            }
+            ToOverlordMessage::RepublishHistory(relay_url) => {
+                self.republish_history(relay_url).await?;
+            }
+            ToOverlordMessage::SaveLongFormDraft(draft) => {
+                GLOBALS.long_form.save(draft).await?;
             }
             ToOverlordMessage::SaveSettings => {
                 let settings = GLOBALS.settings.read().clone();
                 settings.save().await?;
                 tracing::debug!("Settings saved.");
+
+                // We may have just come back online
+                if !settings.offline && !GLOBALS.outbox.is_empty() {
+                    self.flush_outbox().await?;
+                }
+            }
+            ToOverlordMessage::SearchLocal(search) => {
+                self.search_local(search).await?;
+            }
+            ToOverlordMessage::SearchRelays(query) => {
+                self.search_relays(query).await?;
+            }
+            ToOverlordMessage::SelectPostRelays(tagged) => {
+                std::mem::drop(tokio::spawn(async move {
+                    match select_post_relays(tagged.clone()).await {
+                        Ok(relays) => *GLOBALS.post_relays.write() = Some((tagged, relays)),
+                        Err(e) => tracing::error!("{}", e),
+                    }
+                }));
+            }
+            ToOverlordMessage::SendDirectMessage(pubkey, content) => {
+                Self::spawn_signing(Self::send_direct_message(pubkey, content));
+            }
+            ToOverlordMessage::SetDmEncryption(pubkey, encryption) => {
+                GLOBALS.dm_encryption.insert(pubkey.clone(), encryption);
+                DbDmChannel::set_encryption(pubkey, encryption).await?;
             }
             ToOverlordMessage::SetActivePerson(pubkey) => {
                 GLOBALS.people.set_active_person(pubkey).await?;
@@ -755,11 +1220,18 @@ impl Overlord {
                 tracing::info!("Overlord shutting down");
                 return Ok(false);
             }
+            ToOverlordMessage::SwitchIdentity(name) => {
+                self.switch_identity(name).await?;
+            }
             ToOverlordMessage::UnlockKey(mut password) => {
                 if let Err(e) = GLOBALS.signer.unlock_encrypted_private_key(&password) {
                     tracing::error!("{}", e);
                     *GLOBALS.status_message.write().await =
                         "Could not decrypt key with that password.".to_owned();
+                } else if let Err(e) = GLOBALS.wallet.unlock(&password) {
+                    tracing::error!("{}", e);
+                    *GLOBALS.status_message.write().await =
+                        "Could not decrypt your wallet connection with that password.".to_owned();
                 };
                 password.zeroize();
 
@@ -768,10 +1240,22 @@ impl Overlord {
                 GLOBALS.settings.write().public_key = Some(public_key);
                 let settings = GLOBALS.settings.read().clone();
                 settings.save().await?;
+
+                // Bookmarks changed while the key was locked go out now
+                if GLOBALS.signer.is_ready() && GLOBALS.bookmarks.is_unpublished().await? {
+                    Self::spawn_signing(Self::push_bookmarks());
+                }
             }
             ToOverlordMessage::UpdateFollowing(merge) => {
                 self.update_following(merge).await?;
             }
+            ToOverlordMessage::UpdateMuteList(merge) => {
+                if let Err(e) = GLOBALS.mutes.update_from_list(merge).await {
+                    *GLOBALS.status_message.write().await =
+                        format!("Could not read your mute list: {}", e);
+                }
+                GLOBALS.feed.recompute().await?;
+            }
             ToOverlordMessage::UpdateMetadata(pubkey) => {
                 let best_relays =
                     DbPersonRelay::get_best_relays(pubkey.clone(), Direction::Write).await?;
@@ -794,6 +1278,7 @@ impl Overlord {
                                 ]),
                             },
                             persistent: false,
+                            outbox: false,
                         }],
                     )
                     .await?;
@@ -827,11 +1312,15 @@ impl Overlord {
                                 detail: ToMinionPayloadDetail::TempSubscribeMetadata(pubkeys),
                             },
                             persistent: false,
+                            outbox: false,
                         }],
                     )
                     .await?;
                 }
             }
+            ToOverlordMessage::Zap(id, pubkey, msats, comment) => {
+                self.zap(id, pubkey, msats, comment).await?;
+            }
         }
 
         Ok(true)
@@ -882,13 +1371,13 @@ impl Overlord {
     }
 
     async fn post(
-        &mut self,
         mut content: String,
         mut tags: Vec<Tag>,
         reply_to: Option<Id>,
+        relays: Option<Vec<RelayUrl>>,
     ) -> Result<(), Error> {
         // We will fill this just before we create the event
-        let mut tagged_pubkeys: Vec<PublicKeyHex>;
+        let tagged_pubkeys: Vec<PublicKeyHex>;
 
         let event = {
             let public_key = match GLOBALS.signer.public_key() {
@@ -1013,59 +1502,228 @@ impl Overlord {
 
             GLOBALS
                 .signer
-                .sign_preevent(pre_event, pow, Some(work_sender))?
+                .sign_preevent(pre_event, pow, Some(work_sender))
+                .await?
         };
 
         // Process this event locally
         crate::process::process_new_event(&event, false, None, None).await?;
 
-        // Determine which relays to post this to
-        let mut relay_urls: Vec<RelayUrl> = Vec::new();
-        {
-            // Get 'read' relays for everybody tagged in the event.
-            // Currently we take the 2 best read relays per person
-            for pubkey in tagged_pubkeys.drain(..) {
-                let best_relays: Vec<RelayUrl> =
-                    DbPersonRelay::get_best_relays(pubkey, Direction::Read)
-                        .await?
-                        .drain(..)
-                        .take(2)
-                        .map(|(u, _)| u)
-                        .collect();
-                relay_urls.extend(best_relays);
-            }
+        // Determine which relays to post this to: those the user settled on in
+        // the composer, or else our write relays and the inboxes of everybody tagged
+        let mut relay_urls: Vec<RelayUrl> = match relays {
+            Some(relays) => relays,
+            None => select_post_relays(tagged_pubkeys)
+                .await?
+                .drain(..)
+                .map(|r| r.url)
+                .collect(),
+        };
+        relay_urls.sort();
+        relay_urls.dedup();
 
-            // Get all of the relays that we write to
-            let write_relay_urls: Vec<RelayUrl> =
-                GLOBALS.relays_url_filtered(|r| r.has_usage_bits(DbRelay::WRITE));
-            relay_urls.extend(write_relay_urls);
+        Self::post_to_relays(&event, relay_urls, "posting", true);
 
-            relay_urls.sort();
-            relay_urls.dedup();
-        }
+        Ok(())
+    }
 
-        for url in relay_urls {
-            // Send it the event to post
-            tracing::debug!("Asking {} to post", &url);
+    /// Send everything waiting in the outbox to its relay
+    async fn flush_outbox(&mut self) -> Result<(), Error> {
+        if GLOBALS.settings.read().offline {
+            *GLOBALS.status_message.write().await =
+                "You are offline. Queued posts will be sent when you go online.".to_owned();
+            return Ok(());
+        }
 
-            self.engage_minion(
-                url.clone(),
-                vec![RelayJob {
-                    reason: "posting",
+        for url in GLOBALS.outbox.relays() {
+            let jobs: Vec<RelayJob> = GLOBALS
+                .outbox
+                .queued_for(&url)
+                .drain(..)
+                .map(|event| RelayJob {
+                    reason: "posting-queued",
                     payload: ToMinionPayload {
                         job_id: rand::random::<u64>(),
-                        detail: ToMinionPayloadDetail::PostEvent(Box::new(event.clone())),
+                        detail: ToMinionPayloadDetail::PostEvent(Box::new(event)),
                     },
                     persistent: false,
-                }],
-            )
+                    outbox: true,
+                })
+                .collect();
+            self.engage_minion(url, jobs).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn retry_delivery(&mut self, id: Id) -> Result<(), Error> {
+        let event = match GLOBALS.events.get(&id) {
+            Some(event) => event,
+            None => match DbEvent::fetch_by_ids(vec![id.into()]).await?.pop() {
+                Some(dbevent) => serde_json::from_str(&dbevent.raw)?,
+                None => return Err("Cannot find the post to send again.".into()),
+            },
+        };
+
+        for url in GLOBALS.deliveries.failed_relays(&id) {
+            tracing::debug!("Asking {} to post again", &url);
+
+            self.engage_minion(
+                url,
+                vec![RelayJob {
+                    reason: "posting",
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvent(Box::new(event.clone())),
+                    },
+                    persistent: false,
+                    outbox: true,
+                }],
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn search_local(&mut self, search: EventSearch) -> Result<(), Error> {
+        let events = DbEvent::search(search).await?;
+
+        // Bring them into memory so they can be rendered
+        for event in events.iter() {
+            GLOBALS.events.insert(event.clone(), None);
+        }
+
+        *GLOBALS.status_message.write().await = match events.len() {
+            0 => "No notes found.".to_owned(),
+            1 => "Found 1 note.".to_owned(),
+            n => format!("Found {} notes.", n),
+        };
+        GLOBALS
+            .feed
+            .set_search_results(events.iter().map(|e| e.id).collect());
+
+        Ok(())
+    }
+
+    // Ask the relays we are connected to, and our read relays that told us they
+    // support NIP-50, to search. Minions only pass it on if the relay supports it.
+    async fn search_relays(&mut self, query: String) -> Result<(), Error> {
+        GLOBALS.feed.start_relay_search(query.clone());
+
+        let mut relay_urls: Vec<RelayUrl> = GLOBALS
+            .connected_relays
+            .iter()
+            .map(|r| r.key().to_owned())
+            .collect();
+        let searchable = GLOBALS.relays_url_filtered(|r| {
+            r.has_usage_bits(DbRelay::READ)
+                && matches!(&r.nip11, Some(nip11) if nip11.supports_nip(50))
+        });
+        for url in searchable {
+            if !relay_urls.contains(&url) {
+                relay_urls.push(url);
+            }
+        }
+
+        for url in relay_urls.iter() {
+            self.engage_minion(
+                url.to_owned(),
+                vec![RelayJob {
+                    reason: "search",
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::SubscribeSearch(query.clone()),
+                    },
+                    persistent: false,
+                    outbox: false,
+                }],
+            )
             .await?;
         }
 
+        *GLOBALS.status_message.write().await = if relay_urls.is_empty() {
+            "None of your relays is known to support NIP-50 search.".to_owned()
+        } else {
+            format!(
+                "Asked {} relays to search, those that support NIP-50 will answer.",
+                relay_urls.len()
+            )
+        };
+
         Ok(())
     }
 
-    async fn advertise_relay_list(&mut self) -> Result<(), Error> {
+    async fn send_direct_message(recipient: PublicKeyHex, content: String) -> Result<(), Error> {
+        let public_key = match GLOBALS.signer.public_key() {
+            Some(pk) => pk,
+            None => {
+                tracing::warn!("No public key! Not posting");
+                return Ok(());
+            }
+        };
+
+        let recipient_pubkey = PublicKey::try_from_hex_string(recipient.as_str())?;
+
+        let event = {
+            let encryption = GLOBALS.feed.dm_outgoing_encryption(&recipient);
+            let ciphertext = GLOBALS
+                .signer
+                .encrypt_message(&recipient_pubkey, &content, encryption)
+                .await?;
+
+            let pre_event = PreEvent {
+                pubkey: public_key,
+                created_at: Unixtime::now().unwrap(),
+                kind: EventKind::EncryptedDirectMessage,
+                tags: vec![Tag::Pubkey {
+                    pubkey: recipient.clone(),
+                    recommended_relay_url: None,
+                    petname: None,
+                }],
+                content: ciphertext,
+                ots: None,
+            };
+
+            GLOBALS.signer.sign_preevent(pre_event, None, None).await?
+        };
+
+        // Process this event locally
+        crate::process::process_new_event(&event, false, None, None).await?;
+
+        // Post to the relays the recipient reads from, and to the relays we write
+        // to (so we can see our side of the conversation on other clients)
+        let mut relay_urls: Vec<RelayUrl> = Vec::new();
+        {
+            let num_relays_per_person = GLOBALS.settings.read().num_relays_per_person as usize;
+            let best_relays: Vec<RelayUrl> =
+                DbPersonRelay::get_best_relays(recipient, Direction::Read)
+                    .await?
+                    .drain(..)
+                    .take(num_relays_per_person.max(2))
+                    .map(|(u, _)| u)
+                    .collect();
+            if best_relays.is_empty() {
+                *GLOBALS.status_message.write().await =
+                    "We don't know where they read from; sending to your write relays only."
+                        .to_owned();
+            }
+            relay_urls.extend(best_relays);
+
+            let write_relay_urls: Vec<RelayUrl> =
+                GLOBALS.relays_url_filtered(|r| r.has_usage_bits(DbRelay::WRITE));
+            relay_urls.extend(write_relay_urls);
+
+            relay_urls.sort();
+            relay_urls.dedup();
+        }
+
+        Self::post_to_relays(&event, relay_urls, "posting", true);
+
+        Ok(())
+    }
+
+    async fn advertise_relay_list() -> Result<(), Error> {
         let public_key = match GLOBALS.signer.public_key() {
             Some(pk) => pk,
             None => {
@@ -1104,33 +1762,17 @@ impl Overlord {
             ots: None,
         };
 
-        let event = GLOBALS.signer.sign_preevent(pre_event, None, None)?;
+        let event = GLOBALS.signer.sign_preevent(pre_event, None, None).await?;
 
         let advertise_to_relay_urls: Vec<RelayUrl> =
             GLOBALS.relays_url_filtered(|r| r.has_usage_bits(DbRelay::ADVERTISE));
 
-        for relay_url in advertise_to_relay_urls {
-            // Send it the event to post
-            tracing::debug!("Asking {} to post", &relay_url);
-
-            self.engage_minion(
-                relay_url.to_owned(),
-                vec![RelayJob {
-                    reason: "advertising",
-                    payload: ToMinionPayload {
-                        job_id: rand::random::<u64>(),
-                        detail: ToMinionPayloadDetail::PostEvent(Box::new(event.clone())),
-                    },
-                    persistent: false,
-                }],
-            )
-            .await?;
-        }
+        Self::post_to_relays(&event, advertise_to_relay_urls, "advertising", false);
 
         Ok(())
     }
 
-    async fn post_like(&mut self, id: Id, pubkey: PublicKey) -> Result<(), Error> {
+    async fn post_like(id: Id, pubkey: PublicKey) -> Result<(), Error> {
         let event = {
             let public_key = match GLOBALS.signer.public_key() {
                 Some(pk) => pk,
@@ -1181,29 +1823,15 @@ impl Overlord {
 
             GLOBALS
                 .signer
-                .sign_preevent(pre_event, pow, Some(work_sender))?
+                .sign_preevent(pre_event, pow, Some(work_sender))
+                .await?
         };
 
-        let relays: Vec<DbRelay> = GLOBALS.relays_filtered(|r| r.has_usage_bits(DbRelay::WRITE));
+        let relays: Vec<RelayUrl> =
+            GLOBALS.relays_url_filtered(|r| r.has_usage_bits(DbRelay::WRITE));
         // FIXME - post it to relays we have seen it on.
 
-        for relay in relays {
-            // Send it the event to post
-            tracing::debug!("Asking {} to post", &relay.url);
-
-            self.engage_minion(
-                relay.url.clone(),
-                vec![RelayJob {
-                    reason: "post-like",
-                    payload: ToMinionPayload {
-                        job_id: rand::random::<u64>(),
-                        detail: ToMinionPayloadDetail::PostEvent(Box::new(event.clone())),
-                    },
-                    persistent: false,
-                }],
-            )
-            .await?;
-        }
+        Self::post_to_relays(&event, relays, "post-like", true);
 
         // Process the message for ourself
         crate::process::process_new_event(&event, false, None, None).await?;
@@ -1228,6 +1856,7 @@ impl Overlord {
                         detail: ToMinionPayloadDetail::PullFollowing,
                     },
                     persistent: false,
+                    outbox: false,
                 }],
             )
             .await?;
@@ -1236,39 +1865,88 @@ impl Overlord {
         Ok(())
     }
 
-    async fn push_following(&mut self) -> Result<(), Error> {
+    async fn push_following() -> Result<(), Error> {
         let event = GLOBALS.people.generate_contact_list_event().await?;
 
         // Push to all of the relays we post to
-        let relays: Vec<DbRelay> = GLOBALS.relays_filtered(|r| r.has_usage_bits(DbRelay::WRITE));
+        let relays: Vec<RelayUrl> =
+            GLOBALS.relays_url_filtered(|r| r.has_usage_bits(DbRelay::WRITE));
 
-        for relay in relays {
-            // Send it the event to pull our followers
-            tracing::debug!("Pushing ContactList to {}", &relay.url);
+        Self::post_to_relays(&event, relays, "pushing-contacts", false);
 
-            self.engage_minion(
-                relay.url.clone(),
-                vec![RelayJob {
-                    reason: "pushing-contacts",
-                    payload: ToMinionPayload {
-                        job_id: rand::random::<u64>(),
-                        detail: ToMinionPayloadDetail::PostEvent(Box::new(event.clone())),
-                    },
-                    persistent: false,
-                }],
-            )
-            .await?;
+        Ok(())
+    }
+
+    async fn push_bookmarks() -> Result<(), Error> {
+        if !GLOBALS.signer.is_ready() {
+            *GLOBALS.status_message.write().await =
+                "Bookmarks saved. Unlock your key to publish them.".to_owned();
+            return Ok(());
         }
 
+        let event = GLOBALS.bookmarks.generate_list_event().await?;
+
+        // The list on relays is now the one we have here
+        GLOBALS.bookmarks.set_last_edit(event.created_at).await?;
+
+        Self::publish_to_write_relays(event, "pushing-bookmarks", false).await
+    }
+
+    /// Keep our own copy of an event we signed (relays will not send it back to
+    /// us, and it may replace an older version) and push it to all of the relays
+    /// we post to. With `outbox`, it stays queued until those relays answer.
+    async fn publish_to_write_relays(
+        event: Event,
+        reason: &'static str,
+        outbox: bool,
+    ) -> Result<(), Error> {
+        crate::process::process_new_event(&event, true, None, None).await?;
+
+        let relays: Vec<RelayUrl> =
+            GLOBALS.relays_url_filtered(|r| r.has_usage_bits(DbRelay::WRITE));
+
+        Self::post_to_relays(&event, relays, reason, outbox);
+
+        Ok(())
+    }
+
+    async fn publish_long_form(draft: DbLongFormDraft) -> Result<(), Error> {
+        let event = GLOBALS.long_form.generate_event(draft).await?;
+
+        Self::publish_to_write_relays(event, "posting", true).await?;
+
+        *GLOBALS.status_message.write().await = "Article published.".to_owned();
+
         Ok(())
     }
 
+    async fn push_person_list(name: String) -> Result<(), Error> {
+        if !GLOBALS.signer.is_ready() {
+            *GLOBALS.status_message.write().await =
+                format!("List {} saved. Unlock your key to publish it.", name);
+            return Ok(());
+        }
+
+        let event = GLOBALS.person_lists.generate_list_event(&name).await?;
+
+        Self::publish_to_write_relays(event, "pushing-person-list", false).await
+    }
+
+    async fn push_mute_list() -> Result<(), Error> {
+        let event = GLOBALS.mutes.generate_list_event().await?;
+
+        // The list on relays is now the one we have here
+        GLOBALS.mutes.set_last_edit(event.created_at).await?;
+
+        Self::publish_to_write_relays(event, "pushing-mutes", false).await
+    }
+
     async fn clear_following(&mut self) -> Result<(), Error> {
         GLOBALS.people.async_follow_none().await?;
         Ok(())
     }
 
-    async fn push_metadata(&mut self, metadata: Metadata) -> Result<(), Error> {
+    async fn push_metadata(metadata: Metadata) -> Result<(), Error> {
         let public_key = match GLOBALS.signer.public_key() {
             Some(pk) => pk,
             None => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()), // not even a public key
@@ -1283,28 +1961,13 @@ impl Overlord {
             ots: None,
         };
 
-        let event = GLOBALS.signer.sign_preevent(pre_event, None, None)?;
+        let event = GLOBALS.signer.sign_preevent(pre_event, None, None).await?;
 
         // Push to all of the relays we post to
-        let relays: Vec<DbRelay> = GLOBALS.relays_filtered(|r| r.has_usage_bits(DbRelay::WRITE));
+        let relays: Vec<RelayUrl> =
+            GLOBALS.relays_url_filtered(|r| r.has_usage_bits(DbRelay::WRITE));
 
-        for relay in relays {
-            // Send it the event to pull our followers
-            tracing::debug!("Pushing Metadata to {}", &relay.url);
-
-            self.engage_minion(
-                relay.url.clone(),
-                vec![RelayJob {
-                    reason: "write-metadata",
-                    payload: ToMinionPayload {
-                        job_id: rand::random::<u64>(),
-                        detail: ToMinionPayloadDetail::PostEvent(Box::new(event.clone())),
-                    },
-                    persistent: false,
-                }],
-            )
-            .await?;
-        }
+        Self::post_to_relays(&event, relays, "write-metadata", false);
 
         Ok(())
     }
@@ -1340,6 +2003,7 @@ impl Overlord {
                         detail: ToMinionPayloadDetail::TempSubscribeMetadata(pubkeys),
                     },
                     persistent: false,
+                    outbox: false,
                 }],
             )
             .await?;
@@ -1348,7 +2012,58 @@ impl Overlord {
         Ok(())
     }
 
-    async fn repost(&mut self, id: Id) -> Result<(), Error> {
+    async fn republish_history(&mut self, relay_url: RelayUrl) -> Result<(), Error> {
+        let busy = match GLOBALS.republish.read().as_ref() {
+            Some(republish) if !republish.done => Some(republish.relay.clone()),
+            _ => None,
+        };
+        if let Some(busy) = busy {
+            *GLOBALS.status_message.write().await =
+                format!("Already republishing to {}, wait for it to finish.", busy);
+            return Ok(());
+        }
+
+        let pubkey = match GLOBALS.signer.public_key() {
+            Some(pk) => pk,
+            None => {
+                tracing::warn!("No public key! Not republishing");
+                return Ok(());
+            }
+        };
+
+        // Our current metadata, contact list and relay list, and all of our notes
+        // and deletions (so deleted notes stay deleted), oldest first
+        let cond = format!(
+            "pubkey='{0}' AND (kind IN (1, 5) OR id IN \
+             (SELECT id FROM event AS latest WHERE pubkey='{0}' AND kind IN (0, 3, 10002) \
+             AND created_at=(SELECT max(created_at) FROM event AS other \
+             WHERE other.pubkey=latest.pubkey AND other.kind=latest.kind))) \
+             ORDER BY created_at ASC",
+            pubkey.as_hex_string()
+        );
+        let mut events: Vec<Event> = Vec::new();
+        for dbevent in DbEvent::fetch(Some(&cond)).await?.iter() {
+            events.push(serde_json::from_str(&dbevent.raw)?);
+        }
+
+        if events.is_empty() {
+            *GLOBALS.status_message.write().await = "You have no events to republish.".to_owned();
+            return Ok(());
+        }
+
+        *GLOBALS.republish.write() = Some(crate::republish::Republish::new(
+            relay_url.clone(),
+            events.len(),
+        ));
+
+        std::mem::drop(tokio::spawn(async move {
+            crate::republish::run(relay_url, events).await;
+        }));
+
+        Ok(())
+    }
+
+    async fn repost(id: Id) -> Result<(), Error> {
         let reposted_event = match GLOBALS.events.get(&id) {
             Some(event) => event,
             None => {
@@ -1411,7 +2126,8 @@ impl Overlord {
 
             GLOBALS
                 .signer
-                .sign_preevent(pre_event, pow, Some(work_sender))?
+                .sign_preevent(pre_event, pow, Some(work_sender))
+                .await?
         };
 
         // Process this event locally
@@ -1428,23 +2144,7 @@ impl Overlord {
             relay_urls.dedup();
         }
 
-        for url in relay_urls {
-            // Send it the event to post
-            tracing::debug!("Asking {} to (re)post", &url);
-
-            self.engage_minion(
-                url.clone(),
-                vec![RelayJob {
-                    reason: "reposting",
-                    payload: ToMinionPayload {
-                        job_id: rand::random::<u64>(),
-                        detail: ToMinionPayloadDetail::PostEvent(Box::new(event.clone())),
-                    },
-                    persistent: false,
-                }],
-            )
-            .await?;
-        }
+        Self::post_to_relays(&event, relay_urls, "reposting", true);
 
         Ok(())
     }
@@ -1600,6 +2300,7 @@ impl Overlord {
                             ),
                         },
                         persistent: false,
+                        outbox: false,
                     }],
                 )
                 .await?;
@@ -1652,7 +2353,7 @@ impl Overlord {
         Ok(())
     }
 
-    async fn delete(&mut self, id: Id) -> Result<(), Error> {
+    async fn delete(id: Id) -> Result<(), Error> {
         let tags: Vec<Tag> = vec![Tag::Event {
             id,
             recommended_relay_url: None,
@@ -1678,7 +2379,7 @@ impl Overlord {
             };
 
             // Should we add a pow? Maybe the relay needs it.
-            GLOBALS.signer.sign_preevent(pre_event, None, None)?
+            GLOBALS.signer.sign_preevent(pre_event, None, None).await?
         };
 
         // Process this event locally
@@ -1695,23 +2396,7 @@ impl Overlord {
             relay_urls.dedup();
         }
 
-        for url in relay_urls {
-            // Send it the event to post
-            tracing::debug!("Asking {} to delete", &url);
-
-            self.engage_minion(
-                url.to_owned(),
-                vec![RelayJob {
-                    reason: "deleting",
-                    payload: ToMinionPayload {
-                        job_id: rand::random::<u64>(),
-                        detail: ToMinionPayloadDetail::PostEvent(Box::new(event.clone())),
-                    },
-                    persistent: false,
-                }],
-            )
-            .await?;
-        }
+        Self::post_to_relays(&event, relay_urls, "deleting", true);
 
         Ok(())
     }
@@ -1809,6 +2494,149 @@ impl Overlord {
 
         Ok(())
     }
+
+    async fn switch_identity(&mut self, name: String) -> Result<(), Error> {
+        if GLOBALS
+            .identities
+            .read()
+            .iter()
+            .any(|i| i.active && i.name == name)
+        {
+            return Ok(());
+        }
+
+        tracing::info!("Switching to identity {}", name);
+
+        // Save what belongs to the current identity before it is set aside
+        let ids: Vec<Id> = {
+            let map = GLOBALS.new_viewed_events.write().await;
+            let ids: Vec<Id> = map.iter().map(|elem| *elem.key()).collect();
+            map.clear();
+            ids
+        };
+        DbEventFlags::mark_all_as_viewed(ids).await?;
+        let settings = GLOBALS.settings.read().clone();
+        settings.save().await?;
+
+        // Our subscriptions are all about the current identity
+        self.stop_all_minions().await;
+        for pubkey in crate::person_lists::followed_and_listed_pubkeys() {
+            GLOBALS.relay_picker.remove_someone(pubkey);
+        }
+
+        let (public_key, delegatee_tag) = DbIdentity::switch(name.clone()).await?;
+
+        // Keys and delegation
+        {
+            let mut settings = GLOBALS.settings.write();
+            settings.public_key = public_key;
+            settings.delegatee_tag = delegatee_tag;
+        }
+        GLOBALS.delegation.load_through_settings()?;
+        GLOBALS.signer.load_from_settings().await;
+        GLOBALS.wallet.load_from_settings().await;
+
+        // Relays
+        GLOBALS.all_relays.clear();
+        for dbrelay in DbRelay::fetch(None).await?.drain(..) {
+            GLOBALS.all_relays.insert(dbrelay.url.clone(), dbrelay);
+        }
+
+        // People
+        GLOBALS.people.reload_for_identity().await?;
+        GLOBALS.person_lists.load().await?;
+        GLOBALS.long_form.load().await?;
+        for pubkey in crate::person_lists::followed_and_listed_pubkeys() {
+            GLOBALS.relay_picker.add_someone(pubkey)?;
+        }
+        if let Some(pk) = GLOBALS.signer.public_key() {
+            if let Some(event) = DbEvent::fetch_last_contact_list(pk.into()).await? {
+                crate::process::process_new_event(&event, false, None, None).await?;
+            }
+        }
+
+        // Viewed and dismissed events
+        GLOBALS.viewed_events.clear();
+        for id in DbEventFlags::load_all_viewed().await?.iter() {
+            GLOBALS.viewed_events.insert(*id);
+        }
+        GLOBALS.dismissed.write().await.clear();
+
+        // Bookmarks and mutes
+        GLOBALS.bookmarks.load().await?;
+        GLOBALS.mutes.load().await?;
+        if let Some(event) = crate::mutes::last_mute_list().await? {
+            GLOBALS.mutes.note_remote_list(&event);
+        }
+
+        // Direct message encryption choices
+        GLOBALS.dm_encryption.clear();
+        for channel in DbDmChannel::load_all().await?.drain(..) {
+            GLOBALS
+                .dm_encryption
+                .insert(channel.counterparty, channel.encryption);
+        }
+
+        *GLOBALS.identities.write() = DbIdentity::fetch_all().await?;
+        GLOBALS.feed.recompute().await?;
+
+        // Start over with the new identity's relays and follows
+        GLOBALS.relay_picker.refresh_person_relay_scores().await?;
+        self.start_minions().await?;
+
+        *GLOBALS.status_message.write().await = format!("Switched to identity {}.", name);
+
+        Ok(())
+    }
+
+    async fn zap(
+        &mut self,
+        id: Id,
+        pubkey: PublicKeyHex,
+        msats: u64,
+        comment: String,
+    ) -> Result<(), Error> {
+        if !GLOBALS.signer.is_ready() {
+            *GLOBALS.status_message.write().await =
+                "Your key is not setup, you cannot zap.".to_owned();
+            return Ok(());
+        }
+
+        *GLOBALS.current_zap.write() = Some(crate::zap::ZapInvoice {
+            id,
+            msats,
+            invoice: None,
+            paying: false,
+            paid: false,
+        });
+
+        // LNURL servers and wallets can be slow, so don't hold up the overlord
+        std::mem::drop(tokio::spawn(async move {
+            match crate::zap::request_invoice(id, pubkey, msats, comment).await {
+                Ok(invoice) => {
+                    // With a wallet connected, zapping is one click
+                    if GLOBALS.wallet.is_ready() {
+                        if let Err(e) = crate::zap::pay_with_wallet(id, invoice).await {
+                            *GLOBALS.status_message.write().await =
+                                format!("Your wallet did not pay: {}", e);
+                        }
+                    }
+                }
+                Err(e) => {
+                    {
+                        let mut current_zap = GLOBALS.current_zap.write();
+                        if current_zap.as_ref().map(|z| z.id) == Some(id) {
+                            *current_zap = None;
+                        }
+                    }
+                    *GLOBALS.status_message.write().await =
+                        format!("Could not get an invoice: {}", e);
+                }
+            }
+        }));
+
+        Ok(())
+    }
 }
 
 fn work_logger(work_receiver: mpsc::Receiver<u8>, powint: u8) {
@@ -1,7 +1,8 @@
 use crate::comms::ToOverlordMessage;
-use crate::db::{DbEvent, DbPersonRelay};
+use crate::db::{DbEvent, DbPersonRelay, DbPersonZapper};
 use crate::error::{Error, ErrorKind};
 use crate::globals::GLOBALS;
+use crate::zap::LnurlAddress;
 use crate::AVATAR_SIZE;
 use dashmap::{DashMap, DashSet};
 use eframe::egui::{Color32, ColorImage};
@@ -387,6 +388,19 @@ impl People {
                 person_mut.clone()
             };
 
+            // Their lightning address may have changed, so we ask again the next
+            // time we show one of their notes
+            GLOBALS.zapper_lookups.remove(pubkeyhex);
+            let lnurl = LnurlAddress::from_person(&person).map(|address| address.lnurl);
+            let stale = match GLOBALS.zappers.get(pubkeyhex) {
+                Some(known) => Some(&known.lnurl) != lnurl.as_ref(),
+                None => false,
+            };
+            if stale {
+                GLOBALS.zappers.remove(pubkeyhex);
+                DbPersonZapper::delete(pubkeyhex.to_owned()).await?;
+            }
+
             // Update the database
             let pubkeyhex2 = pubkeyhex.to_owned();
             let person_inner = person.clone();
@@ -516,6 +530,34 @@ impl People {
         Ok(())
     }
 
+    /// After switching identities the followed and muted flags in the database
+    /// belong to somebody else. Forget everyone we have in memory and load the
+    /// followed and muted people again. People only loaded for their metadata
+    /// will be loaded again as they are asked for.
+    pub async fn reload_for_identity(&self) -> Result<(), Error> {
+        self.people.clear();
+        *self.active_person.write().await = None;
+        self.active_persons_write_relays.write().await.clear();
+        self.last_contact_list_asof.store(0, Ordering::Relaxed);
+        self.last_contact_list_size.store(0, Ordering::Relaxed);
+
+        self.load_all_followed().await?;
+
+        let last_edit = task::spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            db.query_row(
+                "SELECT last_contact_list_edit FROM local_settings LIMIT 1",
+                [],
+                |row| row.get::<usize, i64>(0),
+            )
+        })
+        .await??;
+        self.last_contact_list_edit
+            .store(last_edit, Ordering::Relaxed);
+
+        Ok(())
+    }
+
     // Get from our memory map. If missing, eventually load from the database
     pub fn get(&self, pubkeyhex: &PublicKeyHex) -> Option<DbPerson> {
         if self.people.contains_key(pubkeyhex) {
@@ -539,6 +581,14 @@ impl People {
         }
     }
 
+    /// Like get(), but waits for the person to be read from the database
+    pub async fn get_loaded(&self, pubkeyhex: &PublicKeyHex) -> Result<Option<DbPerson>, Error> {
+        if let Some(person) = self.people.get(pubkeyhex) {
+            return Ok(Some(person.value().to_owned()));
+        }
+        People::fetch_one(pubkeyhex).await
+    }
+
     pub fn get_all(&self) -> Vec<DbPerson> {
         let mut v: Vec<DbPerson> = self.people.iter().map(|e| e.value().to_owned()).collect();
         v.sort_by(|a, b| {
@@ -822,7 +872,7 @@ impl People {
             ots: None,
         };
 
-        GLOBALS.signer.sign_preevent(pre_event, None, None)
+        GLOBALS.signer.sign_preevent(pre_event, None, None).await
     }
 
     pub fn follow(&self, pubkeyhex: &PublicKeyHex, follow: bool) {
@@ -869,7 +919,7 @@ impl People {
         if follow > 0 {
             // Add the person to the relay_picker for picking
             GLOBALS.relay_picker.add_someone(pubkeyhex.to_owned())?;
-        } else {
+        } else if !GLOBALS.person_lists.is_listed(pubkeyhex) {
             GLOBALS.relay_picker.remove_someone(pubkeyhex.to_owned());
         }
 
@@ -1039,6 +1089,50 @@ impl People {
             }
         }
 
+        GLOBALS.mutes.mark_edited().await?;
+
+        Ok(())
+    }
+
+    /// Mute all these people, and unmute everybody else if merge=false
+    pub async fn mute_all(&self, pubkeys: &[PublicKeyHex], merge: bool) -> Result<(), Error> {
+        // Make sure they are all in the database (and memory) first.
+        self.create_all_if_missing(pubkeys).await?;
+
+        let pubkey_strings: Vec<String> = pubkeys.iter().map(|p| p.to_string()).collect();
+        task::spawn_blocking(move || {
+            let db = GLOBALS.db.blocking_lock();
+            if !merge {
+                db.execute("UPDATE person SET muted=0", [])?;
+            }
+            if !pubkey_strings.is_empty() {
+                let sql = format!(
+                    "UPDATE person SET muted=1 WHERE pubkey IN ({})",
+                    repeat_vars(pubkey_strings.len())
+                );
+                let mut stmt = db.prepare(&sql)?;
+                let mut pos = 1;
+                for pk in pubkey_strings.iter() {
+                    stmt.raw_bind_parameter(pos, pk)?;
+                    pos += 1;
+                }
+                stmt.raw_execute()?;
+            }
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        // Make sure memory matches
+        for mut elem in self.people.iter_mut() {
+            let pkh = elem.key().clone();
+            let mut person = elem.value_mut();
+            if pubkeys.contains(&pkh) {
+                person.muted = 1;
+            } else if !merge {
+                person.muted = 0;
+            }
+        }
+
         Ok(())
     }
 
@@ -0,0 +1,242 @@
+use crate::db::DbPersonList;
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventKind, PreEvent, PublicKeyHex, Tag, Unixtime};
+use parking_lot::RwLock;
+use std::collections::BTreeMap;
+
+/// NIP-51 follow set, a named list of people
+pub const FOLLOW_SET_KIND: u64 = 30000;
+
+pub fn follow_set_kind() -> EventKind {
+    EventKind::from(FOLLOW_SET_KIND)
+}
+
+/// Our named lists of people, by name
+pub struct PersonLists {
+    lists: RwLock<BTreeMap<String, DbPersonList>>,
+}
+
+impl Default for PersonLists {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PersonLists {
+    pub fn new() -> PersonLists {
+        PersonLists {
+            lists: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Load lists from the database (again, after switching identities)
+    pub async fn load(&self) -> Result<(), Error> {
+        let lists: BTreeMap<String, DbPersonList> = DbPersonList::fetch_all()
+            .await?
+            .drain(..)
+            .map(|l| (l.name.clone(), l))
+            .collect();
+        *self.lists.write() = lists;
+        Ok(())
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.lists.read().keys().cloned().collect()
+    }
+
+    pub fn members(&self, name: &str) -> Vec<PublicKeyHex> {
+        match self.lists.read().get(name) {
+            Some(list) => list.members.clone(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn is_member(&self, name: &str, pubkey: &PublicKeyHex) -> bool {
+        match self.lists.read().get(name) {
+            Some(list) => list.members.contains(pubkey),
+            None => false,
+        }
+    }
+
+    /// Whether this person is on any of our lists
+    pub fn is_listed(&self, pubkey: &PublicKeyHex) -> bool {
+        self.lists
+            .read()
+            .values()
+            .any(|l| l.members.contains(pubkey))
+    }
+
+    /// Everybody on any of our lists
+    pub fn all_members(&self) -> Vec<PublicKeyHex> {
+        let mut members: Vec<PublicKeyHex> = Vec::new();
+        for list in self.lists.read().values() {
+            for pubkey in list.members.iter() {
+                if !members.contains(pubkey) {
+                    members.push(pubkey.to_owned());
+                }
+            }
+        }
+        members
+    }
+
+    pub async fn create(&self, name: String) -> Result<(), Error> {
+        let name = name.trim().to_owned();
+        if name.is_empty() || self.lists.read().contains_key(&name) {
+            return Ok(());
+        }
+        let now = Unixtime::now().unwrap().0;
+        DbPersonList::create(name.clone(), now).await?;
+        self.lists.write().insert(
+            name.clone(),
+            DbPersonList {
+                name,
+                last_edit: now,
+                members: Vec::new(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Delete a list. Its members stop being picked for unless followed or
+    /// on another list.
+    pub async fn delete(&self, name: &str) -> Result<(), Error> {
+        DbPersonList::delete(name.to_owned()).await?;
+        let removed = self.lists.write().remove(name);
+        if let Some(list) = removed {
+            for pubkey in list.members {
+                self.forget_if_unused(pubkey);
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn add(&self, name: &str, pubkey: PublicKeyHex) -> Result<(), Error> {
+        if self.is_member(name, &pubkey) {
+            return Ok(());
+        }
+        let now = Unixtime::now().unwrap().0;
+        DbPersonList::add_member(name.to_owned(), pubkey.clone(), now).await?;
+        if let Some(list) = self.lists.write().get_mut(name) {
+            list.members.push(pubkey.clone());
+            list.last_edit = now;
+        }
+        GLOBALS
+            .people
+            .create_all_if_missing(&[pubkey.clone()])
+            .await?;
+
+        // Add the person to the relay_picker for picking
+        GLOBALS.relay_picker.add_someone(pubkey)?;
+        Ok(())
+    }
+
+    pub async fn remove(&self, name: &str, pubkey: PublicKeyHex) -> Result<(), Error> {
+        let now = Unixtime::now().unwrap().0;
+        DbPersonList::remove_member(name.to_owned(), pubkey.clone(), now).await?;
+        if let Some(list) = self.lists.write().get_mut(name) {
+            list.members.retain(|p| *p != pubkey);
+            list.last_edit = now;
+        }
+        self.forget_if_unused(pubkey);
+        Ok(())
+    }
+
+    // The relay picker only needs people we follow or have listed
+    fn forget_if_unused(&self, pubkey: PublicKeyHex) {
+        if self.is_listed(&pubkey) {
+            return;
+        }
+        if let Some(person) = GLOBALS.people.get(&pubkey) {
+            if person.followed == 1 {
+                return;
+            }
+        }
+        GLOBALS.relay_picker.remove_someone(pubkey);
+    }
+
+    /// Take in a follow set we published from some client, if it is newer than
+    /// what we have under that name
+    pub async fn merge_list(&self, event: &Event) -> Result<(), Error> {
+        let name = match event.parameter() {
+            Some(name) if !name.trim().is_empty() => name.trim().to_owned(),
+            _ => return Ok(()),
+        };
+
+        let last_edit = self.lists.read().get(&name).map(|l| l.last_edit);
+        let members: Vec<PublicKeyHex> = event
+            .tags
+            .iter()
+            .filter_map(|t| match t {
+                Tag::Pubkey { pubkey, .. } => Some(pubkey.to_owned()),
+                _ => None,
+            })
+            .collect();
+        match last_edit {
+            Some(last_edit) if last_edit >= event.created_at.0 => return Ok(()),
+            // An empty list is how a deleted list is published; don't bring it back
+            None if members.is_empty() => return Ok(()),
+            _ => (),
+        }
+
+        GLOBALS.people.create_all_if_missing(&members).await?;
+        for pubkey in members.iter() {
+            GLOBALS.relay_picker.add_someone(pubkey.to_owned())?;
+        }
+
+        let list = DbPersonList {
+            name: name.clone(),
+            last_edit: event.created_at.0,
+            members,
+        };
+        DbPersonList::replace(list.clone()).await?;
+        let old = self.lists.write().insert(name, list);
+        if let Some(old) = old {
+            for pubkey in old.members {
+                self.forget_if_unused(pubkey);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sign a follow set of the list's current members. A list we no longer
+    /// have is signed empty, which replaces it on relays.
+    pub async fn generate_list_event(&self, name: &str) -> Result<Event, Error> {
+        let public_key = match GLOBALS.signer.public_key() {
+            Some(pk) => pk,
+            None => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()), // not even a public key
+        };
+
+        let mut tags: Vec<Tag> = vec![Tag::Identifier(name.to_owned())];
+        for pubkey in self.members(name) {
+            tags.push(Tag::Pubkey {
+                pubkey,
+                recommended_relay_url: None,
+                petname: None,
+            });
+        }
+
+        let pre_event = PreEvent {
+            pubkey: public_key,
+            created_at: Unixtime::now().unwrap(),
+            kind: follow_set_kind(),
+            tags,
+            content: "".to_owned(),
+            ots: None,
+        };
+
+        GLOBALS.signer.sign_preevent(pre_event, None, None).await
+    }
+}
+
+/// Everybody whose notes the general feed subscription pulls in: the people
+/// we follow and the people on our lists
+pub fn followed_and_listed_pubkeys() -> Vec<PublicKeyHex> {
+    let mut pubkeys = GLOBALS.people.get_followed_pubkeys();
+    for pubkey in GLOBALS.person_lists.all_members() {
+        if !pubkeys.contains(&pubkey) {
+            pubkeys.push(pubkey);
+        }
+    }
+    pubkeys
+}
@@ -0,0 +1,137 @@
+use crate::db::{DbPersonRelay, DbRelay};
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use gossip_relay_picker::Direction;
+use nostr_types::{PublicKeyHex, RelayUrl};
+
+/// A relay a post will go to, and why
+#[derive(Debug, Clone)]
+pub struct PostRelay {
+    pub url: RelayUrl,
+
+    /// One of our own write relays
+    pub ours: bool,
+
+    /// The tagged people who read from it
+    pub readers: Vec<PublicKeyHex>,
+
+    /// Their scores for it, weighted by our rank of it
+    pub score: u64,
+}
+
+/// The relays to post to when tagging these people: all of our write relays, plus
+/// the best ranked inbox (read) relays of each of them, within the caps in settings.
+/// Everybody tagged gets one of their inbox relays before anybody gets a second.
+pub async fn select_post_relays(tagged: Vec<PublicKeyHex>) -> Result<Vec<PostRelay>, Error> {
+    let (per_person, max_inbox) = {
+        let settings = GLOBALS.settings.read();
+        (
+            settings.num_post_relays_per_person as usize,
+            settings.max_post_relays as usize,
+        )
+    };
+
+    let our_pubkey: Option<PublicKeyHex> = GLOBALS.signer.public_key().map(|pk| pk.into());
+
+    let mut ours: Vec<PostRelay> = GLOBALS
+        .relays_url_filtered(|r| r.has_usage_bits(DbRelay::WRITE))
+        .into_iter()
+        .map(|url| PostRelay {
+            url,
+            ours: true,
+            readers: vec![],
+            score: 0,
+        })
+        .collect();
+
+    // Each person's inbox relays, best first, skipping relays we ranked 0 (do not use)
+    let mut inboxes: Vec<(PublicKeyHex, Vec<(RelayUrl, u64)>)> = Vec::new();
+    for pubkey in tagged {
+        if Some(&pubkey) == our_pubkey.as_ref() || inboxes.iter().any(|(pk, _)| *pk == pubkey) {
+            continue;
+        }
+        let best: Vec<(RelayUrl, u64)> =
+            DbPersonRelay::get_best_relays(pubkey.clone(), Direction::Read)
+                .await?
+                .drain(..)
+                .filter_map(|(url, score)| {
+                    let rank = match GLOBALS.all_relays.get(&url) {
+                        Some(r) => r.rank,
+                        None => 3,
+                    };
+                    if rank == 0 {
+                        None
+                    } else {
+                        Some((url, score * rank))
+                    }
+                })
+                .take(per_person)
+                .collect();
+        inboxes.push((pubkey, best));
+    }
+
+    // Our write relays also reach anybody who reads from them
+    for relay in ours.iter_mut() {
+        for (pubkey, best) in inboxes.iter() {
+            if let Some((_, score)) = best.iter().find(|(url, _)| *url == relay.url) {
+                relay.readers.push(pubkey.clone());
+                relay.score += score;
+            }
+        }
+    }
+
+    // Gather the other candidates, most shared and best scored first
+    let mut candidates: Vec<PostRelay> = Vec::new();
+    for (pubkey, best) in inboxes.iter() {
+        for (url, score) in best.iter() {
+            if ours.iter().any(|r| r.url == *url) {
+                continue;
+            }
+            match candidates.iter_mut().find(|r| r.url == *url) {
+                Some(relay) => {
+                    relay.readers.push(pubkey.clone());
+                    relay.score += score;
+                }
+                None => candidates.push(PostRelay {
+                    url: url.clone(),
+                    ours: false,
+                    readers: vec![pubkey.clone()],
+                    score: *score,
+                }),
+            }
+        }
+    }
+    candidates.sort_by(|a, b| {
+        b.readers
+            .len()
+            .cmp(&a.readers.len())
+            .then(b.score.cmp(&a.score))
+    });
+
+    // First make sure everybody is reached at least once, then fill up to the cap
+    let mut chosen: Vec<PostRelay> = Vec::new();
+    for (pubkey, _) in inboxes.iter() {
+        if chosen.len() >= max_inbox {
+            break;
+        }
+        let reached = ours
+            .iter()
+            .chain(chosen.iter())
+            .any(|r| r.readers.contains(pubkey));
+        if reached {
+            continue;
+        }
+        if let Some(pos) = candidates.iter().position(|r| r.readers.contains(pubkey)) {
+            chosen.push(candidates.remove(pos));
+        }
+    }
+    for relay in candidates {
+        if chosen.len() >= max_inbox {
+            break;
+        }
+        chosen.push(relay);
+    }
+
+    ours.extend(chosen);
+    Ok(ours)
+}
@@ -20,6 +20,18 @@ pub async fn process_new_event(
 ) -> Result<(), Error> {
     let now = Unixtime::now()?.0 as u64;
 
+    // Remote signer (NIP-46) traffic is for the signer, not for the feed or the database
+    if event.kind == crate::nip46::nostr_connect_kind() {
+        GLOBALS.signer.handle_nip46_event(event);
+        return Ok(());
+    }
+
+    // Likewise wallet (NIP-47) responses are for the wallet
+    if event.kind == crate::nip47::wallet_response_kind() {
+        GLOBALS.wallet.handle_nip47_event(event);
+        return Ok(());
+    }
+
     // If it was from a relay,
     // Insert into database; bail if event is an already-replaced replaceable event.
     if from_relay {
@@ -71,6 +83,11 @@ pub async fn process_new_event(
         // Insert into map (memory only)
         // This also inserts the 'seen_on' relay information
         GLOBALS.events.insert(event.clone(), seen_on.clone());
+
+        // Tell anybody listening about it
+        if GLOBALS.new_events.receiver_count() > 0 {
+            let _ = GLOBALS.new_events.send(event.clone());
+        }
     } else {
         // Just insert the new seen_on information (memory only)
         if let Some(url) = &seen_on {
@@ -279,6 +296,11 @@ pub async fn process_new_event(
                     .await;
             }
         }
+
+        // zaps
+        if event.kind == EventKind::Zap {
+            crate::zap::process_zap_receipt(event, from_relay).await?;
+        }
     }
 
     // Save event_hashtags
@@ -344,6 +366,27 @@ pub async fn process_new_event(
         process_relay_list(event).await?;
     }
 
+    // Take in bookmarks we made from other clients
+    if crate::bookmarks::is_bookmark_list(event)
+        && Some(event.pubkey) == GLOBALS.signer.public_key()
+    {
+        GLOBALS.bookmarks.take_list(event).await?;
+    }
+
+    // Take in people lists we made from other clients
+    if event.kind == crate::person_lists::follow_set_kind()
+        && Some(event.pubkey) == GLOBALS.signer.public_key()
+    {
+        GLOBALS.person_lists.merge_list(event).await?;
+    }
+
+    // Our mute list is applied only on user command, see Mutes::update_from_list()
+    if event.kind == crate::mutes::mute_list_kind()
+        && Some(event.pubkey) == GLOBALS.signer.public_key()
+    {
+        GLOBALS.mutes.note_remote_list(event);
+    }
+
     // If the content contains an nevent and we don't have it, fetch it from those relays
     for bech32 in NostrBech32::find_all_in_string(&event.content) {
         if let NostrBech32::EventPointer(ep) = bech32 {
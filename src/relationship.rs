@@ -6,4 +6,6 @@ pub enum Relationship {
     Mention,
     Reaction(String),
     Deletion(String),
+    /// A zap receipt, for this many millisats
+    ZapReceipt(u64),
 }
@@ -0,0 +1,120 @@
+use crate::globals::GLOBALS;
+use nostr_types::{RelayUrl, Unixtime};
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// How many relay notices are kept
+const MAX_RELAY_NOTICES: usize = 500;
+
+/// What a relay notice is about, where it matters to the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoticeCategory {
+    /// We are sending too much, too fast
+    RateLimited,
+    /// The relay wants us to AUTH (NIP-42) first
+    AuthRequired,
+    Other,
+}
+
+impl NoticeCategory {
+    /// By the NIP-01 machine-readable prefix if there is one, otherwise by the
+    /// wording relays commonly use
+    pub fn of(message: &str) -> NoticeCategory {
+        let message = message.to_lowercase();
+        if message.starts_with("rate-limited:")
+            || message.contains("rate limit")
+            || message.contains("rate-limit")
+            || message.contains("too many")
+            || message.contains("too fast")
+            || message.contains("slow down")
+        {
+            NoticeCategory::RateLimited
+        } else if message.starts_with("auth-required:")
+            || message.contains("auth required")
+            || message.contains("authenticat")
+        {
+            NoticeCategory::AuthRequired
+        } else {
+            NoticeCategory::Other
+        }
+    }
+
+    pub fn highlighted(&self) -> bool {
+        *self != NoticeCategory::Other
+    }
+}
+
+/// Something a relay told us, as a NOTICE or in closing a subscription
+#[derive(Debug, Clone)]
+pub struct RelayNotice {
+    pub relay: RelayUrl,
+    pub at: Unixtime,
+    pub message: String,
+
+    /// The handle of the subscription, if the relay closed one (CLOSED)
+    pub closed: Option<String>,
+
+    pub category: NoticeCategory,
+}
+
+/// What relays have told us this session, newest first
+pub struct RelayNotices {
+    notices: RwLock<Vec<RelayNotice>>,
+    unread: AtomicUsize,
+}
+
+impl Default for RelayNotices {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RelayNotices {
+    pub fn new() -> RelayNotices {
+        RelayNotices {
+            notices: RwLock::new(Vec::new()),
+            unread: AtomicUsize::new(0),
+        }
+    }
+
+    /// Keep what the relay said. Rate limits and auth demands also go to the
+    /// status line, as they explain why things silently fail.
+    pub async fn add(&self, relay: &RelayUrl, message: String, closed: Option<String>) {
+        let category = NoticeCategory::of(&message);
+        if category.highlighted() {
+            *GLOBALS.status_message.write().await = format!("{}: {}", relay, message);
+        }
+
+        let notice = RelayNotice {
+            relay: relay.clone(),
+            at: Unixtime::now().unwrap(),
+            message,
+            closed,
+            category,
+        };
+        let mut notices = self.notices.write();
+        notices.insert(0, notice);
+        notices.truncate(MAX_RELAY_NOTICES);
+        let unread = self.unread.load(Ordering::Relaxed);
+        self.unread
+            .store((unread + 1).min(MAX_RELAY_NOTICES), Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> Vec<RelayNotice> {
+        self.notices.read().clone()
+    }
+
+    /// How many notices came in since they were last looked at
+    pub fn unread(&self) -> usize {
+        self.unread.load(Ordering::Relaxed)
+    }
+
+    pub fn mark_read(&self) {
+        self.unread.store(0, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        self.notices.write().clear();
+        self.unread.store(0, Ordering::Relaxed);
+    }
+}
@@ -46,9 +46,10 @@ impl RelayPickerHooks for Hooks {
         GLOBALS.settings.read().num_relays_per_person as usize
     }
 
-    /// Returns the public keys of all the people followed
+    /// Returns the public keys of all the people followed, and of the people on
+    /// our lists, so the general feed subscription covers list feeds too
     fn get_followed_pubkeys(&self) -> Vec<PublicKeyHex> {
-        GLOBALS.people.get_followed_pubkeys()
+        crate::person_lists::followed_and_listed_pubkeys()
     }
 
     /// Adjusts the score for a given relay, perhaps based on relay-specific metrics
@@ -0,0 +1,117 @@
+use crate::comms::{RelayJob, ToMinionPayload, ToMinionPayloadDetail, ToOverlordMessage};
+use crate::globals::GLOBALS;
+use nostr_types::{Event, Id, RelayUrl};
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Instant;
+
+/// How many events we hand to the minion at a time
+const BATCH_SIZE: usize = 20;
+
+/// How long we wait for the relay to answer a batch before moving on
+const BATCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Progress of republishing our history to a relay
+#[derive(Clone, Debug)]
+pub struct Republish {
+    pub relay: RelayUrl,
+    pub total: usize,
+    pub sent: usize,
+    pub accepted: usize,
+    pub rejected: usize,
+    pub unanswered: usize,
+    pub done: bool,
+}
+
+impl Republish {
+    pub fn new(relay: RelayUrl, total: usize) -> Republish {
+        Republish {
+            relay,
+            total,
+            sent: 0,
+            accepted: 0,
+            rejected: 0,
+            unanswered: 0,
+            done: false,
+        }
+    }
+}
+
+/// Post the events to the relay a batch at a time, tallying its OK answers in
+/// GLOBALS.republish as they come in.
+pub async fn run(relay: RelayUrl, events: Vec<Event>) {
+    let mut responses = GLOBALS.posting_responses.subscribe();
+
+    for batch in events.chunks(BATCH_SIZE) {
+        if GLOBALS.shutting_down.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let mut waiting: HashSet<Id> = batch.iter().map(|e| e.id).collect();
+        let jobs: Vec<RelayJob> = batch
+            .iter()
+            .map(|event| RelayJob {
+                reason: "republish",
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::PostEvent(Box::new(event.clone())),
+                },
+                persistent: false,
+                outbox: false,
+            })
+            .collect();
+
+        // The overlord connects to the relay if we are not already connected
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::ReengageMinion(relay.clone(), jobs));
+
+        if let Some(republish) = GLOBALS.republish.write().as_mut() {
+            republish.sent += batch.len();
+        }
+
+        let deadline = Instant::now() + BATCH_TIMEOUT;
+        while !waiting.is_empty() {
+            match tokio::time::timeout_at(deadline, responses.recv()).await {
+                Ok(Ok(response)) => {
+                    if response.relay != relay || !waiting.remove(&response.id) {
+                        continue;
+                    }
+                    if let Some(republish) = GLOBALS.republish.write().as_mut() {
+                        if response.ok {
+                            republish.accepted += 1;
+                        } else {
+                            republish.rejected += 1;
+                        }
+                    }
+                }
+                Ok(Err(RecvError::Lagged(n))) => {
+                    tracing::warn!("Missed {} relay responses while republishing", n);
+                }
+                Ok(Err(RecvError::Closed)) | Err(_) => break,
+            }
+        }
+
+        if let Some(republish) = GLOBALS.republish.write().as_mut() {
+            republish.unanswered += waiting.len();
+        }
+    }
+
+    let summary = match GLOBALS.republish.write().as_mut() {
+        Some(republish) => {
+            republish.done = true;
+            format!(
+                "Republished {} events to {}: {} accepted, {} rejected, {} unanswered.",
+                republish.sent,
+                republish.relay,
+                republish.accepted,
+                republish.rejected,
+                republish.unanswered
+            )
+        }
+        None => return,
+    };
+    *GLOBALS.status_message.write().await = summary;
+}
@@ -1,3 +1,4 @@
+use crate::db::RetentionPolicy;
 use crate::error::Error;
 use crate::globals::GLOBALS;
 use crate::ui::{Theme, ThemeVariant};
@@ -10,6 +11,8 @@ pub const DEFAULT_REPLIES_CHUNK: u64 = 60 * 60 * 24 * 7; // 1 week
 pub const DEFAULT_OVERLAP: u64 = 300; // 5 minutes
 pub const DEFAULT_NUM_RELAYS_PER_PERSON: u8 = 3;
 pub const DEFAULT_MAX_RELAYS: u8 = 15;
+pub const DEFAULT_NUM_POST_RELAYS_PER_PERSON: u8 = 2;
+pub const DEFAULT_MAX_POST_RELAYS: u8 = 10;
 pub const DEFAULT_MAX_FPS: u32 = 15;
 pub const DEFAULT_RECOMPUTE_FEED_PERIODICALLY: bool = true;
 pub const DEFAULT_FEED_RECOMPUTE_INTERVAL_MS: u32 = 3500;
@@ -36,6 +39,11 @@ pub const DEFAULT_AUTOMATICALLY_FETCH_METADATA: bool = true;
 pub const DEFAULT_HIGHLIGHT_UNREAD_EVENTS: bool = true;
 pub const DEFAULT_POSTING_AREA_AT_TOP: bool = true;
 pub const DEFAULT_ENABLE_ZAP_RECEIPTS: bool = false;
+pub const DEFAULT_ENABLE_CONTROL_API: bool = false;
+pub const DEFAULT_CONTROL_API_PORT: u16 = 4869;
+pub const DEFAULT_PRUNE_INTERVAL_DAYS: u64 = 0; // only when asked
+pub const DEFAULT_DATABASE_SIZE_BUDGET_MB: u64 = 0; // no budget
+pub const DEFAULT_MUTE_LIST_PRIVATE: bool = true;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Settings {
@@ -44,6 +52,8 @@ pub struct Settings {
     pub overlap: u64,
     pub num_relays_per_person: u8,
     pub max_relays: u8,
+    pub num_post_relays_per_person: u8,
+    pub max_post_relays: u8,
     pub public_key: Option<PublicKey>,
     pub max_fps: u32,
     pub recompute_feed_periodically: bool,
@@ -68,6 +78,13 @@ pub struct Settings {
     pub highlight_unread_events: bool,
     pub posting_area_at_top: bool,
     pub enable_zap_receipts: bool,
+    pub enable_control_api: bool,
+    pub control_api_port: u16,
+    pub pinned_hashtags: Vec<String>,
+    pub retention: RetentionPolicy,
+    pub prune_interval_days: u64,
+    pub database_size_budget_mb: u64,
+    pub mute_list_private: bool,
 }
 
 impl Default for Settings {
@@ -78,6 +95,8 @@ impl Default for Settings {
             overlap: DEFAULT_OVERLAP,
             num_relays_per_person: DEFAULT_NUM_RELAYS_PER_PERSON,
             max_relays: DEFAULT_MAX_RELAYS,
+            num_post_relays_per_person: DEFAULT_NUM_POST_RELAYS_PER_PERSON,
+            max_post_relays: DEFAULT_MAX_POST_RELAYS,
             public_key: None,
             max_fps: DEFAULT_MAX_FPS,
             recompute_feed_periodically: DEFAULT_RECOMPUTE_FEED_PERIODICALLY,
@@ -102,6 +121,13 @@ impl Default for Settings {
             highlight_unread_events: DEFAULT_HIGHLIGHT_UNREAD_EVENTS,
             posting_area_at_top: DEFAULT_POSTING_AREA_AT_TOP,
             enable_zap_receipts: DEFAULT_ENABLE_ZAP_RECEIPTS,
+            enable_control_api: DEFAULT_ENABLE_CONTROL_API,
+            control_api_port: DEFAULT_CONTROL_API_PORT,
+            pinned_hashtags: Vec::new(),
+            retention: RetentionPolicy::default(),
+            prune_interval_days: DEFAULT_PRUNE_INTERVAL_DAYS,
+            database_size_budget_mb: DEFAULT_DATABASE_SIZE_BUDGET_MB,
+            mute_list_private: DEFAULT_MUTE_LIST_PRIVATE,
         }
     }
 }
@@ -135,6 +161,16 @@ impl Settings {
                 "max_relays" => {
                     settings.max_relays = row.1.parse::<u8>().unwrap_or(DEFAULT_MAX_RELAYS)
                 }
+                "num_post_relays_per_person" => {
+                    settings.num_post_relays_per_person = row
+                        .1
+                        .parse::<u8>()
+                        .unwrap_or(DEFAULT_NUM_POST_RELAYS_PER_PERSON)
+                }
+                "max_post_relays" => {
+                    settings.max_post_relays =
+                        row.1.parse::<u8>().unwrap_or(DEFAULT_MAX_POST_RELAYS)
+                }
                 "public_key" => {
                     settings.public_key = match PublicKey::try_from_hex_string(&row.1) {
                         Ok(pk) => Some(pk),
@@ -195,7 +231,29 @@ impl Settings {
                     settings.highlight_unread_events = numstr_to_bool(row.1)
                 }
                 "posting_area_at_top" => settings.posting_area_at_top = numstr_to_bool(row.1),
-                "enable_zap_receipts" => settings.enable_zap_receipts = false, //numstr_to_bool(row.1),
+                "enable_zap_receipts" => settings.enable_zap_receipts = numstr_to_bool(row.1),
+                "enable_control_api" => settings.enable_control_api = numstr_to_bool(row.1),
+                "control_api_port" => {
+                    settings.control_api_port =
+                        row.1.parse::<u16>().unwrap_or(DEFAULT_CONTROL_API_PORT)
+                }
+                "pinned_hashtags" => {
+                    settings.pinned_hashtags = serde_json::from_str(&row.1).unwrap_or_default()
+                }
+                "retention_policy" => {
+                    settings.retention = serde_json::from_str(&row.1).unwrap_or_default()
+                }
+                "prune_interval_days" => {
+                    settings.prune_interval_days =
+                        row.1.parse::<u64>().unwrap_or(DEFAULT_PRUNE_INTERVAL_DAYS)
+                }
+                "database_size_budget_mb" => {
+                    settings.database_size_budget_mb = row
+                        .1
+                        .parse::<u64>()
+                        .unwrap_or(DEFAULT_DATABASE_SIZE_BUDGET_MB)
+                }
+                "mute_list_private" => settings.mute_list_private = numstr_to_bool(row.1),
                 _ => {}
             }
         }
@@ -221,6 +279,8 @@ impl Settings {
              ('overlap', ?),\
              ('num_relays_per_person', ?),\
              ('max_relays', ?),\
+             ('num_post_relays_per_person', ?),\
+             ('max_post_relays', ?),\
              ('max_fps', ?),\
              ('recompute_feed_periodically', ?),\
              ('feed_recompute_interval_ms', ?),\
@@ -244,7 +304,14 @@ impl Settings {
              ('delegatee_tag', ?),\
              ('highlight_unread_events', ?),\
              ('posting_area_at_top', ?),\
-             ('enable_zap_receipts', ?)",
+             ('enable_zap_receipts', ?),\
+             ('enable_control_api', ?),\
+             ('control_api_port', ?),\
+             ('pinned_hashtags', ?),\
+             ('retention_policy', ?),\
+             ('prune_interval_days', ?),\
+             ('database_size_budget_mb', ?),\
+             ('mute_list_private', ?)",
         )?;
         stmt.execute(params![
             self.feed_chunk,
@@ -252,6 +319,8 @@ impl Settings {
             self.overlap,
             self.num_relays_per_person,
             self.max_relays,
+            self.num_post_relays_per_person,
+            self.max_post_relays,
             self.max_fps,
             self.recompute_feed_periodically,
             self.feed_recompute_interval_ms,
@@ -275,7 +344,14 @@ impl Settings {
             self.delegatee_tag,
             bool_to_numstr(self.highlight_unread_events),
             bool_to_numstr(self.posting_area_at_top),
-            "0" // bool_to_numstr(self.enable_zap_receipts),
+            bool_to_numstr(self.enable_zap_receipts),
+            bool_to_numstr(self.enable_control_api),
+            self.control_api_port,
+            serde_json::to_string(&self.pinned_hashtags)?,
+            serde_json::to_string(&self.retention)?,
+            self.prune_interval_days,
+            self.database_size_budget_mb,
+            bool_to_numstr(self.mute_list_private),
         ])?;
 
         // Settings which are Options should not even exist when None.  We don't accept null valued
@@ -1,24 +1,46 @@
+use std::collections::HashMap;
 use std::sync::mpsc::Sender;
+use std::time::Duration;
 
+use crate::comms::{ToMinionMessage, ToMinionPayload, ToMinionPayloadDetail};
 use crate::error::{Error, ErrorKind};
 use crate::globals::GLOBALS;
-use nostr_types::{EncryptedPrivateKey, Event, KeySecurity, PreEvent, PrivateKey, PublicKey};
-use parking_lot::RwLock;
+use crate::nip44::{self, ContentEncryption};
+use crate::nip46::{Nip46Connection, Nip46Message, Nip46Request};
+use dashmap::{DashMap, DashSet};
+use nostr_types::{
+    EncryptedPrivateKey, Event, Id, KeySecurity, PreEvent, PrivateKey, PublicKey, RelayUrl, Tag,
+};
+use parking_lot::{Mutex, RwLock};
+use tokio::sync::oneshot;
 use tokio::task;
+use zeroize::Zeroize;
 
 const DEFAULT_LOG_N: u8 = 18;
 
+/// How long we wait for a remote signer to answer (it may be asking a human)
+const REMOTE_SIGNER_TIMEOUT: Duration = Duration::from_secs(60);
+
 #[derive(Default)]
 pub struct Signer {
     public: RwLock<Option<PublicKey>>,
     encrypted: RwLock<Option<EncryptedPrivateKey>>,
     private: RwLock<Option<PrivateKey>>,
+
+    // NIP-46 remote signing (instead of a local private key)
+    remote: RwLock<Option<Nip46Connection>>,
+    remote_pending: Mutex<HashMap<String, oneshot::Sender<Nip46Message>>>,
+    remote_decrypted: DashMap<Id, String>,
+    remote_decrypting: DashSet<Id>,
 }
 
 impl Signer {
     pub async fn load_from_settings(&self) {
         *self.public.write() = GLOBALS.settings.read().public_key;
         *self.private.write() = None;
+        *self.encrypted.write() = None;
+        *self.remote.write() = None;
+        self.remote_decrypted.clear();
 
         let db = GLOBALS.db.lock().await;
         if let Ok(epk) = db.query_row(
@@ -28,6 +50,21 @@ impl Signer {
         ) {
             *self.encrypted.write() = Some(EncryptedPrivateKey(epk));
         }
+        if let Ok(json) = db.query_row(
+            "SELECT remote_signer FROM local_settings LIMIT 1",
+            [],
+            |row| row.get::<usize, String>(0),
+        ) {
+            match Nip46Connection::from_json(&json) {
+                Ok(connection) => {
+                    if let Some(pk) = connection.user_pubkey {
+                        *self.public.write() = Some(pk);
+                    }
+                    *self.remote.write() = Some(connection);
+                }
+                Err(e) => tracing::error!("Remote signer in database is invalid: {}", e),
+            }
+        }
     }
 
     pub async fn save_through_settings(&self) -> Result<(), Error> {
@@ -36,10 +73,17 @@ impl Signer {
         settings.save().await?;
 
         let epk = self.encrypted.read().clone();
+        // Only save remote signers that finished pairing
+        let remote = self
+            .remote
+            .write()
+            .as_mut()
+            .filter(|c| c.is_paired())
+            .map(|c| c.to_json());
         let db = GLOBALS.db.lock().await;
         db.execute(
-            "UPDATE local_settings SET encrypted_private_key=?",
-            (epk.map(|e| e.0),),
+            "UPDATE local_settings SET encrypted_private_key=?, remote_signer=?",
+            (epk.map(|e| e.0), remote),
         )?;
         Ok(())
     }
@@ -138,11 +182,22 @@ impl Signer {
     }
 
     pub fn is_loaded(&self) -> bool {
-        self.encrypted.read().is_some() || self.private.read().is_some()
+        self.encrypted.read().is_some() || self.private.read().is_some() || self.is_remote()
     }
 
     pub fn is_ready(&self) -> bool {
-        self.private.read().is_some()
+        self.private.read().is_some() || self.is_remote()
+    }
+
+    /// Whether signing is done by a paired NIP-46 remote signer
+    pub fn is_remote(&self) -> bool {
+        self.private.read().is_none()
+            && self
+                .remote
+                .read()
+                .as_ref()
+                .map(|c| c.is_paired())
+                .unwrap_or(false)
     }
 
     pub fn public_key(&self) -> Option<PublicKey> {
@@ -157,19 +212,39 @@ impl Signer {
         self.private.read().as_ref().map(|pk| pk.key_security())
     }
 
-    pub fn sign_preevent(
+    pub async fn sign_preevent(
         &self,
         preevent: PreEvent,
         pow: Option<u8>,
         work_sender: Option<Sender<u8>>,
     ) -> Result<Event, Error> {
-        match &*self.private.read() {
-            Some(pk) => match pow {
-                Some(pow) => Ok(Event::new_with_pow(preevent, pk, pow, work_sender)?),
-                None => Ok(Event::new(preevent, pk)?),
-            },
-            _ => Err((ErrorKind::NoPrivateKey, file!(), line!()).into()),
+        if !self.is_remote() {
+            return match &*self.private.read() {
+                Some(pk) => match pow {
+                    Some(pow) => Ok(Event::new_with_pow(preevent, pk, pow, work_sender)?),
+                    None => Ok(Event::new(preevent, pk)?),
+                },
+                _ => Err((ErrorKind::NoPrivateKey, file!(), line!()).into()),
+            };
+        }
+
+        // The remote signer does not do proof of work, so we ignore pow
+        let unsigned = serde_json::json!({
+            "pubkey": preevent.pubkey.as_hex_string(),
+            "created_at": preevent.created_at.0,
+            "kind": <nostr_types::EventKind as Into<u64>>::into(preevent.kind),
+            "tags": serde_json::to_value(&preevent.tags)?,
+            "content": preevent.content,
+        });
+        let response = self
+            .remote_request(Nip46Request::new("sign_event", vec![unsigned]))
+            .await?;
+        let event: Event = serde_json::from_str(&response.result_string()?)?;
+        event.verify(None)?;
+        if event.pubkey != preevent.pubkey {
+            return Err("Remote signer signed with a different key")?;
         }
+        Ok(event)
     }
 
     pub fn export_private_key_bech32(&self, pass: &str) -> Result<String, Error> {
@@ -226,6 +301,8 @@ impl Signer {
         *self.private.write() = None;
         *self.encrypted.write() = None;
         *self.public.write() = None;
+        *self.remote.write() = None;
+        self.remote_decrypted.clear();
 
         task::spawn(async move {
             if let Err(e) = GLOBALS.signer.save_through_settings().await {
@@ -235,9 +312,340 @@ impl Signer {
     }
 
     pub fn decrypt_message(&self, event: &Event) -> Result<String, Error> {
-        match &*self.private.read() {
-            Some(private) => Ok(event.decrypted_contents(private)?),
-            _ => Err((ErrorKind::NoPrivateKey, file!(), line!()).into()),
+        if self.is_remote() {
+            return self.remote_decrypt_message(event);
+        }
+        match ContentEncryption::detect(&event.content) {
+            ContentEncryption::Nip04 => match &*self.private.read() {
+                Some(private) => Ok(event.decrypted_contents(private)?),
+                _ => Err((ErrorKind::NoPrivateKey, file!(), line!()).into()),
+            },
+            ContentEncryption::Nip44 => {
+                let other = match self.dm_counterparty(event) {
+                    Some(pk) => pk,
+                    None => return Err("Cannot tell who this message is from")?,
+                };
+                let mut key = self.nip44_conversation_key(&other)?;
+                let result = nip44::decrypt(&key, &event.content);
+                key.zeroize();
+                result
+            }
+        }
+    }
+
+    /// Encrypt a direct message to `other`
+    pub async fn encrypt_message(
+        &self,
+        other: &PublicKey,
+        plaintext: &str,
+        encryption: ContentEncryption,
+    ) -> Result<String, Error> {
+        if self.is_remote() {
+            let method = match encryption {
+                ContentEncryption::Nip04 => "nip04_encrypt",
+                ContentEncryption::Nip44 => "nip44_encrypt",
+            };
+            let response = self
+                .remote_request(Nip46Request::new(
+                    method,
+                    vec![other.as_hex_string().into(), plaintext.into()],
+                ))
+                .await?;
+            return response.result_string();
+        }
+        match encryption {
+            ContentEncryption::Nip04 => match &*self.private.read() {
+                Some(private) => Ok(private.nip04_encrypt(other, plaintext.as_bytes())?),
+                _ => Err((ErrorKind::NoPrivateKey, file!(), line!()).into()),
+            },
+            ContentEncryption::Nip44 => {
+                let mut key = self.nip44_conversation_key(other)?;
+                let result = nip44::encrypt(&key, plaintext);
+                key.zeroize();
+                result
+            }
+        }
+    }
+
+    /// Encrypt something only we can read, such as the private part of a list
+    pub async fn encrypt_to_self(&self, plaintext: &str) -> Result<String, Error> {
+        let public_key = match self.public_key() {
+            Some(pk) => pk,
+            None => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()),
+        };
+        self.encrypt_message(&public_key, plaintext, ContentEncryption::Nip44)
+            .await
+    }
+
+    /// Decrypt something we encrypted to ourselves, with either encryption
+    pub async fn decrypt_from_self(&self, ciphertext: &str) -> Result<String, Error> {
+        let public_key = match self.public_key() {
+            Some(pk) => pk,
+            None => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()),
+        };
+        let encryption = ContentEncryption::detect(ciphertext);
+        if self.is_remote() {
+            let method = match encryption {
+                ContentEncryption::Nip04 => "nip04_decrypt",
+                ContentEncryption::Nip44 => "nip44_decrypt",
+            };
+            let response = self
+                .remote_request(Nip46Request::new(
+                    method,
+                    vec![public_key.as_hex_string().into(), ciphertext.into()],
+                ))
+                .await?;
+            return response.result_string();
+        }
+        match encryption {
+            ContentEncryption::Nip04 => {
+                let bytes = match &*self.private.read() {
+                    Some(private) => private.nip04_decrypt(&public_key, ciphertext)?,
+                    _ => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()),
+                };
+                Ok(String::from_utf8_lossy(&bytes).into_owned())
+            }
+            ContentEncryption::Nip44 => {
+                let mut key = self.nip44_conversation_key(&public_key)?;
+                let result = nip44::decrypt(&key, ciphertext);
+                key.zeroize();
+                result
+            }
+        }
+    }
+
+    // The key never leaves the signer; we only hand out the conversation key
+    // derived from it, and only inside this file.
+    fn nip44_conversation_key(&self, other: &PublicKey) -> Result<[u8; 32], Error> {
+        let mut private = match &*self.private.read() {
+            Some(private) => private.clone(),
+            _ => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()),
+        };
+        // This marks our copy as weak, not the key we hold
+        let mut secret_hex = private.as_hex_string();
+        let result = nip44::conversation_key(&secret_hex, other);
+        secret_hex.zeroize();
+        result
+    }
+
+    /// The other party to a direct message: the author, unless we are the author
+    fn dm_counterparty(&self, event: &Event) -> Option<PublicKey> {
+        if Some(event.pubkey) == self.public_key() {
+            event.tags.iter().find_map(|t| match t {
+                Tag::Pubkey { pubkey, .. } => PublicKey::try_from_hex_string(pubkey.as_str()).ok(),
+                _ => None,
+            })
+        } else {
+            Some(event.pubkey)
+        }
+    }
+
+    /// Start pairing with a remote signer from a bunker:// URI
+    pub fn set_remote_signer_from_bunker_uri(&self, uri: &str) -> Result<RelayUrl, Error> {
+        let connection = Nip46Connection::from_bunker_uri(uri)?;
+        let relay = connection.relay.clone();
+        *self.remote.write() = Some(connection);
+        Ok(relay)
+    }
+
+    /// Start waiting for a remote signer to scan a nostrconnect:// URI
+    pub fn set_remote_signer_for_nostrconnect(&self, relay: RelayUrl) {
+        *self.remote.write() = Some(Nip46Connection::new_for_nostrconnect(relay));
+    }
+
+    /// The nostrconnect:// URI to show, while we wait for a remote signer to scan it
+    pub fn nostrconnect_uri(&self) -> Option<String> {
+        match &*self.remote.read() {
+            Some(c) if c.remote_pubkey.is_none() => Some(c.nostrconnect_uri()),
+            _ => None,
         }
     }
+
+    /// The relay we talk to the remote signer through, and whether pairing is finished
+    pub fn remote_signer_status(&self) -> Option<(RelayUrl, bool)> {
+        self.remote
+            .read()
+            .as_ref()
+            .map(|c| (c.relay.clone(), c.is_paired()))
+    }
+
+    /// The relay and our throwaway pubkey, for subscribing to remote signer messages
+    pub fn remote_signer_subscription(&self) -> Option<(RelayUrl, PublicKey)> {
+        self.remote
+            .read()
+            .as_ref()
+            .map(|c| (c.relay.clone(), c.local_key.public_key()))
+    }
+
+    pub fn disconnect_remote_signer(&self) {
+        *self.remote.write() = None;
+        self.remote_pending.lock().clear();
+        self.remote_decrypted.clear();
+        if self.private.read().is_none() && self.encrypted.read().is_none() {
+            *self.public.write() = None;
+        }
+
+        task::spawn(async move {
+            if let Err(e) = GLOBALS.signer.save_through_settings().await {
+                tracing::error!("{}", e);
+            }
+        });
+    }
+
+    /// Finish pairing: ask the remote signer whose key it holds, then save.
+    pub async fn pair_remote_signer(&self) -> Result<(), Error> {
+        let secret = self.remote.read().as_ref().and_then(|c| c.secret.clone());
+        let local_pubkey = match &*self.remote.read() {
+            Some(c) => c.local_key.public_key(),
+            None => return Err("No remote signer")?,
+        };
+
+        // If we were given a bunker URI, we introduce ourselves. (If the remote
+        // signer scanned our nostrconnect URI, it already introduced itself.)
+        if let Some(secret) = secret {
+            let response = self
+                .remote_request(Nip46Request::new(
+                    "connect",
+                    vec![local_pubkey.as_hex_string().into(), secret.into()],
+                ))
+                .await?;
+            response.result_string()?;
+        }
+
+        let response = self
+            .remote_request(Nip46Request::new("get_public_key", vec![]))
+            .await?;
+        let user_pubkey = PublicKey::try_from_hex_string(&response.result_string()?)?;
+
+        if let Some(c) = self.remote.write().as_mut() {
+            c.user_pubkey = Some(user_pubkey);
+        }
+        *self.public.write() = Some(user_pubkey);
+        self.save_through_settings().await?;
+
+        *GLOBALS.status_message.write().await = "Remote signer connected.".to_owned();
+
+        Ok(())
+    }
+
+    /// Handle an incoming NIP-46 event (addressed to our throwaway key)
+    pub fn handle_nip46_event(&self, event: &Event) {
+        let message = {
+            let remote = self.remote.read();
+            let connection = match &*remote {
+                Some(c) => c,
+                None => return,
+            };
+            if let Some(remote_pubkey) = connection.remote_pubkey {
+                if event.pubkey != remote_pubkey {
+                    return;
+                }
+            }
+            match connection.decrypt_message(event) {
+                Ok(m) => m,
+                Err(e) => {
+                    tracing::warn!("Could not read remote signer message: {}", e);
+                    return;
+                }
+            }
+        };
+
+        // A remote signer answering our nostrconnect:// URI
+        if message.method.as_deref() == Some("connect") {
+            if let Some(c) = self.remote.write().as_mut() {
+                if !c.accepts_connect(&message) {
+                    tracing::warn!("Ignoring a remote signer connect without our secret");
+                    return;
+                }
+                c.remote_pubkey = Some(event.pubkey);
+                // It was only good for this; we do not send it to the remote signer
+                c.secret = None;
+            }
+            std::mem::drop(task::spawn(async move {
+                if let Err(e) = GLOBALS.signer.pair_remote_signer().await {
+                    tracing::error!("{}", e);
+                    *GLOBALS.status_message.write().await =
+                        format!("Remote signer pairing failed: {}", e);
+                }
+            }));
+            return;
+        }
+
+        // A response to one of our requests
+        if let Some(sender) = self.remote_pending.lock().remove(&message.id) {
+            let _ = sender.send(message);
+        }
+    }
+
+    async fn remote_request(&self, request: Nip46Request) -> Result<Nip46Message, Error> {
+        let (event, relay) = match &*self.remote.read() {
+            Some(c) => (c.request_event(&request)?, c.relay.clone()),
+            None => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()),
+        };
+
+        let (sender, receiver) = oneshot::channel();
+        self.remote_pending
+            .lock()
+            .insert(request.id.clone(), sender);
+
+        // We send straight to the minion rather than through the overlord, because
+        // the overlord may be the one waiting on us.
+        GLOBALS.to_minions.send(ToMinionMessage {
+            target: relay.0,
+            payload: ToMinionPayload {
+                job_id: 0,
+                detail: ToMinionPayloadDetail::PostEvent(Box::new(event)),
+            },
+        })?;
+
+        match tokio::time::timeout(REMOTE_SIGNER_TIMEOUT, receiver).await {
+            Ok(Ok(message)) => Ok(message),
+            _ => {
+                self.remote_pending.lock().remove(&request.id);
+                Err("The remote signer did not answer")?
+            }
+        }
+    }
+
+    // The UI needs an answer now, so we answer from the cache, and on a cache miss
+    // we ask the remote signer in the background.
+    fn remote_decrypt_message(&self, event: &Event) -> Result<String, Error> {
+        if let Some(plaintext) = self.remote_decrypted.get(&event.id) {
+            return Ok(plaintext.value().clone());
+        }
+
+        if self.remote_decrypting.insert(event.id) {
+            let other = match self.dm_counterparty(event) {
+                Some(pk) => pk,
+                None => return Err("Cannot tell who this message is from")?,
+            };
+
+            let method = match ContentEncryption::detect(&event.content) {
+                ContentEncryption::Nip04 => "nip04_decrypt",
+                ContentEncryption::Nip44 => "nip44_decrypt",
+            };
+            let id = event.id;
+            let ciphertext = event.content.clone();
+            std::mem::drop(task::spawn(async move {
+                let result = GLOBALS
+                    .signer
+                    .remote_request(Nip46Request::new(
+                        method,
+                        vec![other.as_hex_string().into(), ciphertext.into()],
+                    ))
+                    .await
+                    .and_then(|m| m.result_string());
+                // On failure we leave it marked, so we don't keep asking
+                match result {
+                    Ok(plaintext) => {
+                        GLOBALS.signer.remote_decrypted.insert(id, plaintext);
+                        GLOBALS.signer.remote_decrypting.remove(&id);
+                    }
+                    Err(e) => tracing::warn!("Remote decrypt failed: {}", e),
+                }
+            }));
+        }
+
+        Err("Waiting for the remote signer to decrypt")?
+    }
 }
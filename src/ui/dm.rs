@@ -0,0 +1,282 @@
+use super::{GossipUi, Page};
+use crate::comms::ToOverlordMessage;
+use crate::globals::GLOBALS;
+use crate::nip44::ContentEncryption;
+use crate::AVATAR_SIZE_F32;
+use eframe::egui;
+use egui::{Align, Context, Image, Layout, RichText, ScrollArea, Sense, Ui, Vec2};
+use nostr_types::{PublicKey, PublicKeyHex};
+use std::sync::atomic::Ordering;
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    if !app.settings.direct_messages {
+        ui.add_space(10.0);
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Direct messages are turned off. You can turn them on in ");
+            if ui.link("Settings").clicked() {
+                app.set_page(Page::Settings);
+            }
+            ui.label(".");
+        });
+        ui.add_space(10.0);
+    }
+
+    match app.page.clone() {
+        Page::DmChatList => render_dm_list(app, ctx, ui),
+        Page::DmChat(pubkey) => render_dm_chat(app, ctx, ui, pubkey),
+        _ => {}
+    }
+}
+
+fn render_dm_list(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
+    ui.heading("Direct Messages");
+    ui.add_space(12.0);
+
+    ui.horizontal(|ui| {
+        ui.label("New conversation with");
+        ui.add(
+            text_edit_line!(app, app.dm_new_recipient)
+                .hint_text("npub1 or hex")
+                .desired_width(400.0),
+        );
+        if ui.button("Start").clicked() {
+            let input = app.dm_new_recipient.trim().to_owned();
+            match PublicKey::try_from_bech32_string(&input)
+                .or_else(|_| PublicKey::try_from_hex_string(&input))
+            {
+                Ok(pk) => {
+                    app.dm_new_recipient = "".to_owned();
+                    app.set_page(Page::DmChat(pk.into()));
+                }
+                Err(_) => {
+                    *GLOBALS.status_message.blocking_write() =
+                        "That is not a valid public key.".to_owned();
+                }
+            }
+        }
+    });
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    let channels = GLOBALS.feed.get_dm_channels();
+    if channels.is_empty() {
+        ui.label("No conversations yet.");
+        return;
+    }
+
+    ScrollArea::vertical()
+        .override_scroll_delta(Vec2 {
+            x: 0.0,
+            y: app.current_scroll_offset,
+        })
+        .show(ui, |ui| {
+            for channel in channels.iter() {
+                let mut open = false;
+
+                ui.horizontal(|ui| {
+                    let avatar = match app.try_get_avatar(ctx, &channel.counterparty) {
+                        Some(avatar) => avatar,
+                        None => app.placeholder_avatar.clone(),
+                    };
+                    let size = AVATAR_SIZE_F32
+                        * GLOBALS.pixels_per_point_times_100.load(Ordering::Relaxed) as f32
+                        / 100.0;
+                    if ui
+                        .add(Image::new(&avatar, Vec2 { x: size, y: size }).sense(Sense::click()))
+                        .clicked()
+                    {
+                        open = true;
+                    }
+
+                    ui.vertical(|ui| {
+                        let name =
+                            GossipUi::display_name_from_pubkeyhex_lookup(&channel.counterparty);
+                        if ui.link(RichText::new(name).strong()).clicked() {
+                            open = true;
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label(
+                                RichText::new(format!(
+                                    "{} messages, last {}",
+                                    channel.message_ids.len(),
+                                    crate::date_ago::date_ago(channel.latest_message)
+                                ))
+                                .weak(),
+                            );
+                            if channel.unread > 0 {
+                                ui.label(
+                                    RichText::new(format!("{} unread", channel.unread))
+                                        .strong()
+                                        .color(app.settings.theme.notice_marker_text_color()),
+                                );
+                            }
+                        });
+                    });
+                });
+
+                if open {
+                    app.set_page(Page::DmChat(channel.counterparty.clone()));
+                }
+
+                ui.add_space(4.0);
+                ui.separator();
+            }
+        });
+}
+
+fn render_dm_chat(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, counterparty: PublicKeyHex) {
+    // Make sure we eventually have their name and avatar
+    if GLOBALS.people.get(&counterparty).is_none() {
+        GLOBALS.people.person_of_interest(counterparty.clone());
+    }
+
+    ui.horizontal(|ui| {
+        let avatar = match app.try_get_avatar(ctx, &counterparty) {
+            Some(avatar) => avatar,
+            None => app.placeholder_avatar.clone(),
+        };
+        let size = AVATAR_SIZE_F32
+            * GLOBALS.pixels_per_point_times_100.load(Ordering::Relaxed) as f32
+            / 100.0;
+        if ui
+            .add(Image::new(&avatar, Vec2 { x: size, y: size }).sense(Sense::click()))
+            .clicked()
+        {
+            app.set_page(Page::Person(counterparty.clone()));
+        }
+        ui.heading(GossipUi::display_name_from_pubkeyhex_lookup(&counterparty));
+    });
+
+    ui.add_space(10.0);
+    ui.separator();
+
+    let my_pubkey = GLOBALS.signer.public_key();
+    let message_ids = GLOBALS
+        .feed
+        .get_dm_channel(&counterparty)
+        .map(|c| c.message_ids)
+        .unwrap_or_default();
+
+    let composer_height = 130.0;
+    ScrollArea::vertical()
+        .id_source("dm_chat")
+        .max_height(ui.available_height() - composer_height)
+        .stick_to_bottom(true)
+        .override_scroll_delta(Vec2 {
+            x: 0.0,
+            y: app.current_scroll_offset,
+        })
+        .show(ui, |ui| {
+            if message_ids.is_empty() {
+                ui.label("No messages yet.");
+            }
+
+            for id in message_ids.iter() {
+                let event = match GLOBALS.events.get(id) {
+                    Some(e) => e,
+                    None => continue,
+                };
+                let from_me = Some(event.pubkey) == my_pubkey;
+
+                let plaintext = match app.dm_plaintexts.get(id) {
+                    Some(p) => p.clone(),
+                    None => match GLOBALS.signer.decrypt_message(&event) {
+                        Ok(p) => {
+                            app.dm_plaintexts.insert(*id, p.clone());
+                            p
+                        }
+                        Err(_) if !GLOBALS.signer.is_ready() => {
+                            "(unlock your key to read this message)".to_owned()
+                        }
+                        Err(e) => format!("(could not decrypt: {})", e),
+                    },
+                };
+
+                let layout = if from_me {
+                    Layout::top_down(Align::Max)
+                } else {
+                    Layout::top_down(Align::Min)
+                };
+                ui.with_layout(layout, |ui| {
+                    ui.label(
+                        RichText::new(format!(
+                            "{} · {}",
+                            crate::date_ago::date_ago(event.created_at),
+                            ContentEncryption::detect(&event.content).name()
+                        ))
+                        .weak()
+                        .small(),
+                    );
+                    ui.add(egui::Label::new(plaintext).wrap(true));
+                });
+                ui.add_space(6.0);
+
+                // Being shown in the chat counts as viewed
+                if !from_me && !GLOBALS.viewed_events.contains(id) {
+                    GLOBALS.viewed_events.insert(*id);
+                    GLOBALS.new_viewed_events.blocking_write().insert(*id);
+                }
+            }
+        });
+
+    ui.separator();
+
+    if !GLOBALS.signer.is_ready() {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("You need to ");
+            if ui.link("unlock your key").clicked() {
+                app.set_page(Page::YourKeys);
+            }
+            ui.label(" to send messages.");
+        });
+        return;
+    }
+
+    ui.horizontal(|ui| {
+        let current = GLOBALS.feed.dm_outgoing_encryption(&counterparty);
+        let chosen = GLOBALS.dm_encryption.contains_key(&counterparty);
+        ui.label("Encrypt with");
+        for encryption in [ContentEncryption::Nip44, ContentEncryption::Nip04] {
+            if ui
+                .selectable_label(current == encryption, encryption.name())
+                .clicked()
+                && (!chosen || current != encryption)
+            {
+                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::SetDmEncryption(
+                    counterparty.clone(),
+                    encryption,
+                ));
+            }
+        }
+        if !chosen {
+            ui.label(RichText::new("(automatic: matches what they last sent)").weak());
+        }
+        if current == ContentEncryption::Nip04 {
+            ui.label(RichText::new("NIP-04 is unauthenticated and leaks message length").weak());
+        }
+    });
+
+    // Each conversation keeps its own draft
+    ui.horizontal(|ui| {
+        let draft = app.dm_drafts.entry(counterparty.clone()).or_default();
+        let response = ui.add(
+            text_edit_multiline!(app, *draft)
+                .hint_text("Type a message (Ctrl+Enter to send)")
+                .desired_rows(3)
+                .desired_width(ui.available_width() - 80.0),
+        );
+        let send_key = response.has_focus()
+            && ui.input(|i| i.key_pressed(egui::Key::Enter) && i.modifiers.command);
+        if (ui.button("Send").clicked() || send_key) && !draft.trim().is_empty() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::SendDirectMessage(
+                    counterparty.clone(),
+                    draft.trim().to_owned(),
+                ));
+            app.dm_drafts.remove(&counterparty);
+        }
+    });
+}
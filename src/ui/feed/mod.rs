@@ -1,5 +1,6 @@
 use super::theme::FeedProperties;
 use super::{GossipUi, Page};
+use crate::comms::ToOverlordMessage;
 use crate::feed::FeedKind;
 use crate::globals::{Globals, GLOBALS};
 use eframe::egui;
@@ -9,6 +10,7 @@ use nostr_types::Id;
 pub use note::Notes;
 
 mod note;
+pub(super) use note::show_image_toggle;
 pub use note::NoteRenderData;
 pub(super) mod post;
 
@@ -54,6 +56,28 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
                 app.set_page(Page::Feed(FeedKind::Inbox(app.inbox_include_indirect)));
             }
             ui.separator();
+            if ui
+                .add(egui::SelectableLabel::new(
+                    app.page == Page::Feed(FeedKind::Bookmarks),
+                    "Bookmarks",
+                ))
+                .clicked()
+            {
+                app.set_page(Page::Feed(FeedKind::Bookmarks));
+            }
+            for name in GLOBALS.person_lists.names() {
+                ui.separator();
+                if ui
+                    .add(egui::SelectableLabel::new(
+                        app.page == Page::Feed(FeedKind::List(name.clone())),
+                        name.as_str(),
+                    ))
+                    .clicked()
+                {
+                    app.set_page(Page::Feed(FeedKind::List(name)));
+                }
+            }
+            ui.separator();
             if matches!(feed_kind.clone(), FeedKind::Thread { .. }) {
                 ui.separator();
                 if ui
@@ -78,6 +102,30 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
                     app.set_page(Page::Feed(feed_kind.clone()));
                 }
             }
+            if matches!(feed_kind, FeedKind::Hashtag(..)) {
+                ui.separator();
+                if ui
+                    .add(egui::SelectableLabel::new(
+                        app.page == Page::Feed(feed_kind.clone()),
+                        "Hashtag",
+                    ))
+                    .clicked()
+                {
+                    app.set_page(Page::Feed(feed_kind.clone()));
+                }
+            }
+            for hashtag in app.settings.pinned_hashtags.clone() {
+                ui.separator();
+                if ui
+                    .add(egui::SelectableLabel::new(
+                        app.page == Page::Feed(FeedKind::Hashtag(hashtag.clone())),
+                        format!("#{}", hashtag),
+                    ))
+                    .clicked()
+                {
+                    app.set_page(Page::Feed(FeedKind::Hashtag(hashtag)));
+                }
+            }
 
             if GLOBALS
                 .feed
@@ -237,10 +285,64 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
             let feed = GLOBALS.feed.get_person_feed();
             render_a_feed(app, ctx, frame, ui, feed, false, pubkeyhex.as_str());
         }
+        FeedKind::Hashtag(hashtag) => {
+            ui.horizontal(|ui| {
+                #[cfg(feature = "side-menu")] // FIXME relocate
+                recompute_btn(app, ui);
+
+                ui.heading(format!("#{}", hashtag));
+                let pinned = app.settings.pinned_hashtags.contains(&hashtag);
+                if ui
+                    .button(if pinned { "Unpin" } else { "Pin" })
+                    .on_hover_text("Pinned hashtags are listed in the menu")
+                    .clicked()
+                {
+                    toggle_pinned_hashtag(app, &hashtag);
+                }
+            });
+
+            let feed = GLOBALS.feed.get_hashtag_feed();
+            render_a_feed(app, ctx, frame, ui, feed, false, &format!("#{}", hashtag));
+        }
+        FeedKind::Bookmarks => {
+            #[cfg(feature = "side-menu")] // FIXME relocate
+            ui.horizontal(|ui| {
+                recompute_btn(app, ui);
+            });
+
+            let feed = GLOBALS.feed.get_bookmarks_feed();
+            render_a_feed(app, ctx, frame, ui, feed, false, "bookmarks");
+        }
+        FeedKind::List(name) => {
+            ui.horizontal(|ui| {
+                ui.heading(name.as_str());
+                ui.label(format!(
+                    "({} people)",
+                    GLOBALS.person_lists.members(&name).len()
+                ));
+                #[cfg(feature = "side-menu")] // FIXME relocate
+                recompute_btn(app, ui);
+            });
+
+            let feed = GLOBALS.feed.get_list_feed();
+            render_a_feed(app, ctx, frame, ui, feed, false, &format!("list:{}", name));
+        }
+    }
+}
+
+fn toggle_pinned_hashtag(app: &mut GossipUi, hashtag: &String) {
+    let mut pinned = GLOBALS.settings.read().pinned_hashtags.clone();
+    if pinned.contains(hashtag) {
+        pinned.retain(|h| h != hashtag);
+    } else {
+        pinned.push(hashtag.to_owned());
     }
+    app.settings.pinned_hashtags = pinned.clone();
+    GLOBALS.settings.write().pinned_hashtags = pinned;
+    let _ = GLOBALS.to_overlord.send(ToOverlordMessage::SaveSettings);
 }
 
-fn render_a_feed(
+pub(super) fn render_a_feed(
     app: &mut GossipUi,
     ctx: &Context,
     frame: &mut eframe::Frame,
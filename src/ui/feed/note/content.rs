@@ -69,7 +69,7 @@ pub(super) fn render_content(
                                 }
                             }
                             Tag::Hashtag(s) => {
-                                render_hashtag(ui, s);
+                                render_hashtag(app, ui, s);
                             }
                             _ => {
                                 render_unknown_reference(ui, *num);
@@ -161,10 +161,9 @@ pub(super) fn render_event_link(
     };
 }
 
-pub(super) fn render_hashtag(ui: &mut Ui, s: &String) {
+pub(super) fn render_hashtag(app: &mut GossipUi, ui: &mut Ui, s: &String) {
     if ui.link(format!("#{}", s)).clicked() {
-        *GLOBALS.status_message.blocking_write() =
-            "Gossip doesn't have a hashtag feed yet.".to_owned();
+        app.set_page(Page::Feed(FeedKind::Hashtag(s.to_lowercase())));
     }
 }
 
@@ -192,7 +191,7 @@ fn is_video_url(url: &url::Url) -> bool {
         || lower.ends_with(".webm")
 }
 
-fn show_image_toggle(app: &mut GossipUi, ui: &mut Ui, url: Url) {
+pub(in crate::ui) fn show_image_toggle(app: &mut GossipUi, ui: &mut Ui, url: Url) {
     let row_height = ui.cursor().height();
     let url_string = url.to_string();
     let mut show_link = true;
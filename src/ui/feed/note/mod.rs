@@ -1,6 +1,7 @@
 mod content;
 mod notedata;
 
+pub(in crate::ui) use content::show_image_toggle;
 pub use notedata::Notes;
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -9,10 +10,14 @@ use notedata::{NoteData, RepostType};
 
 use super::FeedNoteParams;
 use crate::comms::ToOverlordMessage;
+use crate::db::MuteKind;
+use crate::delivery::DeliveryStatus;
 use crate::feed::FeedKind;
 use crate::globals::{Globals, GLOBALS};
+use crate::long_form::LongFormMeta;
 use crate::ui::widgets::CopyButton;
 use crate::ui::{GossipUi, Page};
+use crate::zap::LnurlAddress;
 use crate::AVATAR_SIZE_F32;
 pub const AVATAR_SIZE_REPOST_F32: f32 = 27.0; // points, not pixels
 use eframe::egui::{self, Margin};
@@ -20,7 +25,7 @@ use egui::{
     Align, Context, Frame, Image, Label, Layout, RichText, Sense, Separator, Stroke, TextStyle, Ui,
     Vec2,
 };
-use nostr_types::{Event, EventDelegation, EventKind, EventPointer, IdHex};
+use nostr_types::{Event, EventDelegation, EventKind, EventPointer, Id, IdHex, RelayUrl};
 
 pub struct NoteRenderData {
     /// Available height for post
@@ -364,6 +369,27 @@ fn render_note_inner(
                         if ui.button("Dismiss").clicked() {
                             GLOBALS.dismissed.blocking_write().push(note.event.id);
                         }
+                        if ui.button("Mute Thread").clicked() {
+                            let root = match note.event.replies_to_root() {
+                                Some((root, _)) => root,
+                                None => note.event.id,
+                            };
+                            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::AddMute(
+                                MuteKind::Thread,
+                                root.as_hex_string(),
+                            ));
+                        }
+                        if GLOBALS.bookmarks.is_bookmarked(&note.event.id) {
+                            if ui.button("Remove Bookmark").clicked() {
+                                let _ = GLOBALS
+                                    .to_overlord
+                                    .send(ToOverlordMessage::DeleteBookmark(note.event.id));
+                            }
+                        } else if ui.button("Bookmark").clicked() {
+                            let _ = GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::AddBookmark(note.event.id));
+                        }
                         if Some(note.event.pubkey) == GLOBALS.signer.public_key()
                             && note.deletion.is_none()
                         {
@@ -423,6 +449,11 @@ fn render_note_inner(
 
                     ui.add_space(4.0);
 
+                    if let Some(deliveries) = GLOBALS.deliveries.get(&note.event.id) {
+                        render_delivery_badge(app, ui, note.event.id, deliveries);
+                        ui.add_space(4.0);
+                    }
+
                     let mut seen_on_popup_position = ui.next_widget_position();
                     seen_on_popup_position.y += 18.0; // drop below the icon itself
 
@@ -511,6 +542,20 @@ fn render_note_inner(
                         });
                 }
 
+                // Zapping?
+                if app.zapping == Some(note.event.id) {
+                    Frame::none()
+                        .inner_margin(Margin {
+                            left: footer_margin_left,
+                            bottom: 0.0,
+                            right: 0.0,
+                            top: 8.0,
+                        })
+                        .show(ui, |ui| {
+                            render_zap(app, ui, ctx, &note);
+                        });
+                }
+
                 // Footer
                 if !hide_footer {
                     Frame::none()
@@ -694,6 +739,51 @@ fn render_note_inner(
                                         }
                                     }
                                 }
+
+                                // Button to zap and zap total
+                                if app.settings.enable_zap_receipts
+                                    && note.event.kind != EventKind::EncryptedDirectMessage
+                                {
+                                    ui.add_space(24.0);
+
+                                    // Learn who signs the author's zap receipts, so that
+                                    // we can count them
+                                    if LnurlAddress::from_person(&note.author).is_some()
+                                        && GLOBALS.zapper_lookups.insert(note.author.pubkey.clone())
+                                    {
+                                        let _ = GLOBALS.to_overlord.send(
+                                            ToOverlordMessage::FindZapper(
+                                                note.author.pubkey.clone(),
+                                            ),
+                                        );
+                                    }
+
+                                    if ui
+                                        .add(
+                                            Label::new(RichText::new("⚡").size(16.0))
+                                                .sense(Sense::click()),
+                                        )
+                                        .on_hover_text("Zap")
+                                        .clicked()
+                                    {
+                                        if !render_data.can_post {
+                                            *GLOBALS.status_message.blocking_write() =
+                                                "Your key is not setup.".to_string();
+                                        } else if app.zapping != Some(note.event.id) {
+                                            app.zapping = Some(note.event.id);
+                                            // With a wallet, zap the last amount right away
+                                            if GLOBALS.wallet.is_ready() {
+                                                start_zap(app, &note);
+                                            }
+                                        } else {
+                                            app.zapping = None;
+                                        }
+                                    }
+                                    if note.zaps.1 > 0 {
+                                        ui.label(format!("{} sats", note.zaps.0 / 1000))
+                                            .on_hover_text(format!("{} zaps", note.zaps.1));
+                                    }
+                                }
                             });
                         });
                 }
@@ -702,6 +792,151 @@ fn render_note_inner(
     }
 }
 
+fn render_zap(app: &mut GossipUi, ui: &mut Ui, ctx: &Context, note: &NoteData) {
+    let current_zap = GLOBALS.current_zap.read().clone();
+    match current_zap {
+        Some(zap) if zap.id == note.event.id => match zap.invoice {
+            Some(_) if zap.paid => {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Zapped {} sats.", zap.msats / 1000));
+                    if ui.button("Done").clicked() {
+                        *GLOBALS.current_zap.write() = None;
+                        app.zapping = None;
+                    }
+                });
+            }
+            Some(_) if zap.paying => {
+                ui.label(format!(
+                    "Paying {} sats through your wallet...",
+                    zap.msats / 1000
+                ));
+            }
+            Some(invoice) => {
+                ui.label(format!(
+                    "Pay this invoice with your lightning wallet to zap {} sats:",
+                    zap.msats / 1000
+                ));
+                app.render_qr(ui, ctx, "zapqr", &invoice);
+                ui.horizontal(|ui| {
+                    if ui
+                        .add(CopyButton {})
+                        .on_hover_text("Copy invoice")
+                        .clicked()
+                    {
+                        ui.output_mut(|o| o.copied_text = invoice.clone());
+                    }
+                    if ui.button("Done").clicked() {
+                        *GLOBALS.current_zap.write() = None;
+                        app.qr_codes.remove("zapqr");
+                        app.zapping = None;
+                    }
+                });
+            }
+            None => {
+                ui.label("Asking their lightning wallet for an invoice...");
+            }
+        },
+        _ => {
+            if LnurlAddress::from_person(&note.author).is_none() {
+                ui.label("This person has no lightning address to zap.");
+                return;
+            }
+            ui.horizontal(|ui| {
+                ui.label("Sats:");
+                ui.add(text_edit_line!(app, app.zap_sats).desired_width(80.0));
+                ui.add(text_edit_line!(app, app.zap_comment).hint_text("comment (optional)"));
+                let label = if GLOBALS.wallet.is_ready() {
+                    "Zap"
+                } else {
+                    "Get Invoice"
+                };
+                if ui.button(label).clicked() {
+                    start_zap(app, note);
+                }
+                if ui.button("Cancel").clicked() {
+                    app.zapping = None;
+                }
+            });
+        }
+    }
+}
+
+fn start_zap(app: &mut GossipUi, note: &NoteData) {
+    if LnurlAddress::from_person(&note.author).is_none() {
+        return; // the zap area says so
+    }
+    match app.zap_sats.trim().parse::<u64>() {
+        Ok(sats) if sats > 0 => {
+            app.qr_codes.remove("zapqr");
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::Zap(
+                note.event.id,
+                note.author.pubkey.clone(),
+                sats * 1000,
+                std::mem::take(&mut app.zap_comment),
+            ));
+        }
+        _ => {
+            *GLOBALS.status_message.blocking_write() = "Enter a number of sats to zap.".to_owned();
+        }
+    }
+}
+
+// How many relays took our post, with what each of them said
+fn render_delivery_badge(
+    app: &mut GossipUi,
+    ui: &mut Ui,
+    id: Id,
+    deliveries: Vec<(RelayUrl, DeliveryStatus)>,
+) {
+    let accepted = deliveries
+        .iter()
+        .filter(|(_, s)| *s == DeliveryStatus::Accepted)
+        .count();
+    let pending = deliveries
+        .iter()
+        .any(|(_, s)| matches!(s, DeliveryStatus::Pending(_)));
+    let failed = deliveries.iter().any(|(_, s)| s.failed());
+
+    let mut text = RichText::new(format!(
+        "{}{}/{}",
+        if pending { "⏳" } else { "✔" },
+        accepted,
+        deliveries.len()
+    ))
+    .size(12.0);
+    if failed {
+        text = text.color(app.settings.theme.warning_marker_text_color());
+    }
+
+    ui.menu_button(text, |ui| {
+        for (url, status) in deliveries.iter() {
+            ui.horizontal(|ui| {
+                ui.label(url.as_str());
+                match status {
+                    DeliveryStatus::Pending(_) => ui.label(RichText::new("waiting").weak()),
+                    DeliveryStatus::Accepted => ui.label("accepted"),
+                    DeliveryStatus::Rejected(message) => ui.label(
+                        RichText::new(format!("rejected: {}", message))
+                            .color(app.settings.theme.warning_marker_text_color()),
+                    ),
+                    DeliveryStatus::NoAnswer => ui.label(
+                        RichText::new("no answer")
+                            .color(app.settings.theme.warning_marker_text_color()),
+                    ),
+                };
+            });
+        }
+        if failed && ui.button("Retry failed relays").clicked() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::RetryDelivery(id));
+            ui.close_menu();
+        }
+    })
+    .response
+    .on_hover_text("Delivery to relays");
+}
+
 fn thin_separator(ui: &mut Ui, stroke: Stroke) {
     let mut style = ui.style_mut();
     style.visuals.widgets.noninteractive.bg_stroke = stroke;
@@ -709,6 +944,25 @@ fn thin_separator(ui: &mut Ui, stroke: Stroke) {
     ui.reset_style();
 }
 
+// Articles are too long for the feed, so they get a card to open them from
+fn render_long_form_card(app: &mut GossipUi, ui: &mut Ui, event: &Event) {
+    let meta = LongFormMeta::from_event(event);
+    ui.vertical(|ui| {
+        let title = if meta.title.trim().is_empty() {
+            "Untitled article"
+        } else {
+            meta.title.as_str()
+        };
+        ui.label(RichText::new(title).text_style(TextStyle::Name("subject".into())));
+        if !meta.summary.is_empty() {
+            ui.label(RichText::new(meta.summary.as_str()).italics());
+        }
+        if ui.button("Read Article").clicked() {
+            app.set_page(Page::LongFormRead(event.id));
+        }
+    });
+}
+
 fn render_subject(ui: &mut Ui, event: &Event) {
     if let Some(subject) = event.subject() {
         ui.style_mut().spacing.item_spacing.x = 0.0;
@@ -782,6 +1036,8 @@ fn render_content(
                                 bottom_of_avatar,
                             );
                         }
+                    } else if event.kind == EventKind::LongFormContent {
+                        render_long_form_card(app, ui, event);
                     } else {
                         // Possible subject line
                         render_subject(ui, event);
@@ -40,6 +40,8 @@ pub(super) struct NoteData {
     pub(super) reactions: Vec<(char, usize)>,
     /// Has the current user reacted to this post?
     pub(super) self_already_reacted: bool,
+    /// Total millisats zapped to this post, and by how many zap receipts
+    pub(super) zaps: (u64, usize),
     /// The content shattered into renderable elements
     pub(super) shattered_content: ShatteredContent,
 }
@@ -55,6 +57,8 @@ impl NoteData {
 
         let (reactions, self_already_reacted) = Globals::get_reactions_sync(event.id);
 
+        let zaps = Globals::get_zaps_sync(event.id);
+
         // build a list of all cached mentions and their index
         // only notes that are in the cache will be rendered as reposts
         let mentions = {
@@ -178,6 +182,7 @@ impl NoteData {
             mentions,
             reactions,
             self_already_reacted,
+            zaps,
             shattered_content,
         }
     }
@@ -189,6 +194,8 @@ impl NoteData {
         self.reactions.append(&mut reactions);
 
         self.self_already_reacted = self_already_reacted;
+
+        self.zaps = Globals::get_zaps_sync(self.event.id);
     }
 }
 
@@ -212,6 +219,11 @@ impl Notes {
     }
      */
 
+    /// Drop all NoteData (e.g. after switching identities)
+    pub(in crate::ui) fn cache_invalidate_all(&mut self) {
+        self.notes.clear();
+    }
+
     /// Drop NoteData for a specific note
     pub(super) fn cache_invalidate_note(&mut self, id: &Id) {
         self.notes.remove(id);
@@ -2,12 +2,13 @@ use super::FeedNoteParams;
 use crate::comms::ToOverlordMessage;
 use crate::db::DbRelay;
 use crate::globals::GLOBALS;
+use crate::post_relays::PostRelay;
 use crate::ui::{you, GossipUi, HighlightType, Page, Theme};
 use eframe::egui;
 use eframe::epaint::text::LayoutJob;
 use egui::{Align, Context, Key, Layout, Modifiers, RichText, ScrollArea, Ui, Vec2};
 use memoize::memoize;
-use nostr_types::{find_nostr_bech32_pos, NostrBech32, Tag};
+use nostr_types::{find_nostr_bech32_pos, NostrBech32, PublicKeyHex, RelayUrl, Tag};
 
 #[memoize]
 pub fn textarea_highlighter(theme: Theme, text: String) -> LayoutJob {
@@ -265,6 +266,13 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
         });
     });
 
+    let relays = chosen_post_relays(app);
+    if send_now && app.draft_repost.is_none() && matches!(&relays, Some(r) if r.is_empty()) {
+        *GLOBALS.status_message.blocking_write() =
+            "Choose at least one relay to post to.".to_owned();
+        send_now = false;
+    }
+
     if send_now {
         match app.replying_to {
             Some(replying_to_id) => {
@@ -272,6 +280,7 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
                     app.draft.clone(),
                     vec![],
                     Some(replying_to_id),
+                    relays,
                 ));
             }
             None => {
@@ -294,6 +303,7 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
                         app.draft.clone(),
                         tags,
                         None,
+                        relays,
                     ));
                 }
             }
@@ -324,4 +334,159 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
 
         ui.label(format!("{}: {}", i, rendered));
     }
+    if app.draft_repost.is_none() {
+        post_relays_area(app, ui);
+    }
+}
+
+// Everybody the post will tag, as the overlord works it out when posting: those
+// mentioned in it, and the author and people tagged in the note replied to
+fn tagged_pubkeys(app: &GossipUi) -> Vec<PublicKeyHex> {
+    let mut tagged: Vec<PublicKeyHex> = Vec::new();
+    for bech32 in NostrBech32::find_all_in_string(&app.draft).iter() {
+        match bech32 {
+            NostrBech32::Pubkey(pk) => tagged.push((*pk).into()),
+            NostrBech32::Profile(prof) => tagged.push(prof.pubkey.into()),
+            _ => {}
+        }
+    }
+    if let Some(parent) = app.replying_to.and_then(|id| GLOBALS.events.get(&id)) {
+        tagged.push(parent.pubkey.into());
+        for tag in parent.tags.iter() {
+            if let Tag::Pubkey { pubkey, .. } = tag {
+                tagged.push(pubkey.clone());
+            }
+        }
+    }
+    if let Some(pk) = GLOBALS.signer.public_key() {
+        let ours: PublicKeyHex = pk.into();
+        tagged.retain(|p| *p != ours);
+    }
+    let mut unique: Vec<PublicKeyHex> = Vec::new();
+    for pubkey in tagged {
+        if !unique.contains(&pubkey) {
+            unique.push(pubkey);
+        }
+    }
+    unique
+}
+
+// The relays worked out for the people the post tags, if they are worked out yet
+fn computed_post_relays(app: &GossipUi) -> Option<Vec<PostRelay>> {
+    match &*GLOBALS.post_relays.read() {
+        Some((tagged, relays)) if Some(tagged) == app.post_relays_tagged.as_ref() => {
+            Some(relays.clone())
+        }
+        _ => None,
+    }
+}
+
+// What the user settled on, or None to leave it to the overlord
+fn chosen_post_relays(app: &GossipUi) -> Option<Vec<RelayUrl>> {
+    let computed = computed_post_relays(app)?;
+    let mut relays: Vec<RelayUrl> = computed
+        .into_iter()
+        .map(|r| r.url)
+        .filter(|url| !app.post_relays_excluded.contains(url))
+        .collect();
+    for url in app.post_relays_added.iter() {
+        if !relays.contains(url) {
+            relays.push(url.clone());
+        }
+    }
+    Some(relays)
+}
+
+fn post_relays_area(app: &mut GossipUi, ui: &mut Ui) {
+    // Work the relays out again whenever the people tagged change
+    let tagged = tagged_pubkeys(app);
+    if app.post_relays_tagged.as_ref() != Some(&tagged) {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::SelectPostRelays(tagged.clone()));
+        app.post_relays_tagged = Some(tagged);
+    }
+
+    let computed = computed_post_relays(app);
+    let count = match chosen_post_relays(app) {
+        Some(relays) => format!("{}", relays.len()),
+        None => "…".to_owned(),
+    };
+
+    egui::CollapsingHeader::new(format!("Posting to {} relays", count))
+        .id_source("post_relays")
+        .show(ui, |ui| {
+            let computed = match computed {
+                Some(computed) => computed,
+                None => {
+                    ui.label("Working out which relays to post to...");
+                    return;
+                }
+            };
+
+            for relay in computed {
+                ui.horizontal(|ui| {
+                    let mut included = !app.post_relays_excluded.contains(&relay.url);
+                    if ui.checkbox(&mut included, relay.url.as_str()).changed() {
+                        if included {
+                            app.post_relays_excluded.remove(&relay.url);
+                        } else {
+                            app.post_relays_excluded.insert(relay.url.clone());
+                        }
+                    }
+                    let mut why: Vec<String> = Vec::new();
+                    if relay.ours {
+                        why.push("yours".to_owned());
+                    }
+                    if !relay.readers.is_empty() {
+                        let names: Vec<String> = relay
+                            .readers
+                            .iter()
+                            .map(|pubkey| match GLOBALS.people.get(pubkey) {
+                                Some(person) => GossipUi::display_name_from_dbperson(&person),
+                                None => GossipUi::pubkeyhex_convert_short(pubkey),
+                            })
+                            .collect();
+                        why.push(format!("inbox of {}", names.join(", ")));
+                    }
+                    ui.label(RichText::new(why.join("; ")).weak());
+                });
+            }
+
+            let mut remove: Option<RelayUrl> = None;
+            for url in app.post_relays_added.iter() {
+                ui.horizontal(|ui| {
+                    ui.label(url.as_str());
+                    ui.label(RichText::new("added").weak());
+                    if ui.small_button("Remove").clicked() {
+                        remove = Some(url.clone());
+                    }
+                });
+            }
+            if let Some(url) = remove {
+                app.post_relays_added.retain(|u| *u != url);
+            }
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    text_edit_line!(app, app.post_relay_to_add)
+                        .hint_text("wss://")
+                        .desired_width(250.0),
+                );
+                if ui.button("Add Relay").clicked() {
+                    match RelayUrl::try_from_str(app.post_relay_to_add.trim()) {
+                        Ok(url) => {
+                            if !app.post_relays_added.contains(&url) {
+                                app.post_relays_added.push(url);
+                            }
+                            app.post_relay_to_add = "".to_owned();
+                        }
+                        Err(_) => {
+                            *GLOBALS.status_message.blocking_write() =
+                                "That is not a valid relay URL.".to_owned();
+                        }
+                    }
+                }
+            });
+        });
 }
@@ -3,6 +3,7 @@ use crate::globals::GLOBALS;
 use eframe::egui;
 use egui::{Context, ScrollArea, Ui};
 use humansize::{format_size, DECIMAL};
+use nostr_types::Unixtime;
 use std::sync::atomic::Ordering;
 
 pub(super) fn update(_app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
@@ -42,5 +43,33 @@ pub(super) fn update(_app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::F
             "Number of known relays: {}",
             GLOBALS.all_relays.len()
         ));
+
+        ui.add_space(6.0);
+
+        let prune_stats = GLOBALS.prune_stats.read().clone();
+
+        ui.label(format!(
+            "Database size: {}",
+            format_size(prune_stats.database_size, DECIMAL)
+        ));
+
+        ui.add_space(6.0);
+
+        if prune_stats.last_prune > 0 {
+            ui.label(format!(
+                "Last pruned: {} ago, reclaiming {}",
+                crate::date_ago::date_ago(Unixtime(prune_stats.last_prune)),
+                format_size(prune_stats.last_reclaimed, DECIMAL)
+            ));
+        } else {
+            ui.label("Last pruned: never");
+        }
+
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Total Bytes Reclaimed by Pruning: {}",
+            format_size(prune_stats.total_reclaimed, DECIMAL)
+        ));
     });
 }
@@ -0,0 +1,115 @@
+use super::{GossipUi, Page};
+use crate::comms::ToOverlordMessage;
+use crate::globals::GLOBALS;
+use eframe::egui;
+use egui::{Context, RichText, ScrollArea, Ui, Vec2};
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, ui: &mut Ui) {
+    ui.add_space(20.0);
+
+    ui.horizontal(|ui| {
+        ui.heading("Article");
+        ui.label(RichText::new(format!("d: {}", app.long_form_draft.identifier)).weak());
+        if let Some(published_at) = app.long_form_draft.published_at {
+            ui.label(format!("first published {}", super::date(published_at)));
+        }
+    });
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        if ui.button("Save Draft").clicked() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::SaveLongFormDraft(
+                    app.long_form_draft.clone(),
+                ));
+            *GLOBALS.status_message.blocking_write() = "Draft saved.".to_owned();
+        }
+        if GLOBALS.signer.is_ready() {
+            if ui.button("Publish").clicked() {
+                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::PublishLongForm(
+                    app.long_form_draft.clone(),
+                ));
+            }
+        } else {
+            ui.label("Unlock your key to publish.");
+        }
+        ui.separator();
+        ui.checkbox(&mut app.long_form_preview, "Preview");
+        ui.separator();
+        if ui.button("Drafts").clicked() {
+            app.set_page(Page::LongFormList);
+        }
+    });
+
+    ui.add_space(10.0);
+
+    ScrollArea::vertical()
+        .override_scroll_delta(Vec2 {
+            x: 0.0,
+            y: app.current_scroll_offset,
+        })
+        .show(ui, |ui| {
+            if app.long_form_preview {
+                if !app.long_form_draft.image.trim().is_empty() {
+                    if let Some(url) = app.try_check_url(app.long_form_draft.image.trim()) {
+                        ui.horizontal_wrapped(|ui| {
+                            crate::ui::feed::show_image_toggle(app, ui, url);
+                        });
+                    }
+                }
+                ui.heading(app.long_form_draft.title.as_str());
+                if !app.long_form_draft.summary.is_empty() {
+                    ui.label(RichText::new(app.long_form_draft.summary.as_str()).italics());
+                }
+                ui.separator();
+                let content = app.long_form_draft.content.clone();
+                super::markdown::render(app, ui, &content);
+                return;
+            }
+
+            egui::Grid::new("long_form_fields")
+                .num_columns(2)
+                .spacing([10.0, 6.0])
+                .show(ui, |ui| {
+                    ui.label("Title");
+                    ui.add(
+                        text_edit_line!(app, app.long_form_draft.title)
+                            .desired_width(f32::INFINITY),
+                    );
+                    ui.end_row();
+
+                    ui.label("Summary");
+                    ui.add(
+                        text_edit_line!(app, app.long_form_draft.summary)
+                            .desired_width(f32::INFINITY),
+                    );
+                    ui.end_row();
+
+                    ui.label("Image URL");
+                    ui.add(
+                        text_edit_line!(app, app.long_form_draft.image)
+                            .desired_width(f32::INFINITY),
+                    );
+                    ui.end_row();
+
+                    ui.label("Hashtags");
+                    ui.add(
+                        text_edit_line!(app, app.long_form_draft.hashtags)
+                            .hint_text("space separated")
+                            .desired_width(f32::INFINITY),
+                    );
+                    ui.end_row();
+                });
+
+            ui.add_space(10.0);
+
+            ui.add(
+                text_edit_multiline!(app, app.long_form_draft.content)
+                    .hint_text("Write your article in markdown")
+                    .code_editor()
+                    .desired_rows(24)
+                    .desired_width(f32::INFINITY),
+            );
+        });
+}
@@ -0,0 +1,493 @@
+use super::GossipUi;
+use eframe::egui;
+use egui::{Margin, RichText, TextStyle, Ui};
+
+// Just enough markdown for reading articles: headings, paragraphs, lists, quotes,
+// code, rules, images and links, with bold, italic and code inline. Anything
+// else shows as the text it is.
+
+#[derive(Debug, PartialEq)]
+enum Inline {
+    Text {
+        text: String,
+        bold: bool,
+        italic: bool,
+    },
+    Code(String),
+    Link {
+        text: String,
+        url: String,
+    },
+    Image {
+        alt: String,
+        url: String,
+    },
+}
+
+pub(super) fn render(app: &mut GossipUi, ui: &mut Ui, markdown: &str) {
+    let body_size = TextStyle::Body.resolve(ui.style()).size;
+
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut code: Option<Vec<&str>> = None;
+
+    for line in markdown.lines() {
+        // Inside a code fence everything is as it is, until the fence closes
+        if let Some(code_lines) = code.as_mut() {
+            if line.trim_start().starts_with("```") {
+                render_code_block(ui, &code_lines.join("\n"));
+                code = None;
+            } else {
+                code_lines.push(line);
+            }
+            continue;
+        }
+
+        let trimmed = line.trim();
+
+        // A paragraph runs until a blank line or some other block
+        if !trimmed.is_empty() && !starts_block(trimmed) {
+            paragraph.push(trimmed);
+            continue;
+        }
+        if !paragraph.is_empty() {
+            render_paragraph(app, ui, &paragraph.join(" "));
+            paragraph.clear();
+        }
+
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        if trimmed.starts_with("```") {
+            code = Some(Vec::new());
+        } else if let Some((level, text)) = heading(trimmed) {
+            let size = match level {
+                1 => body_size * 1.8,
+                2 => body_size * 1.5,
+                3 => body_size * 1.25,
+                _ => body_size * 1.1,
+            };
+            ui.add_space(body_size * 0.6);
+            ui.label(RichText::new(text).size(size).strong());
+            ui.add_space(body_size * 0.3);
+        } else if is_rule(trimmed) {
+            ui.separator();
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            egui::Frame::none()
+                .inner_margin(Margin {
+                    left: 12.0,
+                    right: 0.0,
+                    top: 2.0,
+                    bottom: 2.0,
+                })
+                .show(ui, |ui| {
+                    ui.label(RichText::new(quote.trim()).italics().weak());
+                });
+        } else if let Some((marker, item)) = list_item(trimmed) {
+            ui.horizontal_wrapped(|ui| {
+                ui.add_space(12.0);
+                ui.label(marker);
+                render_inline(app, ui, item);
+            });
+        } else {
+            // an image on a line of its own
+            render_paragraph(app, ui, trimmed);
+        }
+    }
+
+    if let Some(code_lines) = code {
+        render_code_block(ui, &code_lines.join("\n"));
+    }
+    if !paragraph.is_empty() {
+        render_paragraph(app, ui, &paragraph.join(" "));
+    }
+}
+
+fn starts_block(line: &str) -> bool {
+    line.starts_with("```")
+        || heading(line).is_some()
+        || line.starts_with('>')
+        || line.starts_with("![")
+        || is_rule(line)
+        || list_item(line).is_some()
+}
+
+// The level and text of an ATX heading (but not of a line starting with a hashtag)
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let text = line[level..].strip_prefix(' ')?;
+    Some((level, text.trim().trim_end_matches('#').trim()))
+}
+
+fn is_rule(line: &str) -> bool {
+    let line: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    line.len() >= 3
+        && (line.chars().all(|c| c == '-')
+            || line.chars().all(|c| c == '*')
+            || line.chars().all(|c| c == '_'))
+}
+
+// The bullet (or number) to show, and the item text
+fn list_item(line: &str) -> Option<(String, &str)> {
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(item) = line.strip_prefix(bullet) {
+            return Some(("•  ".to_owned(), item));
+        }
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        if let Some(item) = line[digits..].strip_prefix(". ") {
+            return Some((format!("{}.  ", &line[..digits]), item));
+        }
+    }
+    None
+}
+
+fn render_paragraph(app: &mut GossipUi, ui: &mut Ui, text: &str) {
+    ui.horizontal_wrapped(|ui| {
+        render_inline(app, ui, text);
+    });
+    ui.add_space(6.0);
+}
+
+fn render_code_block(ui: &mut Ui, code: &str) {
+    egui::Frame::none()
+        .fill(ui.visuals().code_bg_color)
+        .inner_margin(Margin::same(6.0))
+        .rounding(ui.style().noninteractive().rounding)
+        .show(ui, |ui| {
+            ui.label(RichText::new(code).monospace());
+        });
+    ui.add_space(6.0);
+}
+
+fn render_inline(app: &mut GossipUi, ui: &mut Ui, text: &str) {
+    ui.spacing_mut().item_spacing.x = 0.0;
+
+    for inline in parse_inline(text) {
+        match inline {
+            Inline::Text { text, bold, italic } => render_text(ui, &text, bold, italic),
+            Inline::Code(code) => {
+                ui.code(code);
+            }
+            Inline::Link { text, url } => {
+                ui.hyperlink_to(text, url);
+            }
+            Inline::Image { alt, url } => match app.try_check_url(&url) {
+                Some(url) => crate::ui::feed::show_image_toggle(app, ui, url),
+                None => {
+                    ui.hyperlink_to(if alt.is_empty() { url.clone() } else { alt }, url);
+                }
+            },
+        }
+    }
+}
+
+// Plain text, with any bare web links in it made clickable
+fn render_text(ui: &mut Ui, text: &str, bold: bool, italic: bool) {
+    let styled = |s: &str| {
+        let mut rt = RichText::new(s);
+        if bold {
+            rt = rt.strong();
+        }
+        if italic {
+            rt = rt.italics();
+        }
+        rt
+    };
+
+    let mut rest = text;
+    while let Some(start) = rest.find("https://").or_else(|| rest.find("http://")) {
+        if start > 0 {
+            ui.label(styled(&rest[..start]));
+        }
+        let end = rest[start..]
+            .find(char::is_whitespace)
+            .map(|e| start + e)
+            .unwrap_or(rest.len());
+        let url = rest[start..end].trim_end_matches(&['.', ',', ';', ':', ')'][..]);
+        ui.hyperlink_to(url, url);
+        rest = &rest[start + url.len()..];
+    }
+    if !rest.is_empty() {
+        ui.label(styled(rest));
+    }
+}
+
+fn parse_inline(text: &str) -> Vec<Inline> {
+    let mut output: Vec<Inline> = Vec::new();
+    let mut plain = String::new();
+    let mut bold = false;
+    let mut italic = false;
+    let mut previous: Option<char> = None;
+
+    let flush = |output: &mut Vec<Inline>, plain: &mut String, bold: bool, italic: bool| {
+        if !plain.is_empty() {
+            output.push(Inline::Text {
+                text: std::mem::take(plain),
+                bold,
+                italic,
+            });
+        }
+    };
+
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("**") || rest.starts_with("__") {
+            flush(&mut output, &mut plain, bold, italic);
+            bold = !bold;
+            rest = &rest[2..];
+            previous = None;
+            continue;
+        }
+
+        // Underscores inside words (snake_case) are not emphasis
+        let word_char = |c: Option<char>| c.map(|c| c.is_alphanumeric()).unwrap_or(false);
+        let underscore = if italic {
+            !word_char(rest[c.len_utf8()..].chars().next())
+        } else {
+            !word_char(previous)
+        };
+        if c == '*' || (c == '_' && underscore) {
+            flush(&mut output, &mut plain, bold, italic);
+            italic = !italic;
+            rest = &rest[1..];
+            previous = None;
+            continue;
+        }
+
+        if c == '`' {
+            if let Some(end) = rest[1..].find('`') {
+                flush(&mut output, &mut plain, bold, italic);
+                output.push(Inline::Code(rest[1..1 + end].to_owned()));
+                rest = &rest[end + 2..];
+                previous = None;
+                continue;
+            }
+        }
+
+        if c == '[' || rest.starts_with("![") {
+            let image = c == '!';
+            let start = if image { 2 } else { 1 };
+            if let Some((label, url, len)) = parse_link(&rest[start..]) {
+                flush(&mut output, &mut plain, bold, italic);
+                output.push(if image {
+                    Inline::Image { alt: label, url }
+                } else {
+                    Inline::Link { text: label, url }
+                });
+                rest = &rest[start + len..];
+                previous = None;
+                continue;
+            }
+        }
+
+        plain.push(c);
+        previous = Some(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    flush(&mut output, &mut plain, bold, italic);
+    output
+}
+
+// From just after the opening bracket of `[label](url)`: the label, the url, and
+// how much of the text that was
+fn parse_link(text: &str) -> Option<(String, String, usize)> {
+    let label_end = text.find(']')?;
+    if !text[label_end..].starts_with("](") {
+        return None;
+    }
+    let url_start = label_end + 2;
+    let url_end = url_start + text[url_start..].find(')')?;
+    let label = &text[..label_end];
+    // Links may carry a title after the url
+    let url = text[url_start..url_end].split_whitespace().next()?;
+    Some((label.to_owned(), url.to_owned(), url_end + 1))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn text(text: &str, bold: bool, italic: bool) -> Inline {
+        Inline::Text {
+            text: text.to_owned(),
+            bold,
+            italic,
+        }
+    }
+
+    #[test]
+    fn test_parse_inline_emphasis() {
+        assert_eq!(
+            parse_inline("plain **bold** and *italic*"),
+            vec![
+                text("plain ", false, false),
+                text("bold", true, false),
+                text(" and ", false, false),
+                text("italic", false, true),
+            ]
+        );
+        assert_eq!(
+            parse_inline("**bold *both* bold**"),
+            vec![
+                text("bold ", true, false),
+                text("both", true, true),
+                text(" bold", true, false),
+            ]
+        );
+        assert_eq!(
+            parse_inline("__bold _both___"),
+            vec![text("bold ", true, false), text("both", true, true)]
+        );
+    }
+
+    #[test]
+    fn test_parse_inline_snake_case() {
+        assert_eq!(
+            parse_inline("call snake_case_name()"),
+            vec![text("call snake_case_name()", false, false)]
+        );
+        assert_eq!(
+            parse_inline("_see snake_case here_ now"),
+            vec![
+                text("see snake_case here", false, true),
+                text(" now", false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_inline_code() {
+        assert_eq!(
+            parse_inline("run `cargo **build**` now"),
+            vec![
+                text("run ", false, false),
+                Inline::Code("cargo **build**".to_owned()),
+                text(" now", false, false),
+            ]
+        );
+        assert_eq!(
+            parse_inline("an `unclosed tick"),
+            vec![text("an `unclosed tick", false, false)]
+        );
+    }
+
+    #[test]
+    fn test_parse_inline_links_and_images() {
+        assert_eq!(
+            parse_inline("see [the site](https://example.com \"Title\") and ![a cat](https://example.com/cat.png)"),
+            vec![
+                text("see ", false, false),
+                Inline::Link {
+                    text: "the site".to_owned(),
+                    url: "https://example.com".to_owned(),
+                },
+                text(" and ", false, false),
+                Inline::Image {
+                    alt: "a cat".to_owned(),
+                    url: "https://example.com/cat.png".to_owned(),
+                },
+            ]
+        );
+        assert_eq!(
+            parse_inline("[not a link] and [a](https://a.example)"),
+            vec![
+                text("[not a link] and ", false, false),
+                Inline::Link {
+                    text: "a".to_owned(),
+                    url: "https://a.example".to_owned(),
+                },
+            ]
+        );
+        assert_eq!(
+            parse_inline("an [unclosed bracket and ![unclosed image"),
+            vec![text(
+                "an [unclosed bracket and ![unclosed image",
+                false,
+                false
+            )]
+        );
+        assert_eq!(
+            parse_inline("[label](https://unclosed.example"),
+            vec![text("[label](https://unclosed.example", false, false)]
+        );
+    }
+
+    #[test]
+    fn test_parse_inline_multibyte() {
+        assert_eq!(
+            parse_inline("über**straße**_naïve_ `日本`"),
+            vec![
+                text("über", false, false),
+                text("straße", true, false),
+                text("naïve", false, true),
+                text(" ", false, false),
+                Inline::Code("日本".to_owned()),
+            ]
+        );
+        assert_eq!(
+            parse_inline("ü_ber_ [ñ](https://ñ.example)"),
+            vec![
+                text("ü_ber_ ", false, false),
+                Inline::Link {
+                    text: "ñ".to_owned(),
+                    url: "https://ñ.example".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_link() {
+        assert_eq!(
+            parse_link("label](https://example.com) rest"),
+            Some(("label".to_owned(), "https://example.com".to_owned(), 27))
+        );
+        assert_eq!(
+            parse_link("label](https://example.com 'Title')"),
+            Some(("label".to_owned(), "https://example.com".to_owned(), 35))
+        );
+        assert_eq!(parse_link("label] (https://example.com)"), None);
+        assert_eq!(parse_link("label](https://example.com"), None);
+        assert_eq!(parse_link("label](   )"), None);
+    }
+
+    #[test]
+    fn test_heading() {
+        assert_eq!(heading("# Title"), Some((1, "Title")));
+        assert_eq!(heading("### Closed ###"), Some((3, "Closed")));
+        assert_eq!(heading("###### Six"), Some((6, "Six")));
+        assert_eq!(heading("####### Seven"), None);
+        assert_eq!(heading("#hashtag"), None);
+        assert_eq!(heading("## Ünïcödé"), Some((2, "Ünïcödé")));
+    }
+
+    #[test]
+    fn test_list_item() {
+        assert_eq!(list_item("- one"), Some(("•  ".to_owned(), "one")));
+        assert_eq!(list_item("* two"), Some(("•  ".to_owned(), "two")));
+        assert_eq!(list_item("+ three"), Some(("•  ".to_owned(), "three")));
+        assert_eq!(
+            list_item("12. twelve"),
+            Some(("12.  ".to_owned(), "twelve"))
+        );
+        assert_eq!(list_item("-not"), None);
+        assert_eq!(list_item("1.5 million"), None);
+        assert_eq!(list_item("**bold**"), None);
+    }
+
+    #[test]
+    fn test_is_rule() {
+        assert!(is_rule("---"));
+        assert!(is_rule("* * *"));
+        assert!(is_rule("_____"));
+        assert!(!is_rule("--"));
+        assert!(!is_rule("-*-"));
+        assert!(!is_rule("- item"));
+    }
+}
@@ -0,0 +1,104 @@
+use super::{GossipUi, Page};
+use crate::comms::ToOverlordMessage;
+use crate::globals::GLOBALS;
+use crate::long_form::LongForm;
+use eframe::egui;
+use egui::{Context, RichText, ScrollArea, Ui, Vec2};
+
+mod editor;
+mod markdown;
+mod reader;
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    match app.page.clone() {
+        Page::LongFormList => render_drafts(app, ui),
+        Page::LongFormRead(id) => reader::update(app, ctx, ui, id),
+        Page::LongFormEditor => editor::update(app, ctx, ui),
+        _ => {}
+    }
+}
+
+/// The date (not the time) of a unix timestamp
+pub(super) fn date(timestamp: i64) -> String {
+    match time::OffsetDateTime::from_unix_timestamp(timestamp) {
+        Ok(stamp) => format!("{}", stamp.date()),
+        Err(_) => "?".to_owned(),
+    }
+}
+
+fn render_drafts(app: &mut GossipUi, ui: &mut Ui) {
+    ui.add_space(30.0);
+
+    ui.heading("Articles");
+    ui.label(
+        "Long-form articles (NIP-23) are written in markdown. Drafts stay on this \
+         computer until you publish them, and stay here after, so you can edit and \
+         publish them again.",
+    );
+    ui.add_space(10.0);
+
+    if GLOBALS.signer.public_key().is_none() {
+        ui.label("You need to set up your identity to write articles.");
+        return;
+    }
+
+    if ui.button("New Article").clicked() {
+        app.long_form_draft = LongForm::new_draft();
+        app.long_form_preview = false;
+        app.set_page(Page::LongFormEditor);
+    }
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    let drafts = GLOBALS.long_form.drafts();
+    if drafts.is_empty() {
+        ui.label("You have no drafts.");
+        return;
+    }
+
+    ScrollArea::vertical()
+        .override_scroll_delta(Vec2 {
+            x: 0.0,
+            y: app.current_scroll_offset,
+        })
+        .show(ui, |ui| {
+            for draft in drafts {
+                ui.horizontal(|ui| {
+                    let title = if draft.title.trim().is_empty() {
+                        "Untitled"
+                    } else {
+                        draft.title.as_str()
+                    };
+                    ui.heading(title);
+                    match draft.published_at {
+                        Some(published_at) => ui.label(format!("published {}", date(published_at))),
+                        None => ui.label(RichText::new("draft").italics()),
+                    };
+                    ui.label(
+                        RichText::new(format!("last edited {}", date(draft.last_edit))).weak(),
+                    );
+                });
+                if !draft.summary.is_empty() {
+                    ui.label(draft.summary.as_str());
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Edit").clicked() {
+                        app.long_form_draft = draft.clone();
+                        app.long_form_preview = false;
+                        app.set_page(Page::LongFormEditor);
+                    }
+                    if ui.button("Delete Draft").clicked() {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::DeleteLongFormDraft(
+                                draft.identifier.clone(),
+                            ));
+                    }
+                });
+                ui.add_space(10.0);
+                ui.separator();
+            }
+        });
+}
@@ -0,0 +1,98 @@
+use super::{GossipUi, Page};
+use crate::feed::FeedKind;
+use crate::globals::GLOBALS;
+use crate::long_form::LongFormMeta;
+use crate::people::DbPerson;
+use eframe::egui;
+use egui::{Context, RichText, ScrollArea, Ui, Vec2};
+use nostr_types::{EventKind, Id, PublicKeyHex};
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, ui: &mut Ui, id: Id) {
+    let event = match GLOBALS.events.get(&id) {
+        Some(event) if event.kind == EventKind::LongFormContent => event,
+        _ => {
+            ui.add_space(30.0);
+            ui.label("That article is not loaded.");
+            return;
+        }
+    };
+    let meta = LongFormMeta::from_event(&event);
+    let author: PublicKeyHex = event.pubkey.into();
+    let ours = GLOBALS.signer.public_key() == Some(event.pubkey);
+
+    ScrollArea::vertical()
+        .override_scroll_delta(Vec2 {
+            x: 0.0,
+            y: app.current_scroll_offset,
+        })
+        .show(ui, |ui| {
+            ui.add_space(20.0);
+
+            if let Some(url) = app.try_check_url(meta.image.trim()) {
+                ui.horizontal_wrapped(|ui| {
+                    crate::ui::feed::show_image_toggle(app, ui, url);
+                });
+            }
+
+            let title = if meta.title.trim().is_empty() {
+                "Untitled"
+            } else {
+                meta.title.as_str()
+            };
+            ui.heading(title);
+
+            ui.horizontal_wrapped(|ui| {
+                let person = match GLOBALS.people.get(&author) {
+                    Some(p) => p,
+                    None => DbPerson::new(author.clone()),
+                };
+                ui.label("by ");
+                if ui
+                    .link(GossipUi::display_name_from_dbperson(&person))
+                    .clicked()
+                {
+                    app.set_page(Page::Person(author.clone()));
+                }
+                let published_at = meta.published_at(&event);
+                ui.label(format!(", {}", super::date(published_at)));
+                if event.created_at.0 > published_at {
+                    ui.label(
+                        RichText::new(format!(" (updated {})", super::date(event.created_at.0)))
+                            .weak(),
+                    );
+                }
+            });
+
+            if !meta.hashtags.is_empty() {
+                ui.horizontal_wrapped(|ui| {
+                    for hashtag in meta.hashtags.iter() {
+                        if ui.link(format!("#{}", hashtag)).clicked() {
+                            app.set_page(Page::Feed(FeedKind::Hashtag(hashtag.to_lowercase())));
+                        }
+                    }
+                });
+            }
+
+            if !meta.summary.is_empty() {
+                ui.add_space(8.0);
+                ui.label(RichText::new(meta.summary.as_str()).italics());
+            }
+
+            if ours && ui.button("Edit").clicked() {
+                app.long_form_draft = match GLOBALS.long_form.get(&meta.identifier) {
+                    Some(draft) => draft,
+                    None => meta.to_draft(&event),
+                };
+                app.long_form_preview = false;
+                app.set_page(Page::LongFormEditor);
+            }
+
+            ui.add_space(8.0);
+            ui.separator();
+            ui.add_space(8.0);
+
+            super::markdown::render(app, ui, &event.content);
+
+            ui.add_space(30.0);
+        });
+}
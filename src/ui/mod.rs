@@ -13,8 +13,10 @@ macro_rules! text_edit_multiline {
 }
 
 mod components;
+mod dm;
 mod feed;
 mod help;
+mod longform;
 mod people;
 mod relays;
 mod search;
@@ -25,6 +27,7 @@ mod you;
 
 use crate::about::About;
 use crate::comms::ToOverlordMessage;
+use crate::db::DbLongFormDraft;
 use crate::error::Error;
 use crate::feed::FeedKind;
 use crate::globals::GLOBALS;
@@ -100,12 +103,23 @@ enum Page {
     PeopleList,
     PeopleFollow,
     PeopleMuted,
+    PeopleLists,
     Person(PublicKeyHex),
+    DmChatList,
+    DmChat(PublicKeyHex),
+    LongFormList,
+    LongFormRead(Id),
+    LongFormEditor,
     YourKeys,
     YourMetadata,
     YourDelegation,
+    YourIdentities,
+    YourArchive,
     RelaysLive,
     RelaysAll,
+    RelaysOutbox,
+    RelaysHealth,
+    RelaysNotices,
     Search,
     Settings,
     HelpHelp,
@@ -185,9 +199,16 @@ struct GossipUi {
     // Post rendering
     render_raw: Option<Id>,
     render_qr: Option<Id>,
+    zapping: Option<Id>,
+    zap_sats: String,
+    zap_comment: String,
     approved: HashSet<Id>, // content warning posts
     height: HashMap<Id, f32>,
 
+    // Long-form article being edited
+    long_form_draft: DbLongFormDraft,
+    long_form_preview: bool,
+
     // Person page rendering ('npub', 'nprofile', or 'lud06')
     person_qr: Option<&'static str>,
     setting_active_person: bool,
@@ -229,6 +250,19 @@ struct GossipUi {
     include_content_warning: bool,
     content_warning: String,
     replying_to: Option<Id>,
+    post_relays_tagged: Option<Vec<PublicKeyHex>>,
+    post_relays_excluded: HashSet<RelayUrl>,
+    post_relays_added: Vec<RelayUrl>,
+    post_relay_to_add: String,
+
+    // User entry: direct messages
+    dm_drafts: HashMap<PublicKeyHex, String>,
+    dm_new_recipient: String,
+    dm_plaintexts: HashMap<Id, String>,
+
+    // User entry: identities
+    new_identity_name: String,
+    active_identity: Option<String>,
 
     // User entry: metadata
     editing_metadata: bool,
@@ -246,14 +280,40 @@ struct GossipUi {
     password: String,
     password2: String,
     password3: String,
+    wallet_uri: String,
     delete_confirm: bool,
     new_metadata_fieldname: String,
     import_priv: String,
     import_pub: String,
+    remote_signer_uri: String,
+    nostrconnect_relay: String,
     new_relay_url: String,
     show_hidden_relays: bool,
+    relay_health_url: Option<RelayUrl>,
+    relay_health_days: i64,
+    relay_health_requested: Option<(RelayUrl, i64)>,
+    relay_highlight: Option<RelayUrl>,
+    relay_notices_highlighted_only: bool,
     search: String,
+    search_author: String,
+    search_kind: String,
+    search_hashtag: String,
+    search_since: String,
+    search_until: String,
     entering_search_page: bool,
+    retention_rule_kind: String,
+    retention_rule_author: String,
+    retention_rule_days: String,
+    archive_mine: bool,
+    archive_author: String,
+    archive_kind: String,
+    archive_since: String,
+    archive_until: String,
+    archive_export_path: String,
+    archive_import_path: String,
+    mute_word: String,
+    mute_hashtag: String,
+    new_person_list: String,
 
     // Collapsed threads
     collapsed: Vec<Id>,
@@ -264,6 +324,7 @@ impl Drop for GossipUi {
         self.password.zeroize();
         self.password2.zeroize();
         self.password3.zeroize();
+        self.wallet_uri.zeroize();
     }
 }
 
@@ -392,8 +453,13 @@ impl GossipUi {
             notes: Notes::new(),
             render_raw: None,
             render_qr: None,
+            zapping: None,
+            zap_sats: "21".to_owned(),
+            zap_comment: "".to_owned(),
             approved: HashSet::new(),
             height: HashMap::new(),
+            long_form_draft: DbLongFormDraft::default(),
+            long_form_preview: false,
             person_qr: None,
             setting_active_person: false,
             page: start_page,
@@ -429,6 +495,15 @@ impl GossipUi {
             include_content_warning: false,
             content_warning: "".to_owned(),
             replying_to: None,
+            post_relays_tagged: None,
+            post_relays_excluded: HashSet::new(),
+            post_relays_added: Vec::new(),
+            post_relay_to_add: "".to_owned(),
+            dm_drafts: HashMap::new(),
+            dm_new_recipient: "".to_owned(),
+            dm_plaintexts: HashMap::new(),
+            new_identity_name: "".to_owned(),
+            active_identity: None,
             editing_metadata: false,
             metadata: Metadata::new(),
             delegatee_tag_str: "".to_owned(),
@@ -440,14 +515,40 @@ impl GossipUi {
             password: "".to_owned(),
             password2: "".to_owned(),
             password3: "".to_owned(),
+            wallet_uri: "".to_owned(),
             delete_confirm: false,
             new_metadata_fieldname: String::new(),
             import_priv: "".to_owned(),
             import_pub: "".to_owned(),
+            remote_signer_uri: "".to_owned(),
+            nostrconnect_relay: "".to_owned(),
             new_relay_url: "".to_owned(),
             show_hidden_relays: false,
+            relay_health_url: None,
+            relay_health_days: 7,
+            relay_health_requested: None,
+            relay_highlight: None,
+            relay_notices_highlighted_only: false,
             search: "".to_owned(),
+            search_author: "".to_owned(),
+            search_kind: "".to_owned(),
+            search_hashtag: "".to_owned(),
+            search_since: "".to_owned(),
+            search_until: "".to_owned(),
             entering_search_page: false,
+            retention_rule_kind: "".to_owned(),
+            retention_rule_author: "".to_owned(),
+            retention_rule_days: "".to_owned(),
+            archive_mine: true,
+            archive_author: "".to_owned(),
+            archive_kind: "".to_owned(),
+            archive_since: "".to_owned(),
+            archive_until: "".to_owned(),
+            archive_export_path: "".to_owned(),
+            archive_import_path: "".to_owned(),
+            mute_word: "".to_owned(),
+            mute_hashtag: "".to_owned(),
+            new_person_list: "".to_owned(),
             collapsed: vec![],
         }
     }
@@ -463,6 +564,7 @@ impl GossipUi {
             self.qr_codes.clear();
             self.render_qr = None;
             self.person_qr = None;
+            self.zapping = None;
         }
     }
 
@@ -479,6 +581,7 @@ impl GossipUi {
                 self.qr_codes.clear();
                 self.render_qr = None;
                 self.person_qr = None;
+                self.zapping = None;
 
                 self.page = page;
             }
@@ -511,6 +614,15 @@ impl GossipUi {
             Page::Feed(FeedKind::Person(pubkey)) => {
                 GLOBALS.feed.set_feed_to_person(pubkey.to_owned());
             }
+            Page::Feed(FeedKind::Hashtag(hashtag)) => {
+                GLOBALS.feed.set_feed_to_hashtag(hashtag.to_owned());
+            }
+            Page::Feed(FeedKind::Bookmarks) => {
+                GLOBALS.feed.set_feed_to_bookmarks();
+            }
+            Page::Feed(FeedKind::List(name)) => {
+                GLOBALS.feed.set_feed_to_list(name.to_owned());
+            }
             Page::Search => {
                 self.entering_search_page = true;
             }
@@ -530,6 +642,10 @@ impl GossipUi {
         self.replying_to = None;
         self.include_content_warning = false;
         self.content_warning = "".to_owned();
+        self.post_relays_tagged = None;
+        self.post_relays_excluded.clear();
+        self.post_relays_added.clear();
+        self.post_relay_to_add = "".to_owned();
     }
 }
 
@@ -552,6 +668,30 @@ impl eframe::App for GossipUi {
             frame.close();
         }
 
+        // Drop everything we cached about the previous identity
+        let active_identity = GLOBALS
+            .identities
+            .read()
+            .iter()
+            .find(|i| i.active)
+            .map(|i| i.name.clone());
+        if active_identity != self.active_identity {
+            if self.active_identity.is_some() {
+                self.notes.cache_invalidate_all();
+                self.dm_plaintexts.clear();
+                self.dm_drafts.clear();
+                self.editing_metadata = false;
+                if matches!(self.page, Page::DmChat(_)) {
+                    self.set_page(Page::DmChatList);
+                }
+                self.long_form_draft = DbLongFormDraft::default();
+                if self.page == Page::LongFormEditor {
+                    self.set_page(Page::LongFormList);
+                }
+            }
+            self.active_identity = active_identity;
+        }
+
         // Smooth Scrolling
         {
             // Add the amount of scroll requested to the future
@@ -612,6 +752,7 @@ impl eframe::App for GossipUi {
                         self.page == Page::PeopleList
                             || self.page == Page::PeopleFollow
                             || self.page == Page::PeopleMuted
+                            || self.page == Page::PeopleLists
                             || matches!(self.page, Page::Person(_)),
                         "People",
                     ))
@@ -620,11 +761,36 @@ impl eframe::App for GossipUi {
                     self.set_page(Page::PeopleList);
                 }
                 ui.separator();
+                if ui
+                    .add(SelectableLabel::new(
+                        matches!(self.page, Page::DmChatList | Page::DmChat(_)),
+                        Self::dm_menu_label(),
+                    ))
+                    .clicked()
+                {
+                    self.set_page(Page::DmChatList);
+                }
+                ui.separator();
+                if ui
+                    .add(SelectableLabel::new(
+                        matches!(
+                            self.page,
+                            Page::LongFormList | Page::LongFormRead(_) | Page::LongFormEditor
+                        ),
+                        "Articles",
+                    ))
+                    .clicked()
+                {
+                    self.set_page(Page::LongFormList);
+                }
+                ui.separator();
                 if ui
                     .add(SelectableLabel::new(
                         self.page == Page::YourKeys
                             || self.page == Page::YourMetadata
-                            || self.page == Page::YourDelegation,
+                            || self.page == Page::YourDelegation
+                            || self.page == Page::YourIdentities
+                            || self.page == Page::YourArchive,
                         "You",
                     ))
                     .clicked()
@@ -634,7 +800,11 @@ impl eframe::App for GossipUi {
                 ui.separator();
                 if ui
                     .add(SelectableLabel::new(
-                        self.page == Page::RelaysLive || self.page == Page::RelaysAll,
+                        self.page == Page::RelaysLive
+                            || self.page == Page::RelaysAll
+                            || self.page == Page::RelaysOutbox
+                            || self.page == Page::RelaysHealth
+                            || self.page == Page::RelaysNotices,
                         "Relays",
                     ))
                     .clicked()
@@ -747,6 +917,55 @@ impl eframe::App for GossipUi {
                     {
                         self.set_page(Page::Feed(FeedKind::Inbox(self.inbox_include_indirect)));
                     }
+                    if self.add_selected_label(
+                            ui,
+                            matches!(self.page, Page::DmChatList | Page::DmChat(_)),
+                            &Self::dm_menu_label(),
+                        )
+                        .clicked()
+                    {
+                        self.set_page(Page::DmChatList);
+                    }
+                    if self.add_selected_label(
+                            ui,
+                            matches!(self.page, Page::LongFormList | Page::LongFormRead(_) | Page::LongFormEditor),
+                            "Articles",
+                        )
+                        .clicked()
+                    {
+                        self.set_page(Page::LongFormList);
+                    }
+                    if self.add_selected_label(
+                            ui,
+                            self.page == Page::Feed(FeedKind::Bookmarks),
+                            "Bookmarks",
+                        )
+                        .clicked()
+                    {
+                        self.set_page(Page::Feed(FeedKind::Bookmarks));
+                    }
+                    for name in GLOBALS.person_lists.names() {
+                        if self.add_selected_label(
+                                ui,
+                                matches!(&self.page, Page::Feed(FeedKind::List(n)) if *n == name),
+                                &name,
+                            )
+                            .clicked()
+                        {
+                            self.set_page(Page::Feed(FeedKind::List(name)));
+                        }
+                    }
+                    for hashtag in self.settings.pinned_hashtags.clone() {
+                        if self.add_selected_label(
+                                ui,
+                                matches!(&self.page, Page::Feed(FeedKind::Hashtag(h)) if *h == hashtag),
+                                &format!("#{}", hashtag),
+                            )
+                            .clicked()
+                        {
+                            self.set_page(Page::Feed(FeedKind::Hashtag(hashtag)));
+                        }
+                    }
 
                     ui.add_space(8.0);
 
@@ -758,6 +977,7 @@ impl eframe::App for GossipUi {
                                 self.add_menu_item_page(ui, Page::PeopleList, "Followed");
                                 self.add_menu_item_page(ui, Page::PeopleFollow, "Follow new");
                                 self.add_menu_item_page(ui, Page::PeopleMuted, "Muted");
+                                self.add_menu_item_page(ui, Page::PeopleLists, "Lists");
                             });
                         self.after_openable_menu(ui, &submenu);
                     }
@@ -768,6 +988,13 @@ impl eframe::App for GossipUi {
                         submenu.show_body_indented(&header_response, ui, |ui| {
                                 self.add_menu_item_page(ui, Page::RelaysLive, "Live");
                                 self.add_menu_item_page(ui, Page::RelaysAll, "Configure");
+                                self.add_menu_item_page(ui, Page::RelaysOutbox, "Outbox");
+                                self.add_menu_item_page(ui, Page::RelaysHealth, "Health");
+                                self.add_menu_item_page(
+                                    ui,
+                                    Page::RelaysNotices,
+                                    &Self::relay_notices_menu_label(),
+                                );
                             });
                         self.after_openable_menu(ui, &submenu);
                     }
@@ -779,6 +1006,23 @@ impl eframe::App for GossipUi {
                                 self.add_menu_item_page(ui, Page::YourMetadata, "Profile");
                                 self.add_menu_item_page(ui, Page::YourKeys, "Keys");
                                 self.add_menu_item_page(ui, Page::YourDelegation, "Delegation");
+                                self.add_menu_item_page(ui, Page::YourIdentities, "Identities");
+                                self.add_menu_item_page(ui, Page::YourArchive, "Archive");
+                                let identities = GLOBALS.identities.read().clone();
+                                if identities.len() > 1 {
+                                    ui.add_space(6.0);
+                                    for identity in identities.iter() {
+                                        if self.add_selected_label(ui, identity.active, &identity.name)
+                                            .on_hover_text("Switch to this identity")
+                                            .clicked()
+                                            && !identity.active
+                                        {
+                                            let _ = GLOBALS.to_overlord.send(
+                                                ToOverlordMessage::SwitchIdentity(identity.name.clone()),
+                                            );
+                                        }
+                                    }
+                                }
                             });
                         self.after_openable_menu(ui, &submenu);
                     }
@@ -958,13 +1202,25 @@ impl eframe::App for GossipUi {
             })
             .show(ctx, |ui| match self.page {
                 Page::Feed(_) => feed::update(self, ctx, frame, ui),
-                Page::PeopleList | Page::PeopleFollow | Page::PeopleMuted | Page::Person(_) => {
-                    people::update(self, ctx, frame, ui)
+                Page::PeopleList
+                | Page::PeopleFollow
+                | Page::PeopleMuted
+                | Page::PeopleLists
+                | Page::Person(_) => people::update(self, ctx, frame, ui),
+                Page::DmChatList | Page::DmChat(_) => dm::update(self, ctx, frame, ui),
+                Page::LongFormList | Page::LongFormRead(_) | Page::LongFormEditor => {
+                    longform::update(self, ctx, frame, ui)
                 }
-                Page::YourKeys | Page::YourMetadata | Page::YourDelegation => {
-                    you::update(self, ctx, frame, ui)
-                }
-                Page::RelaysLive | Page::RelaysAll => relays::update(self, ctx, frame, ui),
+                Page::YourKeys
+                | Page::YourMetadata
+                | Page::YourDelegation
+                | Page::YourIdentities
+                | Page::YourArchive => you::update(self, ctx, frame, ui),
+                Page::RelaysLive
+                | Page::RelaysAll
+                | Page::RelaysOutbox
+                | Page::RelaysHealth
+                | Page::RelaysNotices => relays::update(self, ctx, frame, ui),
                 Page::Search => search::update(self, ctx, frame, ui),
                 Page::Settings => settings::update(self, ctx, frame, ui),
                 Page::HelpHelp | Page::HelpStats | Page::HelpAbout => {
@@ -975,6 +1231,31 @@ impl eframe::App for GossipUi {
 }
 
 impl GossipUi {
+    /// The menu label for direct messages, with the unread count
+    fn dm_menu_label() -> String {
+        let unread: usize = GLOBALS
+            .feed
+            .get_dm_channels()
+            .iter()
+            .map(|c| c.unread)
+            .sum();
+        if unread > 0 {
+            format!("Messages ({})", unread)
+        } else {
+            "Messages".to_owned()
+        }
+    }
+
+    /// The menu label for relay notices, with the unread count
+    fn relay_notices_menu_label() -> String {
+        let unread = GLOBALS.relay_notices.unread();
+        if unread > 0 {
+            format!("Notices ({})", unread)
+        } else {
+            "Notices".to_owned()
+        }
+    }
+
     /// A short rendering of a `PublicKey`
     pub fn pubkey_short(pk: &PublicKey) -> String {
         let npub = pk.as_bech32_string();
@@ -1054,6 +1335,9 @@ impl GossipUi {
                 if ui.button("View Their Posts").clicked() {
                     app.set_page(Page::Feed(FeedKind::Person(person.pubkey.clone())));
                 }
+                if ui.button("Send Direct Message").clicked() {
+                    app.set_page(Page::DmChat(person.pubkey.clone()));
+                }
             });
 
             if person.followed > 0 {
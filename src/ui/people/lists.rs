@@ -0,0 +1,90 @@
+use super::{GossipUi, Page};
+use crate::comms::ToOverlordMessage;
+use crate::feed::FeedKind;
+use crate::globals::GLOBALS;
+use crate::people::DbPerson;
+use eframe::egui;
+use egui::{Context, RichText, ScrollArea, Ui, Vec2};
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.add_space(30.0);
+
+    ui.heading("Lists");
+    ui.label(
+        "Each list is its own feed, and is published as a NIP-51 follow set. Notes of \
+         people on a list are fetched along with those of the people you follow.",
+    );
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.add(text_edit_line!(app, app.new_person_list).hint_text("new list name"));
+        if ui.button("Create List").clicked() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::CreatePersonList(std::mem::take(
+                    &mut app.new_person_list,
+                )));
+        }
+    });
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    ScrollArea::vertical()
+        .override_scroll_delta(Vec2 {
+            x: 0.0,
+            y: app.current_scroll_offset,
+        })
+        .show(ui, |ui| {
+            for name in GLOBALS.person_lists.names() {
+                let members = GLOBALS.person_lists.members(&name);
+
+                ui.horizontal(|ui| {
+                    ui.heading(name.as_str());
+                    ui.label(format!("({} people)", members.len()));
+                    if ui.button("View Feed").clicked() {
+                        app.set_page(Page::Feed(FeedKind::List(name.clone())));
+                    }
+                    if ui.button("Delete").clicked() {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::DeletePersonList(name.clone()));
+                    }
+                });
+
+                if members.is_empty() {
+                    ui.label("Add people to this list from their page.");
+                }
+
+                for pubkey in members {
+                    ui.horizontal(|ui| {
+                        ui.add_space(20.0);
+                        let person = match GLOBALS.people.get(&pubkey) {
+                            Some(p) => p,
+                            None => DbPerson::new(pubkey.clone()),
+                        };
+                        if ui
+                            .link(GossipUi::display_name_from_dbperson(&person))
+                            .clicked()
+                        {
+                            app.set_page(Page::Person(pubkey.clone()));
+                        }
+                        ui.label(RichText::new(GossipUi::pubkeyhex_convert_short(&pubkey)).weak());
+                        if ui.small_button("Remove").clicked() {
+                            let _ =
+                                GLOBALS
+                                    .to_overlord
+                                    .send(ToOverlordMessage::RemoveFromPersonList(
+                                        name.clone(),
+                                        pubkey,
+                                    ));
+                        }
+                    });
+                }
+
+                ui.add_space(10.0);
+                ui.separator();
+            }
+        });
+}
@@ -8,6 +8,7 @@ use egui::{Context, Image, RichText, ScrollArea, Sense, Ui, Vec2};
 use std::sync::atomic::Ordering;
 
 mod follow;
+mod lists;
 mod muted;
 mod person;
 
@@ -50,6 +51,16 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                 app.set_page(Page::PeopleMuted);
             }
             ui.separator();
+            if ui
+                .add(egui::SelectableLabel::new(
+                    app.page == Page::PeopleLists,
+                    "Lists",
+                ))
+                .clicked()
+            {
+                app.set_page(Page::PeopleLists);
+            }
+            ui.separator();
             if let Some(person) = &maybe_person {
                 if ui
                     .add(egui::SelectableLabel::new(
@@ -231,6 +242,8 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         follow::update(app, ctx, _frame, ui);
     } else if app.page == Page::PeopleMuted {
         muted::update(app, ctx, _frame, ui);
+    } else if app.page == Page::PeopleLists {
+        lists::update(app, ctx, _frame, ui);
     } else if matches!(app.page, Page::Person(_)) {
         person::update(app, ctx, _frame, ui);
     }
@@ -1,4 +1,6 @@
 use super::{GossipUi, Page};
+use crate::comms::ToOverlordMessage;
+use crate::db::MuteKind;
 use crate::globals::GLOBALS;
 use crate::people::DbPerson;
 use crate::AVATAR_SIZE_F32;
@@ -7,7 +9,19 @@ use egui::{Context, Image, RichText, ScrollArea, Sense, Ui, Vec2};
 use std::sync::atomic::Ordering;
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
-    ui.add_space(30.0);
+    ui.add_space(12.0);
+
+    mute_list(app, ui);
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    mute_words_hashtags_threads(app, ui);
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
 
     let people: Vec<DbPerson> = GLOBALS
         .people
@@ -65,3 +79,143 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
             }
         });
 }
+
+fn mute_list(app: &mut GossipUi, ui: &mut Ui) {
+    let asof = GLOBALS.mutes.last_mute_list_asof.load(Ordering::Relaxed);
+    let size = GLOBALS.mutes.last_mute_list_size.load(Ordering::Relaxed);
+    ui.label(
+        RichText::new(format!(
+            "REMOTE: {} (public entries={})",
+            format_stamp(asof),
+            size
+        ))
+        .size(15.0),
+    )
+    .on_hover_text("This is the latest mute list fetched from relays");
+
+    if GLOBALS.mutes.remote_is_newer() {
+        ui.add_space(6.0);
+        ui.label(
+            RichText::new(
+                "Your mute list on relays is newer than your mutes here. \
+                 Merge it in, or overwrite your mutes here with it?",
+            )
+            .color(app.settings.theme.notice_marker_text_color()),
+        );
+    }
+
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.add_space(30.0);
+
+        if ui.button("↓ Overwrite ↓").clicked() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::UpdateMuteList(false));
+        }
+        if ui.button("↓ Merge ↓").clicked() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::UpdateMuteList(true));
+        }
+
+        if GLOBALS.signer.is_ready() {
+            if ui.button("↑ Publish ↑").clicked() {
+                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::PushMuteList);
+            }
+        }
+
+        let mut private = app.settings.mute_list_private;
+        if ui
+            .checkbox(&mut private, "Publish privately (encrypted)")
+            .on_hover_text(
+                "Only you can read a private mute list. A public one can be reused by others.",
+            )
+            .changed()
+        {
+            app.settings.mute_list_private = private;
+            GLOBALS.settings.write().mute_list_private = private;
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::SaveSettings);
+        }
+    });
+
+    ui.add_space(10.0);
+
+    let edit = GLOBALS.mutes.last_mute_list_edit.load(Ordering::Relaxed);
+    ui.label(RichText::new(format!("LOCAL: {}", format_stamp(edit))).size(15.0))
+        .on_hover_text("This is when your mutes here were last changed");
+}
+
+fn mute_words_hashtags_threads(app: &mut GossipUi, ui: &mut Ui) {
+    ui.heading("Muted Words");
+    ui.add_space(6.0);
+    ui.horizontal_wrapped(|ui| {
+        for word in GLOBALS.mutes.get_words() {
+            muted_chip(ui, &word, MuteKind::Word, word.clone());
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.add(text_edit_line!(app, app.mute_word).hint_text("whole word or phrase"))
+            .on_hover_text("Matches whole words only, in any case: muting \"cat\" hides \"Cat!\" but not \"category\"");
+        if ui.button("Mute").clicked() {
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::AddMute(
+                MuteKind::Word,
+                std::mem::take(&mut app.mute_word),
+            ));
+        }
+    });
+
+    ui.add_space(10.0);
+
+    ui.heading("Muted Hashtags");
+    ui.add_space(6.0);
+    ui.horizontal_wrapped(|ui| {
+        for hashtag in GLOBALS.mutes.get_hashtags() {
+            muted_chip(ui, &format!("#{}", hashtag), MuteKind::Hashtag, hashtag);
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.add(text_edit_line!(app, app.mute_hashtag).hint_text("#hashtag"));
+        if ui.button("Mute").clicked() {
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::AddMute(
+                MuteKind::Hashtag,
+                std::mem::take(&mut app.mute_hashtag),
+            ));
+        }
+    });
+
+    ui.add_space(10.0);
+
+    let threads = GLOBALS.mutes.get_threads();
+    ui.heading(format!("Muted Threads ({})", threads.len()));
+    ui.add_space(6.0);
+    ui.label("Mute a thread from the menu of any note in it.");
+    ui.horizontal_wrapped(|ui| {
+        for id in threads {
+            let hex = id.as_hex_string();
+            muted_chip(ui, &hex[..16], MuteKind::Thread, hex.clone());
+        }
+    });
+}
+
+fn muted_chip(ui: &mut Ui, label: &str, kind: MuteKind, value: String) {
+    ui.label(label);
+    if ui.small_button("✖").on_hover_text("Unmute").clicked() {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::DeleteMute(kind, value));
+    }
+    ui.add_space(8.0);
+}
+
+fn format_stamp(stamp: i64) -> String {
+    if let Ok(stamp) = time::OffsetDateTime::from_unix_timestamp(stamp) {
+        if let Ok(formatted) = stamp.format(time::macros::format_description!(
+            "[year]-[month repr:short]-[day] ([weekday repr:short]) [hour]:[minute]"
+        )) {
+            return formatted;
+        }
+    }
+    "unknown".to_owned()
+}
@@ -176,6 +176,38 @@ fn content(
         _ => {}
     }
 
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+    ui.heading("Lists");
+    ui.horizontal_wrapped(|ui| {
+        for name in GLOBALS.person_lists.names() {
+            let mut member = GLOBALS.person_lists.is_member(&name, &pubkeyhex);
+            if ui.checkbox(&mut member, name.as_str()).changed() {
+                let message = if member {
+                    ToOverlordMessage::AddToPersonList(name, pubkeyhex.clone())
+                } else {
+                    ToOverlordMessage::RemoveFromPersonList(name, pubkeyhex.clone())
+                };
+                let _ = GLOBALS.to_overlord.send(message);
+            }
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.add(text_edit_line!(app, app.new_person_list).hint_text("new list name"));
+        if ui.button("Create and Add").clicked() {
+            let name = std::mem::take(&mut app.new_person_list).trim().to_owned();
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::CreatePersonList(name.clone()));
+            if !name.is_empty() {
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::AddToPersonList(name, pubkeyhex.clone()));
+            }
+        }
+    });
+
     let mut need_to_set_active_person = true;
     if let Some(ap) = GLOBALS.people.get_active_person() {
         if ap == pubkeyhex {
@@ -1,9 +1,9 @@
-use super::GossipUi;
+use super::{GossipUi, Page};
 use crate::comms::ToOverlordMessage;
 use crate::db::DbRelay;
 use crate::globals::GLOBALS;
 use eframe::egui;
-use egui::{Align, Context, Layout, Ui};
+use egui::{Align, Context, Layout, RichText, Ui};
 use egui_extras::{Column, TableBuilder};
 use nostr_types::{RelayUrl, Unixtime};
 
@@ -44,6 +44,24 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         ui.checkbox(&mut app.show_hidden_relays, "Show hidden relays");
     });
 
+    if let Some(republish) = GLOBALS.republish.read().as_ref() {
+        ui.add_space(10.0);
+        ui.label(format!(
+            "{} {}: sent {} of {}, {} accepted, {} rejected, {} unanswered",
+            if republish.done {
+                "Republished to"
+            } else {
+                "Republishing to"
+            },
+            republish.relay,
+            republish.sent,
+            republish.total,
+            republish.accepted,
+            republish.rejected,
+            republish.unanswered
+        ));
+    }
+
     ui.add_space(10.0);
     ui.separator();
     ui.add_space(10.0);
@@ -54,7 +72,9 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         .all_relays
         .iter()
         .map(|ri| ri.value().clone())
-        .filter(|ri| app.show_hidden_relays || !ri.hidden)
+        .filter(|ri| {
+            app.show_hidden_relays || !ri.hidden || app.relay_highlight.as_ref() == Some(&ri.url)
+        })
         .collect();
     relays.sort_by(|a, b| {
         b.has_usage_bits(DbRelay::WRITE)
@@ -62,17 +82,33 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
             .then(a.url.cmp(&b.url))
     });
 
+    // A relay we were sent here to look at goes first
+    if let Some(ref url) = app.relay_highlight {
+        if let Some(pos) = relays.iter().position(|r| r.url == *url) {
+            let relay = relays.remove(pos);
+            relays.insert(0, relay);
+        }
+    }
+
     ui.with_layout(Layout::bottom_up(Align::Center), |ui| {
         ui.add_space(18.0);
 
         ui.with_layout(Layout::top_down(Align::Min), |ui| {
             ui.heading("All Known Relays:");
-            relay_table(ui, &mut relays, "allrelays");
+            if let Some(url) = app.relay_highlight.clone() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Showing {} first.", url));
+                    if ui.button("Back to normal order").clicked() {
+                        app.relay_highlight = None;
+                    }
+                });
+            }
+            relay_table(app, ui, &mut relays, "allrelays");
         });
     });
 }
 
-fn relay_table(ui: &mut Ui, relays: &mut [DbRelay], id: &'static str) {
+fn relay_table(app: &mut GossipUi, ui: &mut Ui, relays: &mut [DbRelay], id: &'static str) {
     ui.push_id(id, |ui| {
         TableBuilder::new(ui)
             .striped(true)
@@ -88,6 +124,8 @@ fn relay_table(ui: &mut Ui, relays: &mut [DbRelay], id: &'static str) {
             .column(Column::auto().resizable(true))
             .column(Column::auto().resizable(true))
             .column(Column::auto().resizable(true))
+            .column(Column::auto().resizable(true))
+            .column(Column::auto().resizable(true))
             .column(Column::remainder())
             .header(20.0, |mut header| {
                 header.col(|ui| {
@@ -106,6 +144,10 @@ fn relay_table(ui: &mut Ui, relays: &mut [DbRelay], id: &'static str) {
                     ui.heading("Last Event")
                         .on_hover_text("This only counts events served after EOSE, as they mark where we can pick up from next time.");
                 });
+                header.col(|ui| {
+                    ui.heading("Health")
+                        .on_hover_text("Latency, notices, rejections and disconnects over time.");
+                });
                 header.col(|ui| {
                     ui.heading("Read").on_hover_text(READ_HOVER_TEXT);
                 });
@@ -128,6 +170,10 @@ fn relay_table(ui: &mut Ui, relays: &mut [DbRelay], id: &'static str) {
                     ui.heading("Read rank")
                         .on_hover_text("How likely we will connect to relays to read other people's posts, from 0 (never) to 9 (highly). Default is 3.".to_string());
                 });
+                header.col(|ui| {
+                    ui.heading("Republish")
+                        .on_hover_text("Send your metadata, contact list, relay list and notes to this relay, for example after moving to it.".to_string());
+                });
                 header.col(|ui| {
                     ui.heading("Hide")
                         .on_hover_text("Hide this relay.".to_string());
@@ -136,7 +182,11 @@ fn relay_table(ui: &mut Ui, relays: &mut [DbRelay], id: &'static str) {
                 body.rows(24.0, relays.len(), |row_index, mut row| {
                     let relay = relays.get_mut(row_index).unwrap();
                     row.col(|ui| {
-                        crate::ui::widgets::break_anywhere_label(ui,&relay.url.0);
+                        if app.relay_highlight.as_ref() == Some(&relay.url) {
+                            ui.label(RichText::new(&relay.url.0).strong().color(app.settings.theme.warning_marker_text_color()));
+                        } else {
+                            crate::ui::widgets::break_anywhere_label(ui,&relay.url.0);
+                        }
                     });
                     row.col(|ui| {
                         ui.label(&format!("{}", relay.attempts()));
@@ -156,6 +206,12 @@ fn relay_table(ui: &mut Ui, relays: &mut [DbRelay], id: &'static str) {
                             ui.label(&ago);
                         }
                     });
+                    row.col(|ui| {
+                        if ui.button("📈").clicked() {
+                            app.relay_health_url = Some(relay.url.clone());
+                            app.set_page(Page::RelaysHealth);
+                        }
+                    });
                     row.col(|ui| {
                         let mut read = relay.has_usage_bits(DbRelay::READ); // checkbox needs a mutable state variable.
                         if ui.checkbox(&mut read, "")
@@ -237,6 +293,13 @@ fn relay_table(ui: &mut Ui, relays: &mut [DbRelay], id: &'static str) {
                             }
                         });
                     });
+                    row.col(|ui| {
+                        if ui.button("Send").clicked() {
+                            let _ = GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::RepublishHistory(relay.url.clone()));
+                        }
+                    });
                     row.col(|ui| {
                         let icon = if relay.hidden { "♻️" } else { "🗑️" };
                         if ui.button(icon).clicked() {
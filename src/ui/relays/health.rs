@@ -0,0 +1,284 @@
+use super::GossipUi;
+use crate::comms::ToOverlordMessage;
+use crate::db::{DbRelayMetric, RelayMetricKind};
+use crate::globals::GLOBALS;
+use eframe::egui;
+use egui::plot::{Bar, BarChart, Legend, Plot, PlotPoints, Points};
+use egui::{ComboBox, Context, ScrollArea, Ui};
+use humansize::{format_size, DECIMAL};
+use nostr_types::{RelayUrl, Unixtime};
+
+// How many of the latest notices, rejections and disconnects are listed
+const RECENT_PROBLEMS: usize = 50;
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.add_space(16.0);
+    ui.heading("Relay Health");
+    ui.label(
+        "How quickly a relay connects and answers, what it complains about, and why \
+         connections end. History is kept for 30 days.",
+    );
+    ui.add_space(10.0);
+
+    let mut urls: Vec<RelayUrl> = GLOBALS.all_relays.iter().map(|r| r.key().clone()).collect();
+    urls.sort();
+
+    ui.horizontal(|ui| {
+        ui.label("Relay:");
+        let selected = match app.relay_health_url {
+            Some(ref url) => url.0.clone(),
+            None => "Choose a relay".to_owned(),
+        };
+        ComboBox::from_id_source("relay_health_url")
+            .selected_text(selected)
+            .width(300.0)
+            .show_ui(ui, |ui| {
+                for url in urls.iter() {
+                    ui.selectable_value(&mut app.relay_health_url, Some(url.clone()), &url.0);
+                }
+            });
+        ui.separator();
+        ui.selectable_value(&mut app.relay_health_days, 1, "Day");
+        ui.selectable_value(&mut app.relay_health_days, 7, "Week");
+        ui.selectable_value(&mut app.relay_health_days, 30, "Month");
+        ui.separator();
+        if ui.button("Refresh").clicked() {
+            app.relay_health_requested = None;
+        }
+    });
+    ui.add_space(10.0);
+
+    let url = match app.relay_health_url {
+        Some(ref url) => url.clone(),
+        None => {
+            ui.label("Choose a relay to see its health.");
+            return;
+        }
+    };
+
+    let now = Unixtime::now().unwrap().0;
+    let since = now - app.relay_health_days * 86400;
+
+    let wanted = (url.clone(), app.relay_health_days);
+    if app.relay_health_requested.as_ref() != Some(&wanted) {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::LoadRelayMetrics(url.clone(), since));
+        app.relay_health_requested = Some(wanted);
+    }
+
+    let guard = GLOBALS.relay_metrics.read();
+    let metrics: Vec<&DbRelayMetric> = match guard.as_ref() {
+        Some((u, _, metrics)) if *u == url => metrics.iter().filter(|m| m.at >= since).collect(),
+        _ => {
+            ui.label("Loading...");
+            return;
+        }
+    };
+
+    if metrics.is_empty() {
+        ui.label("Nothing has been recorded for this relay in this period.");
+        return;
+    }
+
+    let of_kind = |kind: RelayMetricKind| -> Vec<&DbRelayMetric> {
+        metrics.iter().filter(|m| m.kind == kind).copied().collect()
+    };
+    let connects = of_kind(RelayMetricKind::ConnectLatency);
+    let eoses = of_kind(RelayMetricKind::EoseLatency);
+    let notices = of_kind(RelayMetricKind::Notice);
+    let rejections = of_kind(RelayMetricKind::Rejected);
+    let received = of_kind(RelayMetricKind::BytesReceived);
+    let disconnects = of_kind(RelayMetricKind::Disconnect);
+
+    // Charts are in hours ago for a day, and days ago otherwise
+    let (unit, unit_name, bucket) = if app.relay_health_days <= 1 {
+        (3600.0, "hours", 3600)
+    } else if app.relay_health_days <= 7 {
+        (86400.0, "days", 3600 * 6)
+    } else {
+        (86400.0, "days", 86400)
+    };
+    let x = |at: i64| (at - now) as f64 / unit;
+
+    ScrollArea::vertical()
+        .id_source("relay_health")
+        .override_scroll_delta(egui::Vec2 {
+            x: 0.0,
+            y: app.current_scroll_offset,
+        })
+        .show(ui, |ui| {
+            egui::Grid::new("relay_health_summary")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Connections:");
+                    ui.label(format!("{}", connects.len()));
+                    ui.end_row();
+
+                    ui.label("Median connect latency:");
+                    ui.label(median_ms(&connects));
+                    ui.end_row();
+
+                    ui.label("Median EOSE latency:");
+                    ui.label(format!(
+                        "{} over {} subscriptions",
+                        median_ms(&eoses),
+                        eoses.len()
+                    ));
+                    ui.end_row();
+
+                    ui.label("Notices:");
+                    ui.label(format!("{}", notices.len()));
+                    ui.end_row();
+
+                    ui.label("Rejected events:");
+                    ui.label(format!("{}", rejections.len()));
+                    ui.end_row();
+
+                    ui.label("Received:");
+                    let bytes: i64 = received.iter().map(|m| m.value).sum();
+                    ui.label(format_size(bytes as u64, DECIMAL));
+                    ui.end_row();
+
+                    ui.label("Disconnects:");
+                    ui.label(format!("{}", disconnects.len()));
+                    ui.end_row();
+                });
+
+            let mut reasons: Vec<(&str, usize)> = Vec::new();
+            for m in disconnects.iter() {
+                match reasons.iter_mut().find(|(r, _)| *r == m.detail) {
+                    Some((_, count)) => *count += 1,
+                    None => reasons.push((m.detail.as_str(), 1)),
+                }
+            }
+            if !reasons.is_empty() {
+                reasons.sort_by(|a, b| b.1.cmp(&a.1));
+                ui.add_space(10.0);
+                ui.label("Why connections ended:");
+                for (reason, count) in reasons.iter() {
+                    ui.label(format!("    {} × {}", count, reason));
+                }
+            }
+
+            ui.add_space(18.0);
+            ui.heading("Latency (ms)");
+            let connect_points = Points::new(PlotPoints::new(
+                connects.iter().map(|m| [x(m.at), m.value as f64]).collect(),
+            ))
+            .name("Connect")
+            .radius(3.0);
+            let eose_points = Points::new(PlotPoints::new(
+                eoses.iter().map(|m| [x(m.at), m.value as f64]).collect(),
+            ))
+            .name("EOSE")
+            .radius(2.0);
+            Plot::new("relay_health_latency")
+                .height(200.0)
+                .legend(Legend::default())
+                .include_y(0.0)
+                .x_axis_formatter(move |v, _| format!("{:.0} {}", v, unit_name))
+                .show(ui, |plot_ui| {
+                    plot_ui.points(connect_points);
+                    plot_ui.points(eose_points);
+                });
+
+            ui.add_space(18.0);
+            ui.heading("Received (kB)");
+            let received_chart = BarChart::new(bars(
+                &received,
+                |m| m.value as f64 / 1000.0,
+                now,
+                bucket,
+                unit,
+            ))
+            .name("Received");
+            Plot::new("relay_health_received")
+                .height(150.0)
+                .include_y(0.0)
+                .x_axis_formatter(move |v, _| format!("{:.0} {}", v, unit_name))
+                .show(ui, |plot_ui| {
+                    plot_ui.bar_chart(received_chart);
+                });
+
+            ui.add_space(18.0);
+            ui.heading("Problems");
+            let notice_chart =
+                BarChart::new(bars(&notices, |_| 1.0, now, bucket, unit)).name("Notices");
+            let rejection_chart = BarChart::new(bars(&rejections, |_| 1.0, now, bucket, unit))
+                .name("Rejections")
+                .stack_on(&[&notice_chart]);
+            let disconnect_chart = BarChart::new(bars(&disconnects, |_| 1.0, now, bucket, unit))
+                .name("Disconnects")
+                .stack_on(&[&notice_chart, &rejection_chart]);
+            Plot::new("relay_health_problems")
+                .height(150.0)
+                .legend(Legend::default())
+                .include_y(0.0)
+                .x_axis_formatter(move |v, _| format!("{:.0} {}", v, unit_name))
+                .show(ui, |plot_ui| {
+                    plot_ui.bar_chart(notice_chart);
+                    plot_ui.bar_chart(rejection_chart);
+                    plot_ui.bar_chart(disconnect_chart);
+                });
+
+            ui.add_space(18.0);
+            ui.heading("Recent problems");
+            let mut problems: Vec<&DbRelayMetric> = notices
+                .iter()
+                .chain(rejections.iter())
+                .chain(disconnects.iter())
+                .copied()
+                .collect();
+            problems.sort_by(|a, b| b.at.cmp(&a.at));
+            if problems.is_empty() {
+                ui.label("None.");
+            }
+            for m in problems.iter().take(RECENT_PROBLEMS) {
+                let what = match m.kind {
+                    RelayMetricKind::Notice => "NOTICE",
+                    RelayMetricKind::Rejected => "Rejected",
+                    _ => "Disconnected",
+                };
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(crate::date_ago::date_ago(Unixtime(m.at)));
+                    ui.label(what);
+                    ui.label(&m.detail);
+                });
+            }
+        });
+}
+
+/// The median value, in milliseconds
+fn median_ms(metrics: &[&DbRelayMetric]) -> String {
+    if metrics.is_empty() {
+        return "-".to_owned();
+    }
+    let mut values: Vec<i64> = metrics.iter().map(|m| m.value).collect();
+    values.sort_unstable();
+    format!("{} ms", values[values.len() / 2])
+}
+
+/// One bar per bucket of time, summing the given measure of the metrics in it
+fn bars(
+    metrics: &[&DbRelayMetric],
+    measure: impl Fn(&DbRelayMetric) -> f64,
+    now: i64,
+    bucket: i64,
+    unit: f64,
+) -> Vec<Bar> {
+    let mut sums: Vec<(i64, f64)> = Vec::new();
+    for m in metrics.iter() {
+        // Buckets count back from now, so the last one is never partial
+        let index = (now - m.at) / bucket;
+        match sums.iter_mut().find(|(i, _)| *i == index) {
+            Some((_, sum)) => *sum += measure(m),
+            None => sums.push((index, measure(m))),
+        }
+    }
+    let width = bucket as f64 / unit;
+    sums.iter()
+        .map(|(index, sum)| Bar::new(-(*index as f64 + 0.5) * width, *sum).width(width * 0.9))
+        .collect()
+}
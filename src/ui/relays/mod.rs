@@ -7,6 +7,9 @@ use egui_extras::{Column, TableBuilder};
 use nostr_types::RelayUrl;
 
 mod all;
+mod health;
+mod notices;
+mod outbox;
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Frame, ui: &mut Ui) {
     #[cfg(not(feature = "side-menu"))]
@@ -32,6 +35,36 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
                 app.set_page(Page::RelaysAll);
             }
             ui.separator();
+            if ui
+                .add(egui::SelectableLabel::new(
+                    app.page == Page::RelaysOutbox,
+                    "Outbox",
+                ))
+                .clicked()
+            {
+                app.set_page(Page::RelaysOutbox);
+            }
+            ui.separator();
+            if ui
+                .add(egui::SelectableLabel::new(
+                    app.page == Page::RelaysHealth,
+                    "Health",
+                ))
+                .clicked()
+            {
+                app.set_page(Page::RelaysHealth);
+            }
+            ui.separator();
+            if ui
+                .add(egui::SelectableLabel::new(
+                    app.page == Page::RelaysNotices,
+                    GossipUi::relay_notices_menu_label(),
+                ))
+                .clicked()
+            {
+                app.set_page(Page::RelaysNotices);
+            }
+            ui.separator();
         });
         ui.separator();
     }
@@ -142,5 +175,11 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut eframe::Fram
             });
     } else if app.page == Page::RelaysAll {
         all::update(app, ctx, frame, ui);
+    } else if app.page == Page::RelaysOutbox {
+        outbox::update(app, ctx, frame, ui);
+    } else if app.page == Page::RelaysHealth {
+        health::update(app, ctx, frame, ui);
+    } else if app.page == Page::RelaysNotices {
+        notices::update(app, ctx, frame, ui);
     }
 }
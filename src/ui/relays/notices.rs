@@ -0,0 +1,90 @@
+use super::{GossipUi, Page};
+use crate::globals::GLOBALS;
+use crate::relay_notices::{NoticeCategory, RelayNotice};
+use eframe::egui;
+use egui::{Context, RichText, ScrollArea, Ui};
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    // Being on this page is reading them
+    GLOBALS.relay_notices.mark_read();
+
+    ui.add_space(16.0);
+    ui.heading("Relay Notices");
+    ui.label(
+        "What relays told us this session in NOTICE messages, and when they closed \
+         a subscription. Rate limits and demands to authenticate are highlighted, as \
+         they are often why posts or feeds quietly go missing.",
+    );
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.checkbox(
+            &mut app.relay_notices_highlighted_only,
+            "Only rate limits and authentication",
+        );
+        ui.separator();
+        if ui.button("Clear").clicked() {
+            GLOBALS.relay_notices.clear();
+        }
+    });
+    ui.add_space(10.0);
+
+    let notices: Vec<RelayNotice> = GLOBALS
+        .relay_notices
+        .get()
+        .drain(..)
+        .filter(|n| !app.relay_notices_highlighted_only || n.category.highlighted())
+        .collect();
+
+    if notices.is_empty() {
+        ui.label("No notices.");
+        return;
+    }
+
+    let warning_color = app.settings.theme.warning_marker_text_color();
+
+    ScrollArea::vertical()
+        .id_source("relay_notices")
+        .override_scroll_delta(egui::Vec2 {
+            x: 0.0,
+            y: app.current_scroll_offset,
+        })
+        .show(ui, |ui| {
+            for notice in notices.iter() {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(crate::date_ago::date_ago(notice.at));
+                    if ui
+                        .link(&notice.relay.0)
+                        .on_hover_text("Show this relay in the relay list")
+                        .clicked()
+                    {
+                        app.relay_highlight = Some(notice.relay.clone());
+                        app.set_page(Page::RelaysAll);
+                    }
+                    match notice.closed {
+                        Some(ref handle) => ui.label(format!("CLOSED {}", handle)),
+                        None => ui.label("NOTICE"),
+                    };
+                    match notice.category {
+                        NoticeCategory::RateLimited => {
+                            ui.label(RichText::new("Rate limited").strong().color(warning_color));
+                        }
+                        NoticeCategory::AuthRequired => {
+                            ui.label(
+                                RichText::new("Authentication required")
+                                    .strong()
+                                    .color(warning_color),
+                            );
+                        }
+                        NoticeCategory::Other => {}
+                    }
+                });
+                if notice.category.highlighted() {
+                    ui.label(RichText::new(&notice.message).color(warning_color));
+                } else {
+                    ui.label(&notice.message);
+                }
+                ui.separator();
+            }
+        });
+}
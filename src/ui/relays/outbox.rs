@@ -0,0 +1,96 @@
+use super::GossipUi;
+use crate::comms::ToOverlordMessage;
+use crate::db::DbOutbox;
+use crate::globals::GLOBALS;
+use crate::outbox::MAX_OUTBOX_ATTEMPTS;
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use egui_extras::{Column, TableBuilder};
+use nostr_types::Unixtime;
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.add_space(16.0);
+    ui.heading("Outbox");
+    ui.label(format!(
+        "Your posts wait here until each relay takes them, or turns them down for \
+         good. They are sent again whenever gossip connects to the relay, up to {} times.",
+        MAX_OUTBOX_ATTEMPTS
+    ));
+    ui.add_space(10.0);
+
+    let queued: Vec<DbOutbox> = GLOBALS.outbox.get();
+
+    ui.horizontal(|ui| {
+        if GLOBALS.settings.read().offline {
+            ui.label(
+                RichText::new("You are in offline mode. Nothing will be sent until you go online.")
+                    .color(app.settings.theme.warning_marker_text_color()),
+            );
+        } else if !queued.is_empty() && ui.button("Send Now").clicked() {
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::FlushOutbox);
+        }
+    });
+    ui.add_space(10.0);
+
+    if queued.is_empty() {
+        ui.label("Nothing is waiting.");
+        return;
+    }
+
+    TableBuilder::new(ui)
+        .striped(true)
+        .column(Column::auto().resizable(true))
+        .column(Column::auto().resizable(true))
+        .column(Column::auto().resizable(true))
+        .column(Column::auto().resizable(true))
+        .column(Column::remainder())
+        .column(Column::auto())
+        .header(20.0, |mut header| {
+            header.col(|ui| {
+                ui.heading("Relay");
+            });
+            header.col(|ui| {
+                ui.heading("Queued");
+            });
+            header.col(|ui| {
+                ui.heading("Attempts");
+            });
+            header.col(|ui| {
+                ui.heading("Kind");
+            });
+            header.col(|ui| {
+                ui.heading("Content");
+            });
+            header.col(|_ui| {});
+        })
+        .body(|body| {
+            body.rows(24.0, queued.len(), |row_index, mut row| {
+                let q = &queued[row_index];
+                row.col(|ui| {
+                    crate::ui::widgets::break_anywhere_label(ui, &q.relay.0);
+                });
+                row.col(|ui| {
+                    ui.label(crate::date_ago::date_ago(Unixtime(q.queued_at)));
+                });
+                row.col(|ui| {
+                    ui.label(format!("{}", q.attempts));
+                });
+                row.col(|ui| {
+                    ui.label(format!("{:?}", q.event.kind));
+                });
+                row.col(|ui| {
+                    let snippet: String = q.event.content.chars().take(80).collect();
+                    ui.label(snippet.replace('\n', " "))
+                        .on_hover_text(&q.event.content);
+                });
+                row.col(|ui| {
+                    if ui.button("Drop").clicked() {
+                        let _ = GLOBALS.to_overlord.send(ToOverlordMessage::DropFromOutbox(
+                            q.event.id,
+                            q.relay.clone(),
+                        ));
+                    }
+                });
+            });
+        });
+}
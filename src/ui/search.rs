@@ -1,12 +1,14 @@
 use super::{GossipUi, Page};
-use crate::feed::FeedKind;
+use crate::comms::ToOverlordMessage;
+use crate::db::EventSearch;
+use crate::feed::{FeedKind, RelaySearch};
 use crate::GLOBALS;
 use eframe::{egui, Frame};
 use egui::widgets::Button;
-use egui::{Context, Ui};
-use nostr_types::{EventPointer, Id, PublicKey, RelayUrl};
+use egui::{Context, RichText, Ui};
+use nostr_types::{EventKind, EventPointer, Id, PublicKey, RelayUrl, Unixtime};
 
-pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut Frame, ui: &mut Ui) {
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, frame: &mut Frame, ui: &mut Ui) {
     ui.heading("Search notes and users");
 
     ui.add_space(12.0);
@@ -16,7 +18,7 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut Frame, ui:
     ui.horizontal(|ui| {
         let response = ui.add(
             text_edit_line!(app, app.search)
-                .hint_text("npub1 / note1 / nevent1.  Other kinds of searches not yet implemented")
+                .hint_text("npub1 / note1 / nevent1 / words to find in notes")
                 .desired_width(600.0),
         );
 
@@ -28,11 +30,54 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut Frame, ui:
         if ui.add(Button::new("Search")).clicked() {
             do_search = true;
         }
+        if ui
+            .add(Button::new("Search Relays"))
+            .on_hover_text("Ask relays that support NIP-50 to search for these words")
+            .clicked()
+        {
+            if app.search.trim().is_empty() {
+                app.search_result = "Enter words to search for.".to_owned();
+            } else {
+                app.search_result = "".to_owned();
+                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::SearchRelays(
+                    app.search.trim().to_owned(),
+                ));
+            }
+        }
         if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
             do_search = true;
         }
     });
 
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Author");
+        ui.add(
+            text_edit_line!(app, app.search_author)
+                .hint_text("npub1 or hex")
+                .desired_width(200.0),
+        );
+        ui.label("Kind");
+        ui.add(
+            text_edit_line!(app, app.search_kind)
+                .hint_text("1")
+                .desired_width(50.0),
+        );
+        ui.label("Hashtag");
+        ui.add(text_edit_line!(app, app.search_hashtag).desired_width(100.0));
+        ui.label("From");
+        ui.add(
+            text_edit_line!(app, app.search_since)
+                .hint_text("YYYY-MM-DD")
+                .desired_width(90.0),
+        );
+        ui.label("To");
+        ui.add(
+            text_edit_line!(app, app.search_until)
+                .hint_text("YYYY-MM-DD")
+                .desired_width(90.0),
+        );
+    });
+
     if do_search {
         search_result(app, ctx, ui);
     }
@@ -41,9 +86,72 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut Frame, ui:
     ui.separator();
     ui.add_space(12.0);
 
-    ui.horizontal_wrapped(|ui| {
-        ui.label(&app.search_result);
+    if !app.search_result.is_empty() {
+        ui.horizontal_wrapped(|ui| {
+            ui.label(&app.search_result);
+        });
+        return;
+    }
+
+    if let Some(relay_search) = GLOBALS.feed.get_relay_search() {
+        render_relay_search(app, ctx, frame, ui, relay_search);
+        return;
+    }
+
+    let feed = GLOBALS.feed.get_search_results();
+    super::feed::render_a_feed(app, ctx, frame, ui, feed, false, "search_results");
+}
+
+fn render_relay_search(
+    app: &mut GossipUi,
+    ctx: &Context,
+    frame: &mut Frame,
+    ui: &mut Ui,
+    relay_search: RelaySearch,
+) {
+    ui.horizontal(|ui| {
+        ui.heading(format!("Relay search: {}", relay_search.query));
+        if ui.button("Close").clicked() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::CloseRelaySearch);
+        }
     });
+    ui.label(
+        RichText::new(format!(
+            "{} relays supporting NIP-50 are searching. {} notes and {} people so far.",
+            relay_search.relays.len(),
+            relay_search.note_ids.len(),
+            relay_search.people.len()
+        ))
+        .weak(),
+    );
+
+    if !relay_search.people.is_empty() {
+        ui.add_space(6.0);
+        ui.horizontal_wrapped(|ui| {
+            ui.label("People:");
+            for pubkey in relay_search.people.iter() {
+                if ui
+                    .link(GossipUi::display_name_from_pubkeyhex_lookup(pubkey))
+                    .clicked()
+                {
+                    app.set_page(Page::Person(pubkey.to_owned()));
+                }
+            }
+        });
+    }
+
+    ui.add_space(6.0);
+    super::feed::render_a_feed(
+        app,
+        ctx,
+        frame,
+        ui,
+        relay_search.note_ids,
+        false,
+        "relay_search_results",
+    );
 }
 
 fn search_result(app: &mut GossipUi, _ctx: &Context, _ui: &mut Ui) {
@@ -129,9 +237,81 @@ fn search_result(app: &mut GossipUi, _ctx: &Context, _ui: &mut Ui) {
     }
 
     // Next, search the text contents of all notes in the database
-    // and display a list of them, so the user can choose.
-    // TBD.
+    match build_event_search(app) {
+        Ok(search) if search.is_empty() => {
+            app.search_result = "Enter something to search for.".to_owned();
+        }
+        Ok(search) => {
+            app.search_result = "".to_owned();
+            if GLOBALS.feed.get_relay_search().is_some() {
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::CloseRelaySearch);
+            }
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::SearchLocal(search));
+        }
+        Err(e) => app.search_result = e,
+    }
+}
 
-    // If nothing worked, let them know.
-    app.search_result = format!("No result for {}.\n\nFulltext search and nym search are not yet implemented, only note1 and npub1.", app.search.clone());
+fn build_event_search(app: &GossipUi) -> Result<EventSearch, String> {
+    let author = match app.search_author.trim() {
+        "" => None,
+        s => Some(
+            PublicKey::try_from_bech32_string(s)
+                .or_else(|_| PublicKey::try_from_hex_string(s))
+                .map_err(|_| "The author is not a valid public key.".to_owned())?
+                .into(),
+        ),
+    };
+
+    let kind = match app.search_kind.trim() {
+        "" => None,
+        s => Some(EventKind::from(
+            s.parse::<u64>()
+                .map_err(|_| "The kind must be a number.".to_owned())?,
+        )),
+    };
+
+    let hashtag = match app.search_hashtag.trim().trim_start_matches('#') {
+        "" => None,
+        s => Some(s.to_owned()),
+    };
+
+    Ok(EventSearch {
+        text: app.search.trim().to_owned(),
+        author,
+        kind,
+        hashtag,
+        since: parse_date(&app.search_since, false)?,
+        until: parse_date(&app.search_until, true)?,
+        limit: 200,
+    })
+}
+
+// Parse a YYYY-MM-DD date (UTC) into the start or the end of that day
+pub(super) fn parse_date(input: &str, end_of_day: bool) -> Result<Option<Unixtime>, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(None);
+    }
+
+    let invalid = || format!("{} is not a date like 2023-05-31.", input);
+    let parts: Vec<&str> = input.split('-').collect();
+    if parts.len() != 3 {
+        return Err(invalid());
+    }
+    let year: i32 = parts[0].parse().map_err(|_| invalid())?;
+    let month: u8 = parts[1].parse().map_err(|_| invalid())?;
+    let day: u8 = parts[2].parse().map_err(|_| invalid())?;
+    let month = time::Month::try_from(month).map_err(|_| invalid())?;
+    let date = time::Date::from_calendar_date(year, month, day).map_err(|_| invalid())?;
+
+    let mut timestamp = date.midnight().assume_utc().unix_timestamp();
+    if end_of_day {
+        timestamp += 60 * 60 * 24 - 1;
+    }
+    Ok(Some(Unixtime(timestamp)))
 }
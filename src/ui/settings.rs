@@ -1,9 +1,12 @@
 use super::{GossipUi, ThemeVariant};
 use crate::comms::ToOverlordMessage;
+use crate::db::{RetentionRule, MAX_RETENTION_DAYS};
 use crate::GLOBALS;
 use eframe::egui;
 use egui::widgets::{Button, Slider};
 use egui::{Align, Context, Layout, ScrollArea, Ui, Vec2};
+use nostr_types::{EventKind, PublicKey};
+use zeroize::Zeroize;
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
     ui.heading("Settings");
@@ -45,6 +48,19 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                         ui.add(Slider::new(&mut app.settings.max_relays, 5..=100).text("relays"));
                     });
 
+                    ui.horizontal(|ui| {
+                        ui.label("Inbox relays to post to per person: ").on_hover_text("When you reply to or mention someone, we also post to this many of the relays they read from (their best ranked ones), so that they see it.");
+                        ui.add(Slider::new(&mut app.settings.num_post_relays_per_person, 1..=4).text("relays"));
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Maximum inbox relays per post: ")
+                            .on_hover_text(
+                                "We will not post to more than this many relays besides your own write relays. Everybody tagged gets at least one of their relays first, if this allows.",
+                            );
+                        ui.add(Slider::new(&mut app.settings.max_post_relays, 1..=50).text("relays"));
+                    });
+
                     ui.add_space(12.0);
                     ui.separator();
                     ui.add_space(12.0);
@@ -99,12 +115,10 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                         "Enable reactions (show and react)",
                     );
 
-                    /*
                     ui.checkbox(
                         &mut app.settings.enable_zap_receipts,
-                        "Enable zap receipts",
-                );
-                    */
+                        "Enable zaps (show zap totals and zap notes)",
+                    );
 
                     ui.checkbox(
                         &mut app.settings.reposts,
@@ -172,6 +186,14 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                     ui.separator();
                     ui.add_space(12.0);
 
+                    ui.heading("Wallet");
+
+                    wallet(app, ui);
+
+                    ui.add_space(12.0);
+                    ui.separator();
+                    ui.add_space(12.0);
+
                     ui.heading("Network");
 
                     ui.checkbox(&mut app.settings.offline, "Offline Mode")
@@ -197,6 +219,17 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                     )
                         .on_hover_text("Takes effect on next relay connection.");
 
+                    ui.add_space(12.0);
+
+                    ui.checkbox(&mut app.settings.enable_control_api, "Enable Control API")
+                        .on_hover_text("Lets other programs on this computer control gossip over a local websocket, authenticated by the token in the control_token file in the gossip data directory. Takes effect on restart.");
+
+                    ui.horizontal(|ui| {
+                        ui.label("Control API port: ")
+                            .on_hover_text("The control API only listens on localhost (127.0.0.1). Takes effect on restart.");
+                        ui.add(Slider::new(&mut app.settings.control_api_port, 1024..=65535));
+                    });
+
                     ui.add_space(12.0);
                     ui.separator();
                     ui.add_space(12.0);
@@ -291,13 +324,51 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                     ui.separator();
                     ui.add_space(12.0);
 
-                    if ui.button("Prune Database")
-                        .on_hover_text("This will delete overridden events, events older than a week, and related data while keeping everything important. It can take MANY MINUTES to complete, and when complete there will be a status message indicating so. Also, because the database will be very busy, best not to use gossip while pruning, just wait.")
-                        .clicked() {
-                            *GLOBALS.status_message.blocking_write() = "Pruning database, please wait (this takes a long time)...".to_owned();
+                    ui.heading("Storage");
+
+                    retention(app, ui);
+
+                    ui.add_space(12.0);
+
+                    ui.horizontal(|ui| {
+                        ui.label("Prune automatically every: ")
+                            .on_hover_text("The retention policy is applied in the background this often. Zero to only prune when you ask to.");
+                        ui.add(Slider::new(&mut app.settings.prune_interval_days, 0..=90).text("days"));
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Database size budget: ")
+                            .on_hover_text("When the database grows beyond this, it is pruned in the background, keeping less and less of what the policy does not keep forever until it fits again. Zero for no budget.");
+                        ui.add(Slider::new(&mut app.settings.database_size_budget_mb, 0..=10000).text("MB"));
+                    });
+
+                    ui.add_space(12.0);
 
-                            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::PruneDatabase);
+                    ui.horizontal(|ui| {
+                        if ui.button("Preview Prune")
+                            .on_hover_text("Count what a prune under the policy above would delete, without deleting anything.")
+                            .clicked() {
+                                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::PreviewRetention(app.settings.retention.clone()));
+                            }
+
+                        if ui.button("Prune Database")
+                            .on_hover_text("This will delete overridden events, events the saved retention policy does not keep, and related data. It can take MANY MINUTES to complete, and when complete there will be a status message indicating so. Also, because the database will be very busy, best not to use gossip while pruning, just wait.")
+                            .clicked() {
+                                *GLOBALS.status_message.blocking_write() = "Pruning database, please wait (this takes a long time)...".to_owned();
+
+                                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::PruneDatabase);
+                            }
+                    });
+
+                    if let Some(preview) = GLOBALS.retention_preview.read().as_ref() {
+                        ui.label(format!(
+                            "Of {} events, {} overridden and {} expired events would be deleted.",
+                            preview.total, preview.superseded, preview.expired
+                        ));
+                        for (kind, count) in preview.expired_by_kind.iter() {
+                            ui.label(format!("    {:?} ({}): {}", EventKind::from(*kind as u64), kind, count));
                         }
+                    }
 
                     ui.add_space(12.0);
                 });
@@ -305,6 +376,125 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
     });
 }
 
+fn retention(app: &mut GossipUi, ui: &mut Ui) {
+    ui.horizontal(|ui| {
+        ui.label("Keep events for: ").on_hover_text(
+            "Events not kept by anything below are deleted when they become older than this.",
+        );
+        ui.add(Slider::new(&mut app.settings.retention.default_days, 1..=365).text("days"));
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Keep events by people you follow for: ")
+            .on_hover_text("Events by people you follow are kept at least this long. Zero to treat them like anybody else.");
+        ui.add(Slider::new(&mut app.settings.retention.followed_days, 0..=3650).text("days"));
+    });
+
+    ui.checkbox(
+        &mut app.settings.retention.keep_own,
+        "Keep your own events forever",
+    )
+    .on_hover_text("Events by any of your identities are never pruned.");
+    ui.checkbox(
+        &mut app.settings.retention.keep_dms,
+        "Keep direct messages forever",
+    );
+    ui.checkbox(
+        &mut app.settings.retention.keep_replied_threads,
+        "Keep threads you replied in forever",
+    )
+    .on_hover_text(
+        "Threads you replied in, from their first post down to every reply, are never pruned.",
+    );
+
+    ui.add_space(12.0);
+    ui.label("Rules by kind and author (the first matching rule wins):");
+
+    let mut remove: Option<usize> = None;
+    for (i, rule) in app.settings.retention.rules.iter().enumerate() {
+        ui.horizontal(|ui| {
+            let kind = match rule.kind {
+                Some(kind) => format!("{:?} ({})", EventKind::from(kind as u64), kind),
+                None => "any kind".to_owned(),
+            };
+            let author = match rule.author {
+                Some(ref pkh) => GossipUi::display_name_from_pubkeyhex_lookup(pkh),
+                None => "anybody".to_owned(),
+            };
+            let days = match rule.days {
+                Some(days) => format!("{} days", days),
+                None => "forever".to_owned(),
+            };
+            ui.label(format!("{} by {}: keep {}", kind, author, days));
+            if ui.button("Remove").clicked() {
+                remove = Some(i);
+            }
+        });
+    }
+    if let Some(i) = remove {
+        app.settings.retention.rules.remove(i);
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Kind:");
+        ui.add(text_edit_line!(app, app.retention_rule_kind).desired_width(60.0));
+        ui.label("Author:");
+        ui.add(text_edit_line!(app, app.retention_rule_author));
+        ui.label("Days:");
+        ui.add(text_edit_line!(app, app.retention_rule_days).desired_width(60.0))
+            .on_hover_text("Leave empty to keep matching events forever.");
+        if ui.button("Add Rule").clicked() {
+            match build_retention_rule(app) {
+                Ok(rule) => {
+                    app.settings.retention.rules.push(rule);
+                    app.retention_rule_kind.clear();
+                    app.retention_rule_author.clear();
+                    app.retention_rule_days.clear();
+                }
+                Err(e) => *GLOBALS.status_message.blocking_write() = e,
+            }
+        }
+    });
+}
+
+fn build_retention_rule(app: &GossipUi) -> Result<RetentionRule, String> {
+    let kind = match app.retention_rule_kind.trim() {
+        "" => None,
+        s => Some(
+            s.parse::<u32>()
+                .map_err(|_| "The kind must be a number.".to_owned())?,
+        ),
+    };
+
+    let author = match app.retention_rule_author.trim() {
+        "" => None,
+        s => Some(
+            PublicKey::try_from_bech32_string(s)
+                .or_else(|_| PublicKey::try_from_hex_string(s))
+                .map_err(|_| "The author is not a valid public key.".to_owned())?
+                .into(),
+        ),
+    };
+
+    let days = match app.retention_rule_days.trim() {
+        "" => None,
+        s => {
+            let days = s
+                .parse::<u64>()
+                .map_err(|_| "The days must be a number.".to_owned())?;
+            if days > MAX_RETENTION_DAYS {
+                return Err(format!(
+                    "The days can be at most {}. Leave it empty to keep events forever.",
+                    MAX_RETENTION_DAYS
+                ));
+            }
+            Some(days)
+        }
+    };
+
+    Ok(RetentionRule { kind, author, days })
+}
+
 fn secs_to_string(secs: u64) -> String {
     let days = secs / 86400;
     let remainder = secs % 86400;
@@ -325,3 +515,43 @@ fn secs_to_string(secs: u64) -> String {
     output.push_str(&format!(" {} seconds", seconds));
     output
 }
+
+fn wallet(app: &mut GossipUi, ui: &mut Ui) {
+    if GLOBALS.wallet.is_connected() {
+        if let Some(relay_url) = GLOBALS.wallet.relay() {
+            ui.label(format!(
+                "Paying zaps through a wallet at {}",
+                relay_url.as_str()
+            ));
+        }
+        if !GLOBALS.wallet.is_ready() {
+            ui.label("Unlock your key to use it.");
+        }
+        if ui.button("Disconnect Wallet").clicked() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::DisconnectWallet);
+        }
+        return;
+    }
+
+    ui.label("Paste a nostr+walletconnect:// string from your wallet to pay zaps with one click. It is kept encrypted with the passphrase of your key.");
+    ui.horizontal(|ui| {
+        ui.label("Connection: ");
+        ui.add(text_edit_line!(app, app.wallet_uri).password(true));
+    });
+    ui.horizontal(|ui| {
+        ui.label("Passphrase: ");
+        ui.add(text_edit_line!(app, app.password).password(true));
+    });
+    if ui.button("Connect Wallet").clicked() {
+        let _ = GLOBALS.to_overlord.send(ToOverlordMessage::ConnectWallet(
+            app.wallet_uri.clone(),
+            app.password.clone(),
+        ));
+        app.wallet_uri.zeroize();
+        app.wallet_uri = "".to_owned();
+        app.password.zeroize();
+        app.password = "".to_owned();
+    }
+}
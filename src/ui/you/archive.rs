@@ -0,0 +1,119 @@
+use super::GossipUi;
+use crate::archive::EventExport;
+use crate::comms::ToOverlordMessage;
+use crate::globals::GLOBALS;
+use eframe::egui;
+use egui::{Context, Ui};
+use nostr_types::{EventKind, PublicKey};
+use std::path::PathBuf;
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.heading("Archive");
+    ui.label(
+        "Events are written one JSON event per line. Imported events have their \
+         signatures checked and are then handled as if a relay sent them.",
+    );
+    ui.add_space(24.0);
+
+    ui.heading("Export");
+    ui.add_space(10.0);
+
+    ui.checkbox(&mut app.archive_mine, "Only events I authored");
+    if !app.archive_mine {
+        ui.horizontal(|ui| {
+            ui.label("Author:");
+            ui.add(text_edit_line!(app, app.archive_author).hint_text("npub or hex"));
+        });
+    }
+    ui.horizontal(|ui| {
+        ui.label("Kind:");
+        ui.add(text_edit_line!(app, app.archive_kind).desired_width(60.0));
+        ui.label("From:");
+        ui.add(
+            text_edit_line!(app, app.archive_since)
+                .hint_text("YYYY-MM-DD")
+                .desired_width(100.0),
+        );
+        ui.label("To:");
+        ui.add(
+            text_edit_line!(app, app.archive_until)
+                .hint_text("YYYY-MM-DD")
+                .desired_width(100.0),
+        );
+    });
+    ui.horizontal(|ui| {
+        ui.label("File:");
+        ui.add(text_edit_line!(app, app.archive_export_path).hint_text("events.jsonl"));
+        if ui.button("Export").clicked() {
+            match build_event_export(app) {
+                Ok(export) => {
+                    let path = PathBuf::from(app.archive_export_path.trim());
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::ExportEvents(export, path));
+                }
+                Err(e) => *GLOBALS.status_message.blocking_write() = e,
+            }
+        }
+    });
+
+    ui.add_space(24.0);
+
+    ui.heading("Import");
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.label("File:");
+        ui.add(text_edit_line!(app, app.archive_import_path).hint_text("events.jsonl"));
+        if ui.button("Import").clicked() {
+            if app.archive_import_path.trim().is_empty() {
+                *GLOBALS.status_message.blocking_write() = "Choose a file to import.".to_owned();
+            } else {
+                *GLOBALS.status_message.blocking_write() =
+                    "Importing events, please wait...".to_owned();
+                let path = PathBuf::from(app.archive_import_path.trim());
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::ImportEvents(path));
+            }
+        }
+    });
+}
+
+fn build_event_export(app: &GossipUi) -> Result<EventExport, String> {
+    if app.archive_export_path.trim().is_empty() {
+        return Err("Choose a file to export to.".to_owned());
+    }
+
+    let author = if app.archive_mine {
+        match GLOBALS.signer.public_key() {
+            Some(pk) => Some(pk.into()),
+            None => return Err("No identity is set up, so no events are yours.".to_owned()),
+        }
+    } else {
+        match app.archive_author.trim() {
+            "" => None,
+            s => Some(
+                PublicKey::try_from_bech32_string(s)
+                    .or_else(|_| PublicKey::try_from_hex_string(s))
+                    .map_err(|_| "The author is not a valid public key.".to_owned())?
+                    .into(),
+            ),
+        }
+    };
+
+    let kind = match app.archive_kind.trim() {
+        "" => None,
+        s => Some(EventKind::from(
+            s.parse::<u64>()
+                .map_err(|_| "The kind must be a number.".to_owned())?,
+        )),
+    };
+
+    Ok(EventExport {
+        author,
+        kind,
+        since: crate::ui::search::parse_date(&app.archive_since, false)?,
+        until: crate::ui::search::parse_date(&app.archive_until, true)?,
+    })
+}
@@ -0,0 +1,68 @@
+use super::GossipUi;
+use crate::comms::ToOverlordMessage;
+use crate::globals::GLOBALS;
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.heading("Identities");
+    ui.label(
+        "Each identity has its own keys, follows, mutes, relays and viewed flags. \
+         Events and people are shared between them.",
+    );
+    ui.add_space(24.0);
+
+    let identities = GLOBALS.identities.read().clone();
+    for identity in identities.iter() {
+        ui.horizontal(|ui| {
+            if identity.active {
+                ui.label(RichText::new(&identity.name).strong());
+            } else {
+                ui.label(&identity.name);
+            }
+
+            match identity.public_key {
+                Some(pk) => {
+                    let name = GossipUi::display_name_from_pubkeyhex_lookup(&pk.into());
+                    ui.label(RichText::new(name).weak());
+                }
+                None => {
+                    ui.label(RichText::new("(no key)").weak());
+                }
+            }
+
+            if identity.active {
+                ui.label("(active)");
+            } else {
+                if ui.button("Switch").clicked() {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::SwitchIdentity(identity.name.clone()));
+                }
+                if ui.button("Delete").clicked() {
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::DeleteIdentity(identity.name.clone()));
+                }
+            }
+        });
+        ui.add_space(4.0);
+    }
+
+    ui.add_space(10.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    ui.heading("New Identity");
+    ui.label("A new identity starts without keys or follows, and with a copy of your current relay configuration.");
+    ui.horizontal(|ui| {
+        ui.label("Name");
+        ui.add(text_edit_line!(app, app.new_identity_name));
+        if ui.button("Create").clicked() {
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::CreateIdentity(
+                app.new_identity_name.trim().to_owned(),
+            ));
+            app.new_identity_name = "".to_owned();
+        }
+    });
+}
@@ -7,10 +7,12 @@ use crate::ui::widgets::CopyButton;
 use eframe::egui;
 use egui::style::Margin;
 use egui::{Color32, Context, Frame, ScrollArea, Stroke, Ui, Vec2};
-use nostr_types::{KeySecurity, PublicKeyHex};
+use nostr_types::{KeySecurity, PublicKeyHex, RelayUrl};
 use zeroize::Zeroize;
 
+mod archive;
 mod delegation;
+mod identities;
 mod metadata;
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
@@ -59,6 +61,26 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                 app.set_page(Page::YourDelegation);
             }
             ui.separator();
+            if ui
+                .add(egui::SelectableLabel::new(
+                    app.page == Page::YourIdentities,
+                    "Identities",
+                ))
+                .clicked()
+            {
+                app.set_page(Page::YourIdentities);
+            }
+            ui.separator();
+            if ui
+                .add(egui::SelectableLabel::new(
+                    app.page == Page::YourArchive,
+                    "Archive",
+                ))
+                .clicked()
+            {
+                app.set_page(Page::YourArchive);
+            }
+            ui.separator();
         });
         ui.separator();
     }
@@ -78,7 +100,23 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                 y: app.current_scroll_offset,
             })
             .show(ui, |ui| {
-                if GLOBALS.signer.is_ready() {
+                if GLOBALS.signer.is_remote() {
+                    ui.heading("Ready to sign events (remote signer)");
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.add_space(10.0);
+
+                    show_pub_key_detail(app, ctx, ui);
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.add_space(10.0);
+
+                    show_remote_signer_detail(app, ui);
+                } else if let Some((relay_url, false)) = GLOBALS.signer.remote_signer_status() {
+                    show_remote_signer_pairing(app, ctx, ui, relay_url);
+                } else if GLOBALS.signer.is_ready() {
                     ui.heading("Ready to sign events");
 
                     ui.add_space(10.0);
@@ -151,6 +189,12 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                     ui.separator();
                     ui.add_space(10.0);
 
+                    offer_remote_signer(app, ui);
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.add_space(10.0);
+
                     offer_delete_or_import_pub_key(app, ui);
                 } else {
                     offer_generate(app, ui);
@@ -165,6 +209,12 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                     ui.separator();
                     ui.add_space(10.0);
 
+                    offer_remote_signer(app, ui);
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.add_space(10.0);
+
                     offer_delete_or_import_pub_key(app, ui);
                 }
             });
@@ -172,6 +222,10 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         metadata::update(app, ctx, _frame, ui);
     } else if app.page == Page::YourDelegation {
         delegation::update(app, ctx, _frame, ui);
+    } else if app.page == Page::YourIdentities {
+        identities::update(app, ctx, _frame, ui);
+    } else if app.page == Page::YourArchive {
+        archive::update(app, ctx, _frame, ui);
     }
 }
 
@@ -472,6 +526,95 @@ fn offer_delete_or_import_pub_key(app: &mut GossipUi, ui: &mut Ui) {
     }
 }
 
+fn offer_remote_signer(app: &mut GossipUi, ui: &mut Ui) {
+    ui.heading("Use a Remote Signer");
+
+    ui.label("Keep your private key on another device (a NIP-46 \"bunker\") and have gossip ask it to sign.");
+
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.label("Enter a bunker URI");
+        ui.add(
+            text_edit_line!(app, app.remote_signer_uri)
+                .hint_text("bunker://...")
+                .desired_width(f32::INFINITY),
+        );
+    });
+    if ui.button("Connect").clicked() {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::ConnectRemoteSigner(
+                app.remote_signer_uri.clone(),
+            ));
+        app.remote_signer_uri = "".to_owned();
+    }
+
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.label("Or, to get a nostrconnect URI for your signer, enter a relay to talk through");
+        ui.add(text_edit_line!(app, app.nostrconnect_relay).hint_text("wss://..."));
+        if ui.button("Get URI").clicked() {
+            if let Ok(url) = RelayUrl::try_from_str(&app.nostrconnect_relay) {
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::NostrConnect(url));
+                app.nostrconnect_relay = "".to_owned();
+            } else {
+                *GLOBALS.status_message.blocking_write() = "Invalid relay URL.".to_owned();
+            }
+        }
+    });
+}
+
+fn show_remote_signer_pairing(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, relay_url: RelayUrl) {
+    ui.heading("Connecting to Remote Signer");
+
+    ui.add_space(10.0);
+
+    if let Some(uri) = GLOBALS.signer.nostrconnect_uri() {
+        ui.label("Scan or paste this into your remote signer:");
+        ui.horizontal_wrapped(|ui| {
+            ui.label(&uri);
+            if ui.add(CopyButton {}).clicked() {
+                ui.output_mut(|o| o.copied_text = uri.clone());
+            }
+        });
+        ui.add_space(10.0);
+        app.render_qr(ui, ctx, "you_nostrconnect_qr", &uri);
+    } else {
+        ui.label(format!(
+            "Waiting for the remote signer to answer through {}",
+            relay_url.as_str()
+        ));
+    }
+
+    ui.add_space(10.0);
+
+    if ui.button("Cancel").clicked() {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::DisconnectRemoteSigner);
+    }
+}
+
+fn show_remote_signer_detail(_app: &mut GossipUi, ui: &mut Ui) {
+    ui.heading("Remote Signer");
+
+    if let Some((relay_url, _)) = GLOBALS.signer.remote_signer_status() {
+        ui.label(format!("Signing through {}", relay_url.as_str()));
+    }
+
+    ui.add_space(10.0);
+
+    if ui.button("Disconnect Remote Signer").clicked() {
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::DisconnectRemoteSigner);
+    }
+}
+
 fn offer_delete(app: &mut GossipUi, ui: &mut Ui) {
     ui.heading("DELETE This Identity");
 
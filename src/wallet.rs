@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::comms::{ToMinionMessage, ToMinionPayload, ToMinionPayloadDetail};
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::nip47::{Nip47Connection, Nip47Request, Nip47Response, StoredNip47Connection};
+use nostr_types::{Event, Id, PublicKey, RelayUrl, Tag};
+use parking_lot::{Mutex, RwLock};
+use tokio::sync::oneshot;
+
+/// Same work factor as for the private key
+const DEFAULT_LOG_N: u8 = 18;
+
+/// How long we wait for the wallet to pay (it may be routing a payment)
+const WALLET_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Our NIP-47 wallet connection, if we have one. Its secret is encrypted with the
+/// same passphrase as the private key, and is unlocked along with it.
+#[derive(Default)]
+pub struct Wallet {
+    stored: RwLock<Option<StoredNip47Connection>>,
+    connection: RwLock<Option<Nip47Connection>>,
+    pending: Mutex<HashMap<Id, oneshot::Sender<Nip47Response>>>,
+}
+
+impl Wallet {
+    /// Load the wallet connection from the database (again, after switching identities)
+    pub async fn load_from_settings(&self) {
+        *self.stored.write() = None;
+        *self.connection.write() = None;
+        self.pending.lock().clear();
+
+        let db = GLOBALS.db.lock().await;
+        if let Ok(json) = db.query_row(
+            "SELECT wallet_connection FROM local_settings LIMIT 1",
+            [],
+            |row| row.get::<usize, String>(0),
+        ) {
+            match serde_json::from_str::<StoredNip47Connection>(&json) {
+                Ok(stored) => *self.stored.write() = Some(stored),
+                Err(e) => tracing::error!("Wallet connection in database is invalid: {}", e),
+            }
+        }
+    }
+
+    async fn save(&self) -> Result<(), Error> {
+        let json = match &*self.stored.read() {
+            Some(stored) => Some(serde_json::to_string(stored)?),
+            None => None,
+        };
+        let db = GLOBALS.db.lock().await;
+        db.execute("UPDATE local_settings SET wallet_connection=?", (json,))?;
+        Ok(())
+    }
+
+    /// Connect a wallet from a nostr+walletconnect:// URI, encrypting its secret with
+    /// the passphrase of our private key
+    pub async fn connect(&self, uri: &str, pass: &str) -> Result<(), Error> {
+        // Test password
+        match GLOBALS.signer.encrypted_private_key() {
+            Some(epk) => {
+                let _ = epk.decrypt(pass)?;
+            }
+            None => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()),
+        }
+
+        let connection = Nip47Connection::from_uri(uri)?;
+        *self.stored.write() = Some(connection.to_stored(pass, DEFAULT_LOG_N)?);
+        *self.connection.write() = Some(connection);
+        self.save().await
+    }
+
+    pub async fn disconnect(&self) -> Result<(), Error> {
+        *self.stored.write() = None;
+        *self.connection.write() = None;
+        self.pending.lock().clear();
+        self.save().await
+    }
+
+    /// Decrypt the wallet secret, when the private key is unlocked
+    pub fn unlock(&self, pass: &str) -> Result<(), Error> {
+        if self.connection.read().is_some() {
+            return Ok(()); // already unlocked
+        }
+        let connection = match &*self.stored.read() {
+            Some(stored) => Nip47Connection::from_stored(stored, pass)?,
+            None => return Ok(()),
+        };
+        *self.connection.write() = Some(connection);
+        Ok(())
+    }
+
+    /// Re-encrypt the wallet secret, when the passphrase of the private key changes
+    pub async fn change_passphrase(&self, old: &str, new: &str) -> Result<(), Error> {
+        let stored = match &*self.stored.read() {
+            Some(stored) => {
+                Some(Nip47Connection::from_stored(stored, old)?.to_stored(new, DEFAULT_LOG_N)?)
+            }
+            None => None,
+        };
+        if stored.is_some() {
+            *self.stored.write() = stored;
+            self.save().await?;
+        }
+        Ok(())
+    }
+
+    /// Whether we have a wallet connection, even if it is still locked
+    pub fn is_connected(&self) -> bool {
+        self.stored.read().is_some()
+    }
+
+    /// Whether we can pay through the wallet right now
+    pub fn is_ready(&self) -> bool {
+        self.connection.read().is_some()
+    }
+
+    /// The relay we talk to the wallet through
+    pub fn relay(&self) -> Option<RelayUrl> {
+        self.stored.read().as_ref().and_then(|s| s.relay().ok())
+    }
+
+    /// The relay and our pubkey, for subscribing to wallet responses
+    pub fn subscription(&self) -> Option<(RelayUrl, PublicKey)> {
+        let stored = self.stored.read();
+        let stored = stored.as_ref()?;
+        Some((stored.relay().ok()?, stored.client_pubkey().ok()?))
+    }
+
+    /// Handle an incoming NIP-47 response (addressed to our wallet key)
+    pub fn handle_nip47_event(&self, event: &Event) {
+        let response = {
+            let connection = self.connection.read();
+            let connection = match &*connection {
+                Some(c) => c,
+                None => return,
+            };
+            if event.pubkey != connection.wallet_pubkey {
+                return;
+            }
+            match connection.decrypt_response(event) {
+                Ok(r) => r,
+                Err(e) => {
+                    tracing::warn!("Could not read wallet response: {}", e);
+                    return;
+                }
+            }
+        };
+
+        // It answers the request its e tag points to
+        let request_id = event.tags.iter().find_map(|tag| match tag {
+            Tag::Event { id, .. } => Some(*id),
+            _ => None,
+        });
+        if let Some(request_id) = request_id {
+            if let Some(sender) = self.pending.lock().remove(&request_id) {
+                let _ = sender.send(response);
+            }
+        }
+    }
+
+    /// Pay a lightning invoice, returning the preimage
+    pub async fn pay_invoice(&self, invoice: &str) -> Result<String, Error> {
+        let response = self.request(Nip47Request::pay_invoice(invoice)).await?;
+        response.preimage()
+    }
+
+    async fn request(&self, request: Nip47Request) -> Result<Nip47Response, Error> {
+        let (event, relay) = match &*self.connection.read() {
+            Some(c) => (c.request_event(&request)?, c.relay.clone()),
+            None => return Err("Your wallet is locked or not connected")?,
+        };
+
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().insert(event.id, sender);
+        let id = event.id;
+
+        // The minion listening to the wallet relay posts it
+        GLOBALS.to_minions.send(ToMinionMessage {
+            target: relay.0,
+            payload: ToMinionPayload {
+                job_id: 0,
+                detail: ToMinionPayloadDetail::PostEvent(Box::new(event)),
+            },
+        })?;
+
+        match tokio::time::timeout(WALLET_TIMEOUT, receiver).await {
+            Ok(Ok(response)) => Ok(response),
+            _ => {
+                self.pending.lock().remove(&id);
+                Err("The wallet did not answer")?
+            }
+        }
+    }
+}
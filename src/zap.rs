@@ -0,0 +1,924 @@
+use crate::db::{DbEvent, DbEventRelationship, DbPersonZapper, DbRelay};
+use crate::error::{Error, ErrorKind};
+use crate::fetcher::Fetcher;
+use crate::globals::{Globals, GLOBALS};
+use crate::people::DbPerson;
+use crate::relationship::Relationship;
+use bech32::{FromBase32, ToBase32};
+use nostr_types::{Event, EventKind, Id, PreEvent, PublicKey, PublicKeyHex, Tag, Unixtime};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::future::Future;
+
+/// NIP-57 zap request, which is handed to the LNURL server rather than to relays
+pub const ZAP_REQUEST_KIND: u64 = 9734;
+
+pub fn zap_request_kind() -> EventKind {
+    EventKind::from(ZAP_REQUEST_KIND)
+}
+
+/// An invoice we asked for (or are asking for) to zap a note
+#[derive(Clone, Debug)]
+pub struct ZapInvoice {
+    pub id: Id,
+    pub msats: u64,
+    /// The bolt11 invoice, once the LNURL server sent it
+    pub invoice: Option<String>,
+    /// Whether our wallet is paying it
+    pub paying: bool,
+    /// Whether our wallet paid it
+    pub paid: bool,
+}
+
+/// Where a person takes lightning payments: the LNURL-pay url to query, and
+/// that same url bech32 encoded as an lnurl
+#[derive(Clone, Debug)]
+pub struct LnurlAddress {
+    pub url: String,
+    pub lnurl: String,
+}
+
+impl LnurlAddress {
+    /// From the person's lud16 (a lightning address) or else their lud06 (an lnurl)
+    pub fn from_person(person: &DbPerson) -> Option<LnurlAddress> {
+        let md = person.metadata.as_ref()?;
+
+        if let Some(serde_json::Value::String(lud16)) = md.other.get("lud16") {
+            if let Some((name, domain)) = lud16.trim().split_once('@') {
+                if !name.is_empty() && !domain.is_empty() {
+                    let url = format!("https://{}/.well-known/lnurlp/{}", domain, name);
+                    if let Ok(lnurl) =
+                        bech32::encode("lnurl", url.as_bytes().to_base32(), bech32::Variant::Bech32)
+                    {
+                        return Some(LnurlAddress { url, lnurl });
+                    }
+                }
+            }
+        }
+
+        if let Some(serde_json::Value::String(lud06)) = md.other.get("lud06") {
+            let lnurl = lud06.trim().to_lowercase();
+            if let Ok((hrp, data, _)) = bech32::decode(&lnurl) {
+                if hrp == "lnurl" {
+                    if let Ok(bytes) = Vec::<u8>::from_base32(&data) {
+                        if let Ok(url) = String::from_utf8(bytes) {
+                            return Some(LnurlAddress { url, lnurl });
+                        }
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+// What an LNURL server says about paying to it (LUD-06, with NIP-57 additions)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PayParams {
+    callback: String,
+    min_sendable: u64,
+    max_sendable: u64,
+    #[serde(default)]
+    allows_nostr: bool,
+    /// The key zap receipts from this server are signed with (NIP-57)
+    #[serde(default)]
+    nostr_pubkey: Option<String>,
+}
+
+impl PayParams {
+    // Who signs zap receipts for payments to this server, if it takes zaps
+    fn zapper(&self) -> Option<PublicKey> {
+        if !self.allows_nostr {
+            return None;
+        }
+        PublicKey::try_from_hex_string(self.nostr_pubkey.as_ref()?).ok()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct InvoiceResponse {
+    pr: String,
+}
+
+// LNURL servers answer errors with a status and reason instead
+fn check_lnurl_error(value: &serde_json::Value) -> Result<(), Error> {
+    if value.get("status").and_then(|s| s.as_str()) == Some("ERROR") {
+        let reason = value
+            .get("reason")
+            .and_then(|r| r.as_str())
+            .unwrap_or("no reason given");
+        return Err((format!("LNURL server: {}", reason), file!(), line!()).into());
+    }
+    Ok(())
+}
+
+/// Sign a zap request for the note, to go to the LNURL server. Receipts are to be
+/// published to our read relays, where we will find them.
+pub async fn generate_zap_request(
+    id: Id,
+    pubkey: PublicKeyHex,
+    msats: u64,
+    lnurl: &str,
+    comment: String,
+) -> Result<Event, Error> {
+    let public_key = match GLOBALS.signer.public_key() {
+        Some(pk) => pk,
+        None => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()), // not even a public key
+    };
+
+    let relays: Vec<String> = GLOBALS
+        .relays_url_filtered(|r| r.has_usage_bits(DbRelay::READ))
+        .iter()
+        .map(|url| url.0.to_owned())
+        .collect();
+
+    let tags: Vec<Tag> = vec![
+        Tag::Other {
+            tag: "relays".to_owned(),
+            data: relays,
+        },
+        Tag::Other {
+            tag: "amount".to_owned(),
+            data: vec![format!("{}", msats)],
+        },
+        Tag::Other {
+            tag: "lnurl".to_owned(),
+            data: vec![lnurl.to_owned()],
+        },
+        Tag::Pubkey {
+            pubkey,
+            recommended_relay_url: None,
+            petname: None,
+        },
+        Tag::Event {
+            id,
+            recommended_relay_url: None,
+            marker: None,
+        },
+    ];
+
+    let pre_event = PreEvent {
+        pubkey: public_key,
+        created_at: Unixtime::now().unwrap(),
+        kind: zap_request_kind(),
+        tags,
+        content: comment,
+        ots: None,
+    };
+
+    GLOBALS.signer.sign_preevent(pre_event, None, None).await
+}
+
+/// Ask the author's LNURL server for an invoice zapping the note, leaving it in
+/// GLOBALS.current_zap
+pub async fn request_invoice(
+    id: Id,
+    pubkey: PublicKeyHex,
+    msats: u64,
+    comment: String,
+) -> Result<String, Error> {
+    let person = match GLOBALS.people.get(&pubkey) {
+        Some(p) => p,
+        None => DbPerson::new(pubkey.clone()),
+    };
+    let address = match LnurlAddress::from_person(&person) {
+        Some(address) => address,
+        None => return Err(("This person has no lightning address.", file!(), line!()).into()),
+    };
+
+    let params = pay_params(&address, Fetcher::get_json).await?;
+    let zapper = params.zapper();
+    remember_zapper(&pubkey, &address, zapper).await?;
+    if zapper.is_none() {
+        return Err((
+            "This person's lightning wallet does not take zaps.",
+            file!(),
+            line!(),
+        )
+            .into());
+    }
+
+    let zap_request = generate_zap_request(id, pubkey, msats, &address.lnurl, comment).await?;
+    let zap_request = serde_json::to_string(&zap_request)?;
+    let invoice = fetch_invoice(&params, &address, msats, &zap_request, Fetcher::get_json).await?;
+
+    update_current_zap(id, |zap| zap.invoice = Some(invoice.clone()));
+
+    Ok(invoice)
+}
+
+// What the LNURL server at the address says about paying to it
+async fn pay_params<F, Fut>(address: &LnurlAddress, get_json: F) -> Result<PayParams, Error>
+where
+    F: Fn(url::Url) -> Fut,
+    Fut: Future<Output = Result<serde_json::Value, Error>>,
+{
+    let url = url::Url::parse(&address.url)
+        .map_err(|e| Error::from((ErrorKind::InvalidUrl(format!("{}", e)), file!(), line!())))?;
+    let value = get_json(url).await?;
+    check_lnurl_error(&value)?;
+    Ok(serde_json::from_value(value)?)
+}
+
+// Have the LNURL server make out an invoice for the (serialized) zap request
+async fn fetch_invoice<F, Fut>(
+    params: &PayParams,
+    address: &LnurlAddress,
+    msats: u64,
+    zap_request: &str,
+    get_json: F,
+) -> Result<String, Error>
+where
+    F: Fn(url::Url) -> Fut,
+    Fut: Future<Output = Result<serde_json::Value, Error>>,
+{
+    if msats < params.min_sendable || msats > params.max_sendable {
+        return Err((
+            format!(
+                "Their wallet takes between {} and {} sats.",
+                params.min_sendable / 1000,
+                params.max_sendable / 1000
+            ),
+            file!(),
+            line!(),
+        )
+            .into());
+    }
+
+    let mut callback = url::Url::parse(&params.callback)
+        .map_err(|e| Error::from((ErrorKind::InvalidUrl(format!("{}", e)), file!(), line!())))?;
+    callback
+        .query_pairs_mut()
+        .append_pair("amount", &format!("{}", msats))
+        .append_pair("nostr", zap_request)
+        .append_pair("lnurl", &address.lnurl);
+    let value = get_json(callback).await?;
+    check_lnurl_error(&value)?;
+    let response: InvoiceResponse = serde_json::from_value(value)?;
+
+    // Only pay for exactly what we asked: the amount (LUD-06), and this zap
+    // request as the description (NIP-57)
+    let invoice = Bolt11::decode(&response.pr)?;
+    if invoice.msats != Some(msats) {
+        return Err((
+            "The invoice is not for the amount asked for.",
+            file!(),
+            line!(),
+        )
+            .into());
+    }
+    if invoice.description_hash != Some(Sha256::digest(zap_request.as_bytes()).into()) {
+        return Err(("The invoice is not for this zap.", file!(), line!()).into());
+    }
+
+    Ok(response.pr)
+}
+
+/// Pay the invoice through our NIP-47 wallet, noting progress in GLOBALS.current_zap
+pub async fn pay_with_wallet(id: Id, invoice: String) -> Result<(), Error> {
+    // Only the invoice we checked for this zap
+    let checked = GLOBALS
+        .current_zap
+        .read()
+        .as_ref()
+        .map(|zap| zap.id == id && zap.invoice.as_ref() == Some(&invoice))
+        .unwrap_or(false);
+    if !checked {
+        return Err(("That is not the invoice for this zap.", file!(), line!()).into());
+    }
+
+    update_current_zap(id, |zap| zap.paying = true);
+    let result = GLOBALS.wallet.pay_invoice(&invoice).await;
+    update_current_zap(id, |zap| {
+        zap.paying = false;
+        zap.paid = result.is_ok();
+    });
+    result.map(|_preimage| ())
+}
+
+// Unless the user moved on to zapping something else meanwhile
+fn update_current_zap<F>(id: Id, f: F)
+where
+    F: FnOnce(&mut ZapInvoice),
+{
+    if let Some(zap) = GLOBALS.current_zap.write().as_mut() {
+        if zap.id == id {
+            f(zap);
+        }
+    }
+}
+
+/// What we need of a bolt11 invoice. Its signature is for the wallet to check.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Bolt11 {
+    /// None if the payer chooses the amount
+    pub msats: Option<u64>,
+    pub description_hash: Option<[u8; 32]>,
+}
+
+impl Bolt11 {
+    pub fn decode(invoice: &str) -> Result<Bolt11, Error> {
+        let (hrp, data, variant) =
+            bech32::decode(&invoice.trim().to_lowercase()).map_err(|_| "Invalid invoice")?;
+        if variant != bech32::Variant::Bech32 {
+            return Err("Invalid invoice")?;
+        }
+
+        let msats = bolt11_amount(&hrp)?;
+
+        // A 35 bit timestamp, the tagged fields, then a 520 bit signature
+        if data.len() < 7 + 104 {
+            return Err("Invalid invoice")?;
+        }
+        let fields = &data[7..data.len() - 104];
+
+        let mut description_hash: Option<[u8; 32]> = None;
+        let mut i = 0;
+        while i + 3 <= fields.len() {
+            let tag = fields[i].to_u8();
+            let len = fields[i + 1].to_u8() as usize * 32 + fields[i + 2].to_u8() as usize;
+            let value = fields.get(i + 3..i + 3 + len).ok_or("Invalid invoice")?;
+            // 'h', the sha256 of the description
+            if tag == 23 && len == 52 {
+                let bytes = Vec::<u8>::from_base32(value).map_err(|_| "Invalid invoice")?;
+                let mut hash = [0u8; 32];
+                hash.copy_from_slice(&bytes[..32]);
+                description_hash = Some(hash);
+            }
+            i += 3 + len;
+        }
+
+        Ok(Bolt11 {
+            msats,
+            description_hash,
+        })
+    }
+}
+
+// The amount in the human readable part of an invoice, like the 2500u of lnbc2500u
+fn bolt11_amount(hrp: &str) -> Result<Option<u64>, Error> {
+    let hrp = hrp.strip_prefix("ln").ok_or("Invalid invoice")?;
+    let amount = match hrp.find(|c: char| c.is_ascii_digit()) {
+        Some(start) => &hrp[start..],
+        None => return Ok(None),
+    };
+    let (number, multiplier) = match amount.char_indices().last() {
+        Some((_, c)) if c.is_ascii_digit() => (amount, None),
+        Some((last, c)) => (&amount[..last], Some(c)),
+        None => return Ok(None),
+    };
+    let number: u64 = number.parse().map_err(|_| "Invalid invoice amount")?;
+
+    // in bitcoin, milli-, micro-, nano- and pico-bitcoin
+    let msats = match multiplier {
+        None => number.checked_mul(100_000_000_000),
+        Some('m') => number.checked_mul(100_000_000),
+        Some('u') => number.checked_mul(100_000),
+        Some('n') => number.checked_mul(100),
+        Some('p') if number % 10 == 0 => Some(number / 10),
+        _ => None,
+    };
+    match msats {
+        Some(msats) => Ok(Some(msats)),
+        None => Err("Invalid invoice amount")?,
+    }
+}
+
+/// Count a zap receipt toward the note it zaps, once it checks out. That takes
+/// knowing who signs the zapped person's receipts, which we only ask their LNURL
+/// server about once we show one of their notes (see `find_zapper`).
+pub async fn process_zap_receipt(event: &Event, from_relay: bool) -> Result<(), Error> {
+    let recipient = match event.tags.iter().find_map(|tag| match tag {
+        Tag::Pubkey { pubkey, .. } => Some(pubkey.clone()),
+        _ => None,
+    }) {
+        Some(recipient) => recipient,
+        None => return Ok(()),
+    };
+
+    if let Some(zapper) = GLOBALS.zappers.get(&recipient).map(|z| z.zapper) {
+        count_zap_receipt(event, zapper, from_relay).await?;
+    }
+
+    Ok(())
+}
+
+/// Ask the person's LNURL server who signs their zap receipts, unless we already
+/// know for their current lightning address, and then count the receipts for
+/// them we have been keeping
+pub async fn find_zapper(recipient: PublicKeyHex) -> Result<(), Error> {
+    // Without their metadata we cannot know yet
+    let person = match GLOBALS.people.get_loaded(&recipient).await? {
+        Some(person) => person,
+        None => return Ok(()),
+    };
+    let address = match LnurlAddress::from_person(&person) {
+        Some(address) => address,
+        None => return Ok(()),
+    };
+    if let Some(known) = GLOBALS.zappers.get(&recipient) {
+        if known.lnurl == address.lnurl {
+            return Ok(());
+        }
+    }
+
+    let zapper = match pay_params(&address, Fetcher::get_json).await {
+        Ok(params) => params.zapper(),
+        Err(e) => {
+            tracing::info!("Cannot check zap receipts for {}: {}", recipient, e);
+            return Ok(());
+        }
+    };
+    remember_zapper(&recipient, &address, zapper).await?;
+
+    if zapper.is_some() {
+        let criteria = format!(
+            "kind={} AND id IN (SELECT event FROM event_tag WHERE label='p' AND field0='{}')",
+            u64::from(EventKind::Zap),
+            recipient.as_str()
+        );
+        for dbevent in DbEvent::fetch(Some(&criteria)).await? {
+            let event: Event = serde_json::from_str(&dbevent.raw)?;
+            count_zap_receipt(&event, zapper, true).await?;
+        }
+    }
+
+    Ok(())
+}
+
+// Keep who signs the person's zap receipts, for as long as their lightning
+// address stays the same
+async fn remember_zapper(
+    person: &PublicKeyHex,
+    address: &LnurlAddress,
+    zapper: Option<PublicKey>,
+) -> Result<(), Error> {
+    let person_zapper = DbPersonZapper {
+        person: person.to_owned(),
+        lnurl: address.lnurl.clone(),
+        zapper,
+    };
+    person_zapper.save().await?;
+    GLOBALS.zappers.insert(person.to_owned(), person_zapper);
+    Ok(())
+}
+
+async fn count_zap_receipt(
+    event: &Event,
+    zapper: Option<PublicKey>,
+    from_relay: bool,
+) -> Result<(), Error> {
+    let (id, msats) = match zapper.and_then(|zapper| zap_receipt_amount(event, &zapper)) {
+        Some(zap) => zap,
+        None => {
+            tracing::debug!(
+                "Not counting zap receipt {}, which does not check out",
+                event.id.as_hex_string()
+            );
+            return Ok(());
+        }
+    };
+
+    if from_relay {
+        let db_event_relationship = DbEventRelationship {
+            original: event.id.as_hex_string(),
+            refers_to: id.as_hex_string(),
+            relationship: "zap".to_string(),
+            content: Some(format!("{}", msats)),
+        };
+        db_event_relationship.insert().await?;
+    }
+
+    Globals::add_relationship(id, event.id, Relationship::ZapReceipt(msats)).await;
+
+    Ok(())
+}
+
+/// The zapped note and amount of a zap receipt, in millisats, if it checks out
+/// (NIP-57 Appendix F): it is signed by the recipient's LNURL server (the zapper),
+/// and its invoice is for the zap request it describes. The amount is the one
+/// that was invoiced.
+pub fn zap_receipt_amount(event: &Event, zapper: &PublicKey) -> Option<(Id, u64)> {
+    if event.kind != EventKind::Zap || event.pubkey != *zapper {
+        return None;
+    }
+
+    let mut zapped: Option<Id> = None;
+    let mut bolt11: Option<&str> = None;
+    let mut description: Option<&str> = None;
+    for tag in event.tags.iter() {
+        match tag {
+            Tag::Event { id, .. } => zapped = Some(*id),
+            Tag::Other { tag, data } if tag == "bolt11" && !data.is_empty() => {
+                bolt11 = Some(data[0].as_str())
+            }
+            Tag::Other { tag, data } if tag == "description" && !data.is_empty() => {
+                description = Some(data[0].as_str())
+            }
+            _ => (),
+        }
+    }
+
+    let invoice = Bolt11::decode(bolt11?).ok()?;
+    let msats = invoice.msats?;
+    let description = description?;
+    if invoice.description_hash? != *Sha256::digest(description.as_bytes()).as_slice() {
+        return None;
+    }
+
+    let zap_request: Event = serde_json::from_str(description).ok()?;
+    if zap_request.kind != zap_request_kind() {
+        return None;
+    }
+    // The zap request need not name an amount, but if it does that was what to pay
+    let asked = zap_request.tags.iter().find_map(|tag| match tag {
+        Tag::Other { tag, data } if tag == "amount" && !data.is_empty() => Some(data[0].as_str()),
+        _ => None,
+    });
+    if let Some(asked) = asked {
+        if asked.parse::<u64>().ok()? != msats {
+            return None;
+        }
+    }
+
+    Some((zapped?, msats))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    // Points on the curve, for keys nobody needs to sign with
+    const RECIPIENT: &str = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+    const ZAPPER: &str = "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+    const SENDER: &str = "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9";
+    const NOTE: &str = "5c83da77af1dec6d7289834998ad7aafbd9e2191396d75ec3cc27f5a77226f36";
+
+    fn person(metadata: serde_json::Value) -> DbPerson {
+        let mut person = DbPerson::new(PublicKeyHex::try_from_str(RECIPIENT).unwrap());
+        person.metadata = Some(serde_json::from_value(metadata).unwrap());
+        person
+    }
+
+    // An invoice with a description hash, and no signature to speak of
+    fn bolt11(hrp: &str, description: &str) -> String {
+        let mut data: Vec<bech32::u5> = vec![bech32::u5::try_from_u8(0).unwrap(); 7];
+        for word in [23, 1, 20] {
+            data.push(bech32::u5::try_from_u8(word).unwrap());
+        }
+        data.extend(Sha256::digest(description.as_bytes()).to_base32());
+        data.extend(vec![bech32::u5::try_from_u8(0).unwrap(); 104]);
+        bech32::encode(hrp, data, bech32::Variant::Bech32).unwrap()
+    }
+
+    fn zap_request(amount: Option<u64>) -> String {
+        let mut tags = vec![json!(["p", RECIPIENT]), json!(["e", NOTE])];
+        if let Some(amount) = amount {
+            tags.push(json!(["amount", format!("{}", amount)]));
+        }
+        json!({
+            "id": NOTE,
+            "pubkey": SENDER,
+            "created_at": 1700000000,
+            "kind": ZAP_REQUEST_KIND,
+            "tags": tags,
+            "content": "",
+            "sig": "00".repeat(64),
+        })
+        .to_string()
+    }
+
+    fn receipt(signer: &str, invoice: &str, description: &str) -> Event {
+        serde_json::from_value(json!({
+            "id": NOTE,
+            "pubkey": signer,
+            "created_at": 1700000000,
+            "kind": 9735,
+            "tags": [
+                ["p", RECIPIENT],
+                ["e", NOTE],
+                ["bolt11", invoice],
+                ["description", description],
+            ],
+            "content": "",
+            "sig": "00".repeat(64),
+        }))
+        .unwrap()
+    }
+
+    // A stand-in LNURL server, answering for alice. It passes on the path and
+    // query of each request it gets.
+    fn lnurl_stub(invoice: String) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+        let callback = format!("{}/callback", base);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request: Vec<u8> = Vec::new();
+                let mut buffer = [0u8; 4096];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = stream.read(&mut buffer).unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buffer[..n]);
+                }
+                let request = String::from_utf8_lossy(&request);
+                let path = request.split(' ').nth(1).unwrap_or("").to_owned();
+                let body = if path == "/.well-known/lnurlp/alice" {
+                    json!({
+                        "tag": "payRequest",
+                        "callback": callback,
+                        "minSendable": 1000,
+                        "maxSendable": 100_000_000,
+                        "metadata": "[[\"text/plain\",\"alice\"]]",
+                        "allowsNostr": true,
+                        "nostrPubkey": ZAPPER,
+                    })
+                } else if path.starts_with("/callback?") {
+                    json!({ "pr": invoice, "routes": [] })
+                } else {
+                    json!({ "status": "ERROR", "reason": "No such user" })
+                };
+                let body = body.to_string();
+                let _ = sender.send(path);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+            }
+        });
+        (base, receiver)
+    }
+
+    async fn get_json(url: url::Url) -> Result<serde_json::Value, Error> {
+        Ok(reqwest::get(url).await?.json().await?)
+    }
+
+    #[test]
+    fn test_lnurl_address_from_lud16() {
+        let address =
+            LnurlAddress::from_person(&person(json!({ "lud16": " alice@example.com " }))).unwrap();
+        assert_eq!(address.url, "https://example.com/.well-known/lnurlp/alice");
+        let (hrp, data, _) = bech32::decode(&address.lnurl).unwrap();
+        assert_eq!(hrp, "lnurl");
+        assert_eq!(
+            Vec::<u8>::from_base32(&data).unwrap(),
+            address.url.as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_lnurl_address_from_lud06() {
+        let url = "https://example.com/lnurlp/bob";
+        let lnurl =
+            bech32::encode("lnurl", url.as_bytes().to_base32(), bech32::Variant::Bech32).unwrap();
+        let address =
+            LnurlAddress::from_person(&person(json!({ "lud06": lnurl.to_uppercase() }))).unwrap();
+        assert_eq!(address.url, url);
+        assert_eq!(address.lnurl, lnurl);
+
+        // A lightning address is preferred
+        let address = LnurlAddress::from_person(&person(
+            json!({ "lud06": lnurl, "lud16": "alice@example.com" }),
+        ))
+        .unwrap();
+        assert_eq!(address.url, "https://example.com/.well-known/lnurlp/alice");
+    }
+
+    #[test]
+    fn test_lnurl_address_none() {
+        let pubkey = PublicKeyHex::try_from_str(RECIPIENT).unwrap();
+        assert!(LnurlAddress::from_person(&DbPerson::new(pubkey)).is_none());
+        assert!(LnurlAddress::from_person(&person(json!({ "name": "alice" }))).is_none());
+        assert!(LnurlAddress::from_person(&person(json!({ "lud16": "alice" }))).is_none());
+        assert!(LnurlAddress::from_person(&person(json!({ "lud16": "@example.com" }))).is_none());
+        assert!(LnurlAddress::from_person(&person(json!({ "lud06": "not an lnurl" }))).is_none());
+
+        // Bech32, but not an lnurl
+        let other =
+            bech32::encode("lnbc", b"https://x".to_base32(), bech32::Variant::Bech32).unwrap();
+        assert!(LnurlAddress::from_person(&person(json!({ "lud06": other }))).is_none());
+    }
+
+    #[test]
+    fn test_check_lnurl_error() {
+        assert!(check_lnurl_error(&json!({ "callback": "https://example.com" })).is_ok());
+        assert!(check_lnurl_error(&json!({ "status": "OK" })).is_ok());
+
+        let e = check_lnurl_error(&json!({ "status": "ERROR", "reason": "Amount too small" }))
+            .unwrap_err();
+        assert!(format!("{}", e).contains("Amount too small"));
+        let e = check_lnurl_error(&json!({ "status": "ERROR" })).unwrap_err();
+        assert!(format!("{}", e).contains("no reason given"));
+    }
+
+    #[tokio::test]
+    async fn test_request_invoice() {
+        let zap_request = zap_request(Some(21_000));
+        let invoice = bolt11("lnbc210n", &zap_request);
+        let (base, requests) = lnurl_stub(invoice.clone());
+        let address = LnurlAddress {
+            url: format!("{}/.well-known/lnurlp/alice", base),
+            lnurl: "lnurl1alice".to_owned(),
+        };
+
+        let params = pay_params(&address, get_json).await.unwrap();
+        assert_eq!(params.callback, format!("{}/callback", base));
+        assert_eq!(params.min_sendable, 1000);
+        assert_eq!(params.max_sendable, 100_000_000);
+        assert_eq!(
+            params.zapper(),
+            Some(PublicKey::try_from_hex_string(ZAPPER).unwrap())
+        );
+        assert_eq!(requests.recv().unwrap(), "/.well-known/lnurlp/alice");
+
+        let pr = fetch_invoice(&params, &address, 21_000, &zap_request, get_json)
+            .await
+            .unwrap();
+        assert_eq!(pr, invoice);
+
+        // The callback got the amount, the zap request and the lnurl
+        let asked = url::Url::parse(&format!("{}{}", base, requests.recv().unwrap())).unwrap();
+        let query: Vec<(String, String)> = asked.query_pairs().into_owned().collect();
+        assert_eq!(
+            query,
+            vec![
+                ("amount".to_owned(), "21000".to_owned()),
+                ("nostr".to_owned(), zap_request.clone()),
+                ("lnurl".to_owned(), "lnurl1alice".to_owned()),
+            ]
+        );
+
+        // Amounts the server does not take are not asked for
+        assert!(
+            fetch_invoice(&params, &address, 999, &zap_request, get_json)
+                .await
+                .is_err()
+        );
+        assert!(
+            fetch_invoice(&params, &address, 100_000_001, &zap_request, get_json)
+                .await
+                .is_err()
+        );
+
+        // Errors from the server come through
+        let nobody = LnurlAddress {
+            url: format!("{}/.well-known/lnurlp/nobody", base),
+            lnurl: "lnurl1nobody".to_owned(),
+        };
+        let e = pay_params(&nobody, get_json).await.unwrap_err();
+        assert!(format!("{}", e).contains("No such user"));
+    }
+
+    #[tokio::test]
+    async fn test_request_invoice_checks_invoice() {
+        let zap_request = zap_request(Some(21_000));
+        let wrong = [
+            // Another amount
+            bolt11("lnbc10u", &zap_request),
+            // No amount
+            bolt11("lnbc", &zap_request),
+            // Another description
+            bolt11("lnbc210n", "something else"),
+            // Not an invoice
+            "lnbc210n1invalid".to_owned(),
+        ];
+        for invoice in wrong {
+            let (base, _requests) = lnurl_stub(invoice);
+            let address = LnurlAddress {
+                url: format!("{}/.well-known/lnurlp/alice", base),
+                lnurl: "lnurl1alice".to_owned(),
+            };
+            let params = pay_params(&address, get_json).await.unwrap();
+            assert!(
+                fetch_invoice(&params, &address, 21_000, &zap_request, get_json)
+                    .await
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_pay_params_zapper() {
+        let params =
+            |value: serde_json::Value| -> PayParams { serde_json::from_value(value).unwrap() };
+        let base =
+            json!({ "callback": "https://example.com/cb", "minSendable": 1, "maxSendable": 2 });
+
+        assert!(params(base.clone()).zapper().is_none());
+
+        let mut value = base.clone();
+        value["allowsNostr"] = json!(true);
+        assert!(params(value.clone()).zapper().is_none());
+        value["nostrPubkey"] = json!("not a key");
+        assert!(params(value.clone()).zapper().is_none());
+        value["nostrPubkey"] = json!(ZAPPER);
+        assert!(params(value).zapper().is_some());
+
+        let mut value = base;
+        value["nostrPubkey"] = json!(ZAPPER);
+        assert!(params(value).zapper().is_none());
+    }
+
+    #[test]
+    fn test_bolt11_amount() {
+        assert_eq!(bolt11_amount("lnbc").unwrap(), None);
+        assert_eq!(bolt11_amount("lnbc1").unwrap(), Some(100_000_000_000));
+        assert_eq!(bolt11_amount("lnbc20m").unwrap(), Some(2_000_000_000));
+        assert_eq!(bolt11_amount("lnbc2500u").unwrap(), Some(250_000_000));
+        assert_eq!(bolt11_amount("lnbc210n").unwrap(), Some(21_000));
+        assert_eq!(bolt11_amount("lntb10p").unwrap(), Some(1));
+        assert_eq!(bolt11_amount("lnbcrt5u").unwrap(), Some(500_000));
+        assert!(bolt11_amount("lnbc15p").is_err());
+        assert!(bolt11_amount("lnbc10x").is_err());
+        assert!(bolt11_amount("lnbc99999999999999999m").is_err());
+        assert!(bolt11_amount("bc10u").is_err());
+    }
+
+    #[test]
+    fn test_bolt11_decode() {
+        // From the BOLT 11 examples
+        let invoice = Bolt11::decode(
+            "lnbc20m1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqhp58yjmdan79s6qqdhdzgynm4zwqd5d7xmw5fk98klysy043l2ahrqscc6gd6ql3jrc5yzme8v4ntcewwz5cnw92tz0pc8qcuufvq7khhr8wpald05e92xw006sq94mg8v2ndf4sefvf9sygkshp5zfem29trqq2yxxz7",
+        )
+        .unwrap();
+        assert_eq!(invoice.msats, Some(2_000_000_000));
+        let description = "One piece of chocolate cake, one icecream cone, one pickle, one slice of swiss cheese, one slice of salami, one lollypop, one piece of cherry pie, one sausage, one cupcake, and one slice of watermelon";
+        assert_eq!(
+            invoice.description_hash.unwrap()[..],
+            Sha256::digest(description.as_bytes())[..]
+        );
+
+        let invoice = bolt11("lnbc210n", "a zap request");
+        assert_eq!(
+            Bolt11::decode(&invoice.to_uppercase()).unwrap(),
+            Bolt11 {
+                msats: Some(21_000),
+                description_hash: Some(Sha256::digest(b"a zap request").into()),
+            }
+        );
+
+        assert!(Bolt11::decode("lnbc210n1qqqqqq").is_err());
+        assert!(Bolt11::decode("not an invoice").is_err());
+    }
+
+    #[test]
+    fn test_zap_receipt_amount() {
+        let zapper = PublicKey::try_from_hex_string(ZAPPER).unwrap();
+        let note = Id::try_from_hex_string(NOTE).unwrap();
+        let description = zap_request(Some(21_000));
+        let invoice = bolt11("lnbc210n", &description);
+
+        assert_eq!(
+            zap_receipt_amount(&receipt(ZAPPER, &invoice, &description), &zapper),
+            Some((note, 21_000))
+        );
+
+        // Not signed by the recipient's LNURL server
+        assert_eq!(
+            zap_receipt_amount(&receipt(SENDER, &invoice, &description), &zapper),
+            None
+        );
+
+        // The invoice is not for this zap request
+        let other = zap_request(Some(1_000_000));
+        assert_eq!(
+            zap_receipt_amount(&receipt(ZAPPER, &invoice, &other), &zapper),
+            None
+        );
+
+        // The invoice is for another amount than asked for
+        let invoice = bolt11("lnbc10u", &description);
+        assert_eq!(
+            zap_receipt_amount(&receipt(ZAPPER, &invoice, &description), &zapper),
+            None
+        );
+
+        // Without an amount in the request, the invoice says how much it was
+        let description = zap_request(None);
+        let invoice = bolt11("lnbc10u", &description);
+        assert_eq!(
+            zap_receipt_amount(&receipt(ZAPPER, &invoice, &description), &zapper),
+            Some((note, 1_000_000))
+        );
+
+        // An invoice without an amount says nothing
+        let invoice = bolt11("lnbc", &description);
+        assert_eq!(
+            zap_receipt_amount(&receipt(ZAPPER, &invoice, &description), &zapper),
+            None
+        );
+    }
+}